@@ -5,14 +5,20 @@ extern crate cryptomkt;
 
 use cryptomkt::{CryptoMktClient, OrderType};
 
-const API_KEY: &'static str = "<API_KEY>";
-const API_SECRET: &'static str = "<API SECRET>";
+const API_KEY: &str = "<API_KEY>";
+const API_SECRET: &str = "<API SECRET>";
 
 fn main() {
     let client = CryptoMktClient::new(API_KEY, API_SECRET);
 
     // Get all markets available
-    let markets = client.get_markets();
+    let markets = match client.get_markets() {
+        Ok(markets) => markets,
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
     for m in markets.iter() {
         println!("{}", m.get_name());
 
@@ -1,8 +1,19 @@
+#[cfg(feature = "blocking")]
 use crate::internal::api::Api;
-use crate::internal::errors::CryptoMktErrorType;
-use crate::internal::request::CryptoMktRequest;
+#[cfg(feature = "blocking")]
+use crate::internal::errors::CryptoMktResult;
+#[cfg(feature = "blocking")]
+use crate::internal::request::{CryptoMktRequest, DynHttpRequest, HttpRequest};
+#[cfg(feature = "blocking")]
+use crate::internal::rate_limit::RateLimiter;
+#[cfg(feature = "blocking")]
+use crate::internal::retry::RetryPolicy;
+#[cfg(feature = "blocking")]
 use serde::de::DeserializeOwned;
+#[cfg(feature = "blocking")]
 use std::collections::HashMap;
+#[cfg(feature = "blocking")]
+use std::sync::Arc;
 
 ///
 /// HTTP methods supported by the API
@@ -32,12 +43,36 @@ pub enum RequestMethod {
 /// println!("API domain: {}", api.domain());
 /// ```
 ///
+#[cfg(feature = "blocking")]
 #[derive(Debug, Clone)]
 pub struct CryptoMktApi {
-    i_api: Box<Api<CryptoMktRequest>>,
+    i_api: Box<Api<DynHttpRequest>>,
 }
 
+#[cfg(feature = "blocking")]
 impl CryptoMktApi {
+    ///
+    /// Returns a builder to configure the domain, API version, default headers
+    /// and transport of the new instance
+    ///
+    /// ```
+    /// extern crate cryptomkt;
+    /// use cryptomkt::CryptoMktApi;
+    ///
+    /// let api = CryptoMktApi::builder("<API Key>", "<Secret Key>")
+    ///     .domain("http://localhost:8080/")
+    ///     .api_version("v2")
+    ///     .default_header("User-Agent", "cryptomkt-rs")
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(api.domain(), "http://localhost:8080/");
+    /// assert_eq!(api.version(), "v2");
+    /// ```
+    ///
+    pub fn builder<'a>(api_key: &'a str, secret_key: &'a str) -> CryptoMktApiBuilder {
+        CryptoMktApiBuilder::new(api_key, secret_key)
+    }
+
     ///
     /// Create the new API instance
     ///
@@ -46,12 +81,55 @@ impl CryptoMktApi {
     ///     secret_key: Secret Key as string
     ///
     pub fn new<'a>(api_key: &'a str, secret_key: &'a str) -> Self {
+        Self::with_transport(api_key, secret_key, Box::new(CryptoMktRequest::new()))
+    }
+
+    ///
+    /// Create the new API instance over a custom HTTP transport
+    ///
+    /// The transport is shared between all the clones of the instance, it can be
+    /// another HTTP stack, a recording proxy or a test double.
+    ///
+    /// ```
+    /// extern crate cryptomkt;
+    /// use cryptomkt::{CryptoMktApi, CryptoMktResult, HttpRequest};
+    /// use cryptomkt::request::{HeaderMap, Url};
+    /// use std::collections::HashMap;
+    ///
+    /// struct Offline;
+    ///
+    /// impl HttpRequest for Offline {
+    ///     type Result = CryptoMktResult<String>;
+    ///
+    ///     fn get(&self, _url: Url, _headers: HeaderMap) -> Self::Result {
+    ///         Ok("{\"status\": \"success\", \"data\": [\"ETHCLP\"]}".to_string())
+    ///     }
+    ///
+    ///     fn post(
+    ///         &self,
+    ///         _url: Url,
+    ///         _headers: HeaderMap,
+    ///         _payload: HashMap<String, String>,
+    ///     ) -> Self::Result {
+    ///         Ok("{\"status\": \"success\", \"data\": \"\"}".to_string())
+    ///     }
+    /// }
+    ///
+    /// let api = CryptoMktApi::with_transport("<API Key>", "<Secret Key>", Box::new(Offline));
+    /// ```
+    ///
+    /// Arguments:
+    ///     api_key: API Key as string
+    ///     secret_key: Secret Key as string
+    ///     transport: HTTP transport used for the GET and POST requests
+    ///
+    pub fn with_transport<'a, R>(api_key: &'a str, secret_key: &'a str, transport: Box<R>) -> Self
+    where
+        R: HttpRequest<Result = CryptoMktResult<String>> + Send + Sync + 'static,
+    {
+        let transport: Box<DynHttpRequest> = transport;
         CryptoMktApi {
-            i_api: Box::new(Api::<CryptoMktRequest>::new(
-                api_key,
-                secret_key,
-                Box::new(CryptoMktRequest::new()),
-            )),
+            i_api: Box::new(Api::<DynHttpRequest>::new(api_key, secret_key, transport)),
         }
     }
 
@@ -69,6 +147,14 @@ impl CryptoMktApi {
         self.i_api.api_version()
     }
 
+    ///
+    /// Get the rate limiter shared by the clones of this instance, use it
+    /// to monitor the current utilisation
+    ///
+    pub fn rate_limiter(&self) -> Option<Arc<RateLimiter>> {
+        self.i_api.rate_limiter()
+    }
+
     ///
     /// Function that you can use to make request over Cryptomarket API
     ///
@@ -99,12 +185,12 @@ impl CryptoMktApi {
     ///     `endpoint`: Endpoint
     ///     `payload`: Payload
     ///
-    pub fn call<'a, T>(
+    pub fn call<T>(
         &self,
         method: RequestMethod,
-        endpoint: &'a str,
+        endpoint: &str,
         payload: HashMap<String, String>,
-    ) -> Result<T, CryptoMktErrorType>
+    ) -> CryptoMktResult<T>
     where
         T: DeserializeOwned,
     {
@@ -114,3 +200,133 @@ impl CryptoMktApi {
         }
     }
 }
+
+///
+/// # Cryptomkt API Builder
+///
+/// Configures a [`CryptoMktApi`](struct.CryptoMktApi.html) before creating it, allows
+/// pointing the client to a staging host or a local mock server.
+///
+#[cfg(feature = "blocking")]
+pub struct CryptoMktApiBuilder {
+    api_key: String,
+    secret_key: String,
+    domain: Option<String>,
+    api_version: Option<String>,
+    default_headers: Vec<(String, String)>,
+    retry_policy: Option<RetryPolicy>,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    transport: Option<Box<DynHttpRequest>>,
+}
+
+#[cfg(feature = "blocking")]
+impl CryptoMktApiBuilder {
+    ///
+    /// Create the new builder
+    ///
+    /// Arguments:
+    ///     api_key: API Key as string
+    ///     secret_key: Secret Key as string
+    ///
+    pub fn new<'a>(api_key: &'a str, secret_key: &'a str) -> Self {
+        CryptoMktApiBuilder {
+            api_key: api_key.to_string(),
+            secret_key: secret_key.to_string(),
+            domain: None,
+            api_version: None,
+            default_headers: Vec::new(),
+            retry_policy: None,
+            rate_limiter: None,
+            transport: None,
+        }
+    }
+
+    ///
+    /// Set the base URL of the API (Default: https://api.cryptomkt.com/)
+    ///
+    pub fn domain(mut self, domain: &str) -> Self {
+        self.domain = Some(domain.to_string());
+        self
+    }
+
+    ///
+    /// Set the API version prefix (Default: v1), it is used to build the URL and
+    /// the signature of the private requests. An empty string removes the prefix.
+    ///
+    pub fn api_version(mut self, api_version: &str) -> Self {
+        self.api_version = Some(api_version.to_string());
+        self
+    }
+
+    ///
+    /// Add a header sent in every request
+    ///
+    pub fn default_header<'a>(mut self, name: &'a str, value: &'a str) -> Self {
+        self.default_headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    ///
+    /// Set the rate limiter shared by all the clones of the API. The same limiter
+    /// can be shared between several instances. The default transport takes a
+    /// token for every retry as well, a custom transport only for the first attempt.
+    ///
+    pub fn rate_limiter(mut self, rate_limiter: Arc<RateLimiter>) -> Self {
+        self.rate_limiter = Some(rate_limiter);
+        self
+    }
+
+    ///
+    /// Set the retry policy of the default transport (Default: no retries). It is
+    /// ignored when a custom transport is set.
+    ///
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(retry_policy);
+        self
+    }
+
+    ///
+    /// Set the HTTP transport (Default: CryptoMktRequest)
+    ///
+    pub fn transport<R>(mut self, transport: Box<R>) -> Self
+    where
+        R: HttpRequest<Result = CryptoMktResult<String>> + Send + Sync + 'static,
+    {
+        self.transport = Some(transport);
+        self
+    }
+
+    ///
+    /// Create the API instance, fails with `InvalidConfiguration` if the domain
+    /// or a default header are not valid
+    ///
+    pub fn build(self) -> CryptoMktResult<CryptoMktApi> {
+        let transport = match self.transport {
+            Some(transport) => transport,
+            None => {
+                let retry_policy = self.retry_policy.unwrap_or_else(RetryPolicy::none);
+                let mut transport = CryptoMktRequest::with_retry_policy(retry_policy);
+                if let Some(rate_limiter) = &self.rate_limiter {
+                    transport = transport.with_rate_limiter(rate_limiter.clone());
+                }
+                Box::new(transport)
+            }
+        };
+        let mut api = Api::<DynHttpRequest>::new(&self.api_key, &self.secret_key, transport);
+        if let Some(domain) = self.domain {
+            api.set_domain(&domain)?;
+        }
+        if let Some(api_version) = self.api_version {
+            api.set_api_version(&api_version);
+        }
+        for (name, value) in self.default_headers.iter() {
+            api.add_default_header(name, value)?;
+        }
+        if let Some(rate_limiter) = self.rate_limiter {
+            api.set_rate_limiter(rate_limiter);
+        }
+        Ok(CryptoMktApi {
+            i_api: Box::new(api),
+        })
+    }
+}
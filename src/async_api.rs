@@ -0,0 +1,285 @@
+use crate::api::RequestMethod;
+use crate::internal::api::Api;
+use crate::internal::errors::CryptoMktResult;
+use crate::internal::request::{AsyncHttpRequest, CryptoMktAsyncRequest, DynAsyncHttpRequest};
+use crate::internal::rate_limit::RateLimiter;
+use crate::internal::retry::RetryPolicy;
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+///
+/// # Cryptomkt Async API
+///
+/// Non-blocking version of [`CryptoMktApi`](struct.CryptoMktApi.html), every request
+/// returns a future that must be driven by a tokio runtime.
+///
+/// ## Example
+///
+/// ```
+/// extern crate cryptomkt;
+/// use cryptomkt::AsyncCryptoMktApi;
+///
+/// let api = AsyncCryptoMktApi::new("<API Key>", "<Secret Key>");
+/// println!("API version: {}", api.version());
+/// println!("API domain: {}", api.domain());
+/// ```
+///
+#[derive(Debug, Clone)]
+pub struct AsyncCryptoMktApi {
+    i_api: Box<Api<DynAsyncHttpRequest>>,
+}
+
+impl AsyncCryptoMktApi {
+    ///
+    /// Returns a builder to configure the domain, API version, default headers
+    /// and transport of the new instance
+    ///
+    /// ```
+    /// extern crate cryptomkt;
+    /// use cryptomkt::AsyncCryptoMktApi;
+    ///
+    /// let api = AsyncCryptoMktApi::builder("<API Key>", "<Secret Key>")
+    ///     .domain("http://localhost:8080/")
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(api.domain(), "http://localhost:8080/");
+    /// ```
+    ///
+    pub fn builder<'a>(api_key: &'a str, secret_key: &'a str) -> AsyncCryptoMktApiBuilder {
+        AsyncCryptoMktApiBuilder::new(api_key, secret_key)
+    }
+
+    ///
+    /// Create the new API instance
+    ///
+    /// Arguments:
+    ///     api_key: API Key as string
+    ///     secret_key: Secret Key as string
+    ///
+    pub fn new<'a>(api_key: &'a str, secret_key: &'a str) -> Self {
+        Self::with_transport(api_key, secret_key, Box::new(CryptoMktAsyncRequest::new()))
+    }
+
+    ///
+    /// Create the new API instance over a custom async HTTP transport
+    ///
+    /// The transport is shared between all the clones of the instance.
+    ///
+    /// Arguments:
+    ///     api_key: API Key as string
+    ///     secret_key: Secret Key as string
+    ///     transport: HTTP transport used for the GET and POST requests
+    ///
+    pub fn with_transport<'a, R>(api_key: &'a str, secret_key: &'a str, transport: Box<R>) -> Self
+    where
+        R: AsyncHttpRequest + Send + Sync + 'static,
+    {
+        let transport: Box<DynAsyncHttpRequest> = transport;
+        AsyncCryptoMktApi {
+            i_api: Box::new(Api::<DynAsyncHttpRequest>::new(api_key, secret_key, transport)),
+        }
+    }
+
+    ///
+    /// Get the domain
+    ///
+    pub fn domain(&self) -> String {
+        self.i_api.domain()
+    }
+
+    ///
+    /// Get the API Version
+    ///
+    pub fn version(&self) -> String {
+        self.i_api.api_version()
+    }
+
+    ///
+    /// Signed credentials of a private socket channel: the API key, the timestamp and the
+    /// signature of `timestamp + channel`
+    ///
+    pub fn sign_channel(&self, channel: &str) -> HashMap<String, String> {
+        self.i_api.sign_channel(channel)
+    }
+
+    ///
+    /// Get the rate limiter shared by the clones of this instance, use it
+    /// to monitor the current utilisation
+    ///
+    pub fn rate_limiter(&self) -> Option<Arc<RateLimiter>> {
+        self.i_api.rate_limiter()
+    }
+
+    ///
+    /// Function that you can use to make request over Cryptomarket API
+    ///
+    /// ```no_run
+    /// extern crate cryptomkt;
+    /// use cryptomkt::{AsyncCryptoMktApi, RequestMethod};
+    /// use cryptomkt::response::MarketResponse;
+    /// use std::collections::HashMap;
+    ///
+    /// async fn markets(api: &AsyncCryptoMktApi) {
+    ///     let resp = api
+    ///         .call::<MarketResponse>(RequestMethod::Get(true), "market", HashMap::new())
+    ///         .await;
+    ///     match resp {
+    ///         Ok(value) => println!("{:?}", value.data),
+    ///         Err(e) => println!("{:?}", e),
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// #Arguments
+    ///     `method`: Enum representing the server request method: Get (is_public) | Post
+    ///     `endpoint`: Endpoint
+    ///     `payload`: Payload
+    ///
+    pub async fn call<T>(
+        &self,
+        method: RequestMethod,
+        endpoint: &str,
+        payload: HashMap<String, String>,
+    ) -> CryptoMktResult<T>
+    where
+        T: DeserializeOwned,
+    {
+        match method {
+            RequestMethod::Get(is_public) => {
+                self.i_api
+                    .get_edge_async(endpoint, payload, is_public)
+                    .await
+            }
+            RequestMethod::Post => self.i_api.post_edge_async(endpoint, payload).await,
+        }
+    }
+}
+
+///
+/// # Cryptomkt Async API Builder
+///
+/// Configures an [`AsyncCryptoMktApi`](struct.AsyncCryptoMktApi.html) before creating it,
+/// allows pointing the client to a staging host or a local mock server.
+///
+pub struct AsyncCryptoMktApiBuilder {
+    api_key: String,
+    secret_key: String,
+    domain: Option<String>,
+    api_version: Option<String>,
+    default_headers: Vec<(String, String)>,
+    retry_policy: Option<RetryPolicy>,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    transport: Option<Box<DynAsyncHttpRequest>>,
+}
+
+impl AsyncCryptoMktApiBuilder {
+    ///
+    /// Create the new builder
+    ///
+    /// Arguments:
+    ///     api_key: API Key as string
+    ///     secret_key: Secret Key as string
+    ///
+    pub fn new<'a>(api_key: &'a str, secret_key: &'a str) -> Self {
+        AsyncCryptoMktApiBuilder {
+            api_key: api_key.to_string(),
+            secret_key: secret_key.to_string(),
+            domain: None,
+            api_version: None,
+            default_headers: Vec::new(),
+            retry_policy: None,
+            rate_limiter: None,
+            transport: None,
+        }
+    }
+
+    ///
+    /// Set the base URL of the API (Default: https://api.cryptomkt.com/)
+    ///
+    pub fn domain(mut self, domain: &str) -> Self {
+        self.domain = Some(domain.to_string());
+        self
+    }
+
+    ///
+    /// Set the API version prefix (Default: v1), it is used to build the URL and
+    /// the signature of the private requests. An empty string removes the prefix.
+    ///
+    pub fn api_version(mut self, api_version: &str) -> Self {
+        self.api_version = Some(api_version.to_string());
+        self
+    }
+
+    ///
+    /// Add a header sent in every request
+    ///
+    pub fn default_header<'a>(mut self, name: &'a str, value: &'a str) -> Self {
+        self.default_headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    ///
+    /// Set the rate limiter shared by all the clones of the API. The same limiter
+    /// can be shared between several instances. The default transport takes a
+    /// token for every retry as well, a custom transport only for the first attempt.
+    ///
+    pub fn rate_limiter(mut self, rate_limiter: Arc<RateLimiter>) -> Self {
+        self.rate_limiter = Some(rate_limiter);
+        self
+    }
+
+    ///
+    /// Set the retry policy of the default transport (Default: no retries). It is
+    /// ignored when a custom transport is set.
+    ///
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(retry_policy);
+        self
+    }
+
+    ///
+    /// Set the async HTTP transport (Default: CryptoMktAsyncRequest)
+    ///
+    pub fn transport<R>(mut self, transport: Box<R>) -> Self
+    where
+        R: AsyncHttpRequest + Send + Sync + 'static,
+    {
+        self.transport = Some(transport);
+        self
+    }
+
+    ///
+    /// Create the API instance, fails with `InvalidConfiguration` if the domain
+    /// or a default header are not valid
+    ///
+    pub fn build(self) -> CryptoMktResult<AsyncCryptoMktApi> {
+        let transport = match self.transport {
+            Some(transport) => transport,
+            None => {
+                let retry_policy = self.retry_policy.unwrap_or_else(RetryPolicy::none);
+                let mut transport = CryptoMktAsyncRequest::with_retry_policy(retry_policy);
+                if let Some(rate_limiter) = &self.rate_limiter {
+                    transport = transport.with_rate_limiter(rate_limiter.clone());
+                }
+                Box::new(transport)
+            }
+        };
+        let mut api = Api::<DynAsyncHttpRequest>::new(&self.api_key, &self.secret_key, transport);
+        if let Some(domain) = self.domain {
+            api.set_domain(&domain)?;
+        }
+        if let Some(api_version) = self.api_version {
+            api.set_api_version(&api_version);
+        }
+        for (name, value) in self.default_headers.iter() {
+            api.add_default_header(name, value)?;
+        }
+        if let Some(rate_limiter) = self.rate_limiter {
+            api.set_rate_limiter(rate_limiter);
+        }
+        Ok(AsyncCryptoMktApi {
+            i_api: Box::new(api),
+        })
+    }
+}
@@ -0,0 +1,390 @@
+//!
+//! ## Async Client Implementation
+//!
+//! Print the current tricker of all available markets without blocking the
+//! current thread
+//!
+//! ```no_run
+//! extern crate cryptomkt;
+//! use cryptomkt::AsyncCryptoMktClient;
+//!
+//! const API_KEY: &'static str = "<API_KEY>";
+//! const API_SECRET: &'static str = "<API SECRET>";
+//!
+//! async fn print_tickers() {
+//!     let client = AsyncCryptoMktClient::new(API_KEY, API_SECRET);
+//!     let markets = match client.get_markets().await {
+//!         Ok(markets) => markets,
+//!         Err(e) => {
+//!             println!("{}", e);
+//!             return;
+//!         }
+//!     };
+//!     for m in markets.iter() {
+//!         println!("{}", m.get_name());
+//!
+//!         // Get Current Ticker
+//!         match m.get_current_ticker().await {
+//!             Ok(ticker) => {
+//!                 println!("{:?}", ticker);
+//!             }
+//!             Err(e) => {
+//!                 println!("{:?}", e);
+//!             }
+//!         }
+//!     }
+//! }
+//! ```
+//!
+
+use crate::api::RequestMethod;
+use crate::async_api::AsyncCryptoMktApi;
+use crate::async_market::AsyncMarket;
+use crate::market::decimal_from_f32;
+
+use crate::internal::dates::{QueryDate, PAYMENT_DATE_FORMAT};
+use crate::internal::errors::CryptoMktResult;
+use crate::internal::markets::MarketRegistry;
+use crate::internal::models::{Balance, OrderType, Payment, SocketAuth};
+use crate::internal::order::CancelOutcome;
+use crate::internal::pagination::{page_stream, Page, PageOptions};
+use crate::internal::payment::PaymentOrderRequest;
+use crate::internal::request::AsyncHttpRequest;
+use crate::internal::response::{
+    BalanceResponse, MarketResponse, PaymentListResponse, PaymentResponse, SocketAuthResponse,
+};
+use futures::stream::Stream;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+///
+/// CryptoMkt Async Client
+///
+#[derive(Debug, Clone)]
+pub struct AsyncCryptoMktClient {
+    api: AsyncCryptoMktApi,
+    markets: Arc<RwLock<MarketRegistry>>,
+}
+
+impl AsyncCryptoMktClient {
+    ///
+    /// Create the new Client instance
+    ///
+    pub fn new<'a>(api_key: &'a str, secret_key: &'a str) -> Self {
+        AsyncCryptoMktClient::with_api(AsyncCryptoMktApi::new(api_key, secret_key))
+    }
+    ///
+    /// Create the new Client instance over a custom async HTTP transport
+    ///
+    /// See [`AsyncCryptoMktApi::with_transport`](struct.AsyncCryptoMktApi.html#method.with_transport)
+    ///
+    pub fn with_transport<'a, R>(api_key: &'a str, secret_key: &'a str, transport: Box<R>) -> Self
+    where
+        R: AsyncHttpRequest + Send + Sync + 'static,
+    {
+        AsyncCryptoMktClient::with_api(AsyncCryptoMktApi::with_transport(api_key, secret_key, transport))
+    }
+    ///
+    /// Create the new Client instance from a configured API, see
+    /// [`AsyncCryptoMktApi::builder`](struct.AsyncCryptoMktApi.html#method.builder)
+    ///
+    pub fn with_api(api: AsyncCryptoMktApi) -> Self {
+        AsyncCryptoMktClient {
+            api,
+            markets: Arc::new(RwLock::new(MarketRegistry::bundled())),
+        }
+    }
+    ///
+    /// API used by the client
+    ///
+    #[cfg(feature = "websocket")]
+    pub(crate) fn api(&self) -> &AsyncCryptoMktApi {
+        &self.api
+    }
+    ///
+    /// Replace the market registry used to round and check the orders, by default
+    /// the markets bundled with the crate
+    ///
+    pub fn with_market_registry(mut self, registry: MarketRegistry) -> Self {
+        self.markets = Arc::new(RwLock::new(registry));
+        self
+    }
+    ///
+    /// Get a copy of the market registry, it is refreshed by `get_markets`
+    ///
+    pub fn market_registry(&self) -> MarketRegistry {
+        self.markets.read().unwrap().clone()
+    }
+    ///
+    /// Get Market List
+    ///
+    /// The markets not yet known are added to the market registry
+    ///
+    pub async fn get_markets(&self) -> CryptoMktResult<Vec<AsyncMarket>> {
+        let resp = self
+            .api
+            .call::<MarketResponse>(RequestMethod::Get(true), "market", HashMap::new())
+            .await;
+        match resp {
+            Ok(value) => {
+                self.markets.write().unwrap().refresh(&value.data);
+                Ok(value.data.iter().map(|name| self.create_market(name)).collect())
+            }
+            Err(e) => Err(e),
+        }
+    }
+    ///
+    /// Return a new market from NAME, with the metadata of the market registry
+    ///
+    pub fn create_market(&self, name: &str) -> AsyncMarket {
+        let info = self.markets.read().unwrap().get_or_default(name);
+        AsyncMarket::new(self.api.clone(), name).with_market_info(info)
+    }
+
+    ///
+    /// Cancel the active orders of every market, or only the purchases or the sales
+    ///
+    /// The active orders of all the markets are listed before cancelling any of
+    /// them, so a listing error leaves every order in place. Each order gets its own
+    /// outcome, see
+    /// [`AsyncMarket::cancel_all_orders`](struct.AsyncMarket.html#method.cancel_all_orders)
+    ///
+    pub async fn cancel_all(
+        &self,
+        side: Option<OrderType>,
+    ) -> CryptoMktResult<Vec<CancelOutcome>> {
+        let mut pending = Vec::new();
+        for market in self.get_markets().await? {
+            let orders = market.active_orders(side.as_ref()).await?;
+            pending.push((market, orders));
+        }
+        let mut outcomes = Vec::new();
+        for (market, orders) in pending {
+            outcomes.extend(market.cancel_orders(orders).await);
+        }
+        Ok(outcomes)
+    }
+
+    ///
+    /// A balance corresponds to the status of your cryptocurrency and local wallets.
+    /// This state contains the available balance, account balance and corresponding wallet.
+    ///
+    pub async fn get_balance(&self) -> CryptoMktResult<Vec<Balance>> {
+        let resp = self
+            .api
+            .call::<BalanceResponse>(RequestMethod::Get(false), "balance", HashMap::new())
+            .await;
+        match resp {
+            Ok(value) => Ok(value.data),
+            Err(e) => Err(e),
+        }
+    }
+
+    ///
+    /// Credentials of the real-time socket connection, signed with the API key
+    ///
+    pub async fn get_socket_auth(&self) -> CryptoMktResult<SocketAuth> {
+        let resp = self
+            .api
+            .call::<SocketAuthResponse>(RequestMethod::Get(false), "socket/auth", HashMap::new())
+            .await;
+        match resp {
+            Ok(value) => Ok(value.data),
+            Err(e) => Err(e),
+        }
+    }
+
+    ///
+    /// It allows you to create a payment order, delivering QRs and urls to pay.
+    ///
+    /// The request is validated when it is built, see
+    /// [`PaymentOrderRequest`](struct.PaymentOrderRequest.html)
+    ///
+    pub async fn create_payment_order(
+        &self,
+        request: &PaymentOrderRequest,
+    ) -> CryptoMktResult<Payment> {
+        let resp = self
+            .api
+            .call::<PaymentResponse>(
+                RequestMethod::Post,
+                "payment/new_order",
+                request.to_params(),
+            )
+            .await;
+        match resp {
+            Ok(value) => Ok(value.data),
+            Err(e) => Err(e),
+        }
+    }
+
+    ///
+    /// Create a payment order from an `f32` amount
+    ///
+    #[deprecated(
+        since = "0.3.0",
+        note = "use `create_payment_order` with a `PaymentOrderRequest`"
+    )]
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create_payment_order_f32(
+        &self,
+        to_receive: f32,
+        to_receive_currency: &str,
+        payment_receiver: &str,
+        external_id: Option<String>,
+        callback_url: Option<String>,
+        error_url: Option<String>,
+        success_url: Option<String>,
+        refund_email: Option<String>,
+    ) -> CryptoMktResult<Payment> {
+        let mut builder = PaymentOrderRequest::builder(
+            decimal_from_f32(to_receive)?,
+            to_receive_currency,
+            payment_receiver,
+        );
+        if let Some(external_id) = external_id {
+            builder = builder.external_id(&external_id);
+        }
+        if let Some(callback_url) = callback_url {
+            builder = builder.callback_url(&callback_url);
+        }
+        if let Some(error_url) = error_url {
+            builder = builder.error_url(&error_url);
+        }
+        if let Some(success_url) = success_url {
+            builder = builder.success_url(&success_url);
+        }
+        if let Some(refund_email) = refund_email {
+            builder = builder.refund_email(&refund_email);
+        }
+        self.create_payment_order(&builder.build()?).await
+    }
+
+    ///
+    /// Returns the status of a payment order
+    ///
+    pub async fn payment_order_status(&self, id: &str) -> CryptoMktResult<Payment> {
+        let mut params = HashMap::new();
+        params.insert("id".to_string(), id.to_string());
+
+        let resp = self
+            .api
+            .call::<PaymentResponse>(RequestMethod::Get(false), "payment/status", params)
+            .await;
+
+        match resp {
+            Ok(value) => Ok(value.data),
+            Err(e) => Err(e),
+        }
+    }
+
+    ///
+    /// Returns the list of generated payment orders
+    ///
+    pub async fn get_payment_orders<S, E>(
+        &self,
+        start_date: &S,
+        end_date: &E,
+        page: Option<i32>,
+        limit: Option<i32>,
+    ) -> CryptoMktResult<Vec<Payment>>
+    where
+        S: QueryDate + ?Sized,
+        E: QueryDate + ?Sized,
+    {
+        self.get_payment_orders_page(start_date, end_date, page, limit).await.map(|page| page.items)
+    }
+
+    ///
+    /// Returns the list of generated payment orders, with the pagination metadata
+    ///
+    pub async fn get_payment_orders_page<S, E>(
+        &self,
+        start_date: &S,
+        end_date: &E,
+        page: Option<i32>,
+        limit: Option<i32>,
+    ) -> CryptoMktResult<Page<Payment>>
+    where
+        S: QueryDate + ?Sized,
+        E: QueryDate + ?Sized,
+    {
+        let mut params = HashMap::new();
+        params.insert(
+            "start_date".to_string(),
+            start_date.to_query_date(PAYMENT_DATE_FORMAT)?,
+        );
+        params.insert(
+            "end_date".to_string(),
+            end_date.to_query_date(PAYMENT_DATE_FORMAT)?,
+        );
+
+        if let Some(page) = page {
+            params.insert("page".to_string(), format!("{}", page));
+        }
+        if let Some(limit) = limit {
+            params.insert("limit".to_string(), format!("{}", limit));
+        }
+
+        let resp = self
+            .api
+            .call::<PaymentListResponse>(RequestMethod::Get(false), "payment/orders", params)
+            .await;
+
+        match resp {
+            Ok(value) => Ok(Page::from(value)),
+            Err(e) => Err(e),
+        }
+    }
+
+    ///
+    /// Returns the generated payment orders of every page, as a lazy stream
+    ///
+    pub fn payment_orders_stream<S, E>(
+        &self,
+        start_date: &S,
+        end_date: &E,
+        options: PageOptions,
+    ) -> impl Stream<Item = CryptoMktResult<Payment>>
+    where
+        S: QueryDate + ?Sized,
+        E: QueryDate + ?Sized,
+    {
+        let mut params = HashMap::new();
+        let mut error = None;
+        match (
+            start_date.to_query_date(PAYMENT_DATE_FORMAT),
+            end_date.to_query_date(PAYMENT_DATE_FORMAT),
+        ) {
+            (Ok(start_date), Ok(end_date)) => {
+                params.insert("start_date".to_string(), start_date);
+                params.insert("end_date".to_string(), end_date);
+            }
+            (Err(e), _) | (_, Err(e)) => error = Some(e),
+        }
+        let api = self.api.clone();
+        page_stream(options, move |page, limit| {
+            let api = api.clone();
+            let mut params = params.clone();
+            params.insert("page".to_string(), format!("{}", page));
+            params.insert("limit".to_string(), format!("{}", limit));
+            let error = error.take();
+            async move {
+                if let Some(e) = error {
+                    return Err(e);
+                }
+                let resp = api
+                    .call::<PaymentListResponse>(
+                        RequestMethod::Get(false),
+                        "payment/orders",
+                        params,
+                    )
+                    .await;
+                match resp {
+                    Ok(value) => Ok((value.data, value.pagination.next)),
+                    Err(e) => Err(e),
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,712 @@
+use crate::api::RequestMethod;
+use crate::async_api::AsyncCryptoMktApi;
+use crate::internal::dates::{QueryDate, TRADES_DATE_FORMAT};
+use crate::internal::errors::{CryptoMktError, CryptoMktErrorType, CryptoMktResult};
+use crate::internal::markets::MarketInfo;
+use crate::internal::models::{Book, Order, OrdersInstant, Ticker, Trade};
+use crate::internal::order::{
+    check_replace, replace_amount, CancelOutcome, OrderLimits, OrderRequest, OrderRequestBuilder,
+    ReplaceOutcome,
+};
+use crate::internal::order_book::{LevelChange, OrderBook};
+use crate::internal::pagination::{page_stream, Page, PageOptions};
+use crate::internal::slippage::{mid_price, SlippageEstimate};
+use crate::internal::response::{
+    BookResponse, EmptyResponse, OrderResponse, OrdersInstantResponse, SimpleOrderResponse,
+    TickerResponse, TradeResponse,
+};
+use crate::market::{decimal_from_f32, is_side, orders_endpoint, OrderState, OrderType};
+use futures::stream::{Stream, TryStreamExt};
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+
+///
+/// Async Market
+///
+/// Non-blocking version of [`Market`](struct.Market.html), it offers the same
+/// functionalities but every method returns a future.
+///
+#[derive(Debug, Clone)]
+pub struct AsyncMarket {
+    api: AsyncCryptoMktApi,
+    name: String,
+    info: MarketInfo,
+    limits: OrderLimits,
+}
+
+impl AsyncMarket {
+    ///
+    /// Create new instance
+    ///
+    pub fn new(api: AsyncCryptoMktApi, market_name: &str) -> Self {
+        AsyncMarket {
+            api,
+            name: market_name.to_string(),
+            info: MarketInfo::new(market_name),
+            limits: OrderLimits::default(),
+        }
+    }
+
+    ///
+    /// Get the market name (Ej ETHCLP)
+    ///
+    pub fn get_name(&self) -> String {
+        self.name.clone()
+    }
+
+    ///
+    /// Get the base currency of the market (Ej ETH in ETHCLP)
+    ///
+    pub fn get_base_currency(&self) -> String {
+        self.info.base_currency.clone()
+    }
+
+    ///
+    /// Get the quote currency of the market (Ej CLP in ETHCLP)
+    ///
+    pub fn get_quote_currency(&self) -> String {
+        self.info.quote_currency.clone()
+    }
+
+    ///
+    /// Get the metadata of the market: currencies, price tick, amount step and
+    /// minimum notional
+    ///
+    pub fn get_market_info(&self) -> MarketInfo {
+        self.info.clone()
+    }
+
+    ///
+    /// Set the metadata of the market, it replaces the order limits with the
+    /// limits of the market info
+    ///
+    pub fn with_market_info(mut self, info: MarketInfo) -> Self {
+        self.limits = info.order_limits();
+        self.info = info;
+        self
+    }
+
+    ///
+    /// Get the order limits of the market, checked before sending an order
+    ///
+    pub fn get_order_limits(&self) -> OrderLimits {
+        self.limits
+    }
+
+    ///
+    /// Set the order limits of the market (amount step, minimum amount, price
+    /// decimals and minimum notional)
+    ///
+    pub fn with_order_limits(mut self, limits: OrderLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    ///
+    /// Get the current ticker
+    ///
+    pub async fn get_current_ticker(&self) -> CryptoMktResult<Ticker> {
+        let mut params = HashMap::new();
+        params.insert("market".to_string(), self.name.clone());
+        let resp = self
+            .api
+            .call::<TickerResponse>(RequestMethod::Get(true), "ticker", params)
+            .await;
+        match resp {
+            Ok(value) => value.data.into_iter().next().ok_or_else(|| {
+                CryptoMktError::from(CryptoMktErrorType::MalformedResource)
+                    .with_message(&format!("empty ticker for market {}", self.name))
+            }),
+            Err(e) => Err(e),
+        }
+    }
+
+    ///
+    /// Get the order books
+    ///
+    pub async fn get_orders_book(
+        &self,
+        orders_type: OrderType,
+        page: u32,
+        limit: u32,
+    ) -> CryptoMktResult<Vec<Book>> {
+        self.get_orders_book_page(orders_type, page, limit).await.map(|page| page.items)
+    }
+
+    ///
+    /// Get the order books, with the pagination metadata
+    ///
+    pub async fn get_orders_book_page(
+        &self,
+        orders_type: OrderType,
+        page: u32,
+        limit: u32,
+    ) -> CryptoMktResult<Page<Book>> {
+        let mut params = HashMap::new();
+        params.insert("market".to_string(), self.name.clone());
+        params.insert("type".to_string(), orders_type.to_string());
+        params.insert("page".to_string(), format!("{}", page));
+        params.insert("limit".to_string(), format!("{}", limit));
+
+        let resp = self
+            .api
+            .call::<BookResponse>(RequestMethod::Get(true), "book", params)
+            .await;
+        match resp {
+            Ok(value) => Ok(Page::from(value)),
+            Err(e) => Err(e),
+        }
+    }
+
+    ///
+    /// Get Trades
+    ///
+    pub async fn get_trades<S, E>(
+        &self,
+        start: &S,
+        end: &E,
+        page: u32,
+        limit: u32,
+    ) -> CryptoMktResult<Vec<Trade>>
+    where
+        S: QueryDate + ?Sized,
+        E: QueryDate + ?Sized,
+    {
+        self.get_trades_page(start, end, page, limit).await.map(|page| page.items)
+    }
+
+    ///
+    /// Get Trades, with the pagination metadata
+    ///
+    pub async fn get_trades_page<S, E>(
+        &self,
+        start: &S,
+        end: &E,
+        page: u32,
+        limit: u32,
+    ) -> CryptoMktResult<Page<Trade>>
+    where
+        S: QueryDate + ?Sized,
+        E: QueryDate + ?Sized,
+    {
+        let mut params = HashMap::new();
+        params.insert("market".to_string(), self.name.clone());
+        params.insert(
+            "start".to_string(),
+            start.to_query_date(TRADES_DATE_FORMAT)?,
+        );
+        params.insert("end".to_string(), end.to_query_date(TRADES_DATE_FORMAT)?);
+        params.insert("page".to_string(), format!("{}", page));
+        params.insert("limit".to_string(), format!("{}", limit));
+
+        let resp = self
+            .api
+            .call::<TradeResponse>(RequestMethod::Get(true), "trades", params)
+            .await;
+        match resp {
+            Ok(value) => Ok(Page::from(value)),
+            Err(e) => Err(e),
+        }
+    }
+
+    ///
+    /// Get user orders by state
+    ///
+    pub async fn get_user_orders_by_state(
+        &self,
+        state: OrderState,
+        page: u32,
+        limit: u32,
+    ) -> CryptoMktResult<Vec<Order>> {
+        self.get_user_orders_by_state_page(state, page, limit).await.map(|page| page.items)
+    }
+
+    ///
+    /// Get user orders by state, with the pagination metadata
+    ///
+    pub async fn get_user_orders_by_state_page(
+        &self,
+        state: OrderState,
+        page: u32,
+        limit: u32,
+    ) -> CryptoMktResult<Page<Order>> {
+        let mut params = HashMap::new();
+        params.insert("market".to_string(), self.name.clone());
+        params.insert("page".to_string(), format!("{}", page));
+        params.insert("limit".to_string(), format!("{}", limit));
+
+        let endpoint = orders_endpoint(&state);
+
+        let resp = self
+            .api
+            .call::<OrderResponse>(RequestMethod::Get(false), &endpoint, params)
+            .await;
+        match resp {
+            Ok(value) => Ok(Page::from(value)),
+            Err(e) => Err(e),
+        }
+    }
+
+    ///
+    /// Get the order books of every page, as a lazy stream
+    ///
+    pub fn orders_book_stream(
+        &self,
+        orders_type: OrderType,
+        options: PageOptions,
+    ) -> impl Stream<Item = CryptoMktResult<Book>> {
+        let mut params = HashMap::new();
+        params.insert("market".to_string(), self.name.clone());
+        params.insert("type".to_string(), orders_type.to_string());
+        let api = self.api.clone();
+        page_stream(options, move |page, limit| {
+            let api = api.clone();
+            let mut params = params.clone();
+            params.insert("page".to_string(), format!("{}", page));
+            params.insert("limit".to_string(), format!("{}", limit));
+            async move {
+                let resp = api
+                    .call::<BookResponse>(RequestMethod::Get(true), "book", params)
+                    .await;
+                match resp {
+                    Ok(value) => Ok((value.data, value.pagination.next)),
+                    Err(e) => Err(e),
+                }
+            }
+        })
+    }
+
+    ///
+    /// Get the trades between two dates of every page, as a lazy stream
+    ///
+    pub fn trades_stream<S, E>(
+        &self,
+        start: &S,
+        end: &E,
+        options: PageOptions,
+    ) -> impl Stream<Item = CryptoMktResult<Trade>>
+    where
+        S: QueryDate + ?Sized,
+        E: QueryDate + ?Sized,
+    {
+        let mut params = HashMap::new();
+        params.insert("market".to_string(), self.name.clone());
+        let mut error = None;
+        match (
+            start.to_query_date(TRADES_DATE_FORMAT),
+            end.to_query_date(TRADES_DATE_FORMAT),
+        ) {
+            (Ok(start), Ok(end)) => {
+                params.insert("start".to_string(), start);
+                params.insert("end".to_string(), end);
+            }
+            (Err(e), _) | (_, Err(e)) => error = Some(e),
+        }
+        let api = self.api.clone();
+        page_stream(options, move |page, limit| {
+            let api = api.clone();
+            let mut params = params.clone();
+            params.insert("page".to_string(), format!("{}", page));
+            params.insert("limit".to_string(), format!("{}", limit));
+            let error = error.take();
+            async move {
+                if let Some(e) = error {
+                    return Err(e);
+                }
+                let resp = api
+                    .call::<TradeResponse>(RequestMethod::Get(true), "trades", params)
+                    .await;
+                match resp {
+                    Ok(value) => Ok((value.data, value.pagination.next)),
+                    Err(e) => Err(e),
+                }
+            }
+        })
+    }
+
+    ///
+    /// Get the user orders by state of every page, as a lazy stream
+    ///
+    pub fn user_orders_by_state_stream(
+        &self,
+        state: OrderState,
+        options: PageOptions,
+    ) -> impl Stream<Item = CryptoMktResult<Order>> {
+        let mut params = HashMap::new();
+        params.insert("market".to_string(), self.name.clone());
+        let endpoint = orders_endpoint(&state);
+        let api = self.api.clone();
+        page_stream(options, move |page, limit| {
+            let api = api.clone();
+            let endpoint = endpoint.clone();
+            let mut params = params.clone();
+            params.insert("page".to_string(), format!("{}", page));
+            params.insert("limit".to_string(), format!("{}", limit));
+            async move {
+                let resp = api
+                    .call::<OrderResponse>(RequestMethod::Get(false), &endpoint, params)
+                    .await;
+                match resp {
+                    Ok(value) => Ok((value.data, value.pagination.next)),
+                    Err(e) => Err(e),
+                }
+            }
+        })
+    }
+
+    ///
+    /// Get both sides of the order book across every page, see
+    /// [`OrderBook`](struct.OrderBook.html)
+    ///
+    pub async fn get_full_order_book(&self) -> CryptoMktResult<OrderBook> {
+        let bids: Vec<Book> = self
+            .orders_book_stream(OrderType::Buy, PageOptions::default())
+            .try_collect()
+            .await?;
+        let asks: Vec<Book> = self
+            .orders_book_stream(OrderType::Sell, PageOptions::default())
+            .try_collect()
+            .await?;
+        Ok(OrderBook::from_entries(&self.name, &bids, &asks))
+    }
+
+    ///
+    /// Fetch the full order book again and update `book`, returns the levels that changed
+    ///
+    pub async fn refresh_order_book(
+        &self,
+        book: &mut OrderBook,
+    ) -> CryptoMktResult<Vec<LevelChange>> {
+        Ok(book.refresh_from(self.get_full_order_book().await?))
+    }
+
+    ///
+    /// Start a limit order with the limits of the market, see
+    /// [`OrderRequest`](struct.OrderRequest.html)
+    ///
+    pub fn order_request(
+        &self,
+        order_type: OrderType,
+        amount: Decimal,
+        price: Decimal,
+    ) -> OrderRequestBuilder {
+        OrderRequest::builder(order_type, amount, price)
+            .market(&self.name)
+            .limits(self.limits)
+    }
+
+    ///
+    /// Create order
+    ///
+    /// The price is rounded to the price tick of the market (down for purchases, up
+    /// for sales) and the amount down to the amount step, then the order is checked
+    /// against the market limits before it is signed and sent
+    ///
+    pub async fn create_order(
+        &self,
+        order_type: OrderType,
+        amount: Decimal,
+        price: Decimal,
+    ) -> CryptoMktResult<Vec<Order>> {
+        let amount = self.info.round_amount(amount);
+        let price = self.info.round_price(price, &order_type);
+        let request = self.order_request(order_type, amount, price).build()?;
+        self.submit_order(&request).await
+    }
+
+    ///
+    /// Send a validated order
+    ///
+    pub async fn submit_order(&self, request: &OrderRequest) -> CryptoMktResult<Vec<Order>> {
+        let resp = self
+            .api
+            .call::<OrderResponse>(
+                RequestMethod::Post,
+                "orders/create",
+                request.to_params(&self.name),
+            )
+            .await;
+        match resp {
+            Ok(value) => Ok(value.data),
+            Err(e) => Err(e),
+        }
+    }
+
+    ///
+    /// Get Order status
+    ///
+    pub async fn get_order_status(&self, order_id: &str) -> CryptoMktResult<Order> {
+        let mut params = HashMap::new();
+        params.insert("id".to_string(), order_id.to_string());
+
+        let resp = self
+            .api
+            .call::<SimpleOrderResponse>(RequestMethod::Get(false), "orders/status", params)
+            .await;
+        match resp {
+            Ok(value) => Ok(value.data),
+            Err(e) => Err(e),
+        }
+    }
+
+    ///
+    /// Cancel Order
+    ///
+    pub async fn cancel_order(&self, order_id: &str) -> CryptoMktResult<Order> {
+        let mut params = HashMap::new();
+        params.insert("id".to_string(), order_id.to_string());
+
+        let resp = self
+            .api
+            .call::<SimpleOrderResponse>(RequestMethod::Post, "orders/cancel", params)
+            .await;
+        match resp {
+            Ok(value) => Ok(value.data),
+            Err(e) => Err(e),
+        }
+    }
+
+    ///
+    /// Cancel a limit order and place a new one at another price
+    ///
+    /// The order is cancelled and its state confirmed with `orders/status`; only when
+    /// the state is cancelled or executed the new order is placed, with the same side
+    /// and the amount not yet executed. An empty or unknown state confirms nothing.
+    /// Every step is reported in the outcome; when the cancelled order reports neither
+    /// its executed nor its remaining amount no order is placed and the placement holds
+    /// an `InvalidArgument` error. Fails without sending any request when the new price
+    /// or amount is not positive
+    ///
+    /// Arguments:
+    ///     order_id: Order to replace
+    ///     new_price: Price of the new order
+    ///     new_amount: Total amount of the new order, including the amount already
+    ///         executed by the cancelled order. `None` keeps the original amount
+    ///
+    pub async fn replace_order(
+        &self,
+        order_id: &str,
+        new_price: Decimal,
+        new_amount: Option<Decimal>,
+    ) -> CryptoMktResult<ReplaceOutcome> {
+        check_replace(new_price, new_amount)?;
+        let cancel = self.cancel_order(order_id).await;
+        let confirmation = self.get_order_status(order_id).await;
+        let mut outcome = ReplaceOutcome {
+            order_id: order_id.to_string(),
+            cancel,
+            confirmation,
+            amount: None,
+            placement: None,
+        };
+        if !outcome.is_cancelled() {
+            return Ok(outcome);
+        }
+        if let Ok(cancelled) = &outcome.confirmation {
+            let order_type = cancelled.order_type.clone();
+            match replace_amount(cancelled, new_amount) {
+                Ok(amount) => {
+                    outcome.amount = Some(amount);
+                    if amount > Decimal::ZERO {
+                        outcome.placement =
+                            Some(self.create_order(order_type, amount, new_price).await);
+                    }
+                }
+                Err(e) => outcome.placement = Some(Err(e)),
+            }
+        }
+        Ok(outcome)
+    }
+
+    ///
+    /// Cancel every active order of the market, or only the purchases or the sales
+    ///
+    /// The active orders of every page are listed before cancelling them, and each
+    /// order gets its own outcome so a failed cancellation does not stop the rest.
+    /// Fails only when the active orders cannot be listed
+    ///
+    pub async fn cancel_all_orders(
+        &self,
+        side: Option<OrderType>,
+    ) -> CryptoMktResult<Vec<CancelOutcome>> {
+        let orders = self.active_orders(side.as_ref()).await?;
+        Ok(self.cancel_orders(orders).await)
+    }
+
+    ///
+    /// Active orders of every page, of the given side
+    ///
+    pub(crate) async fn active_orders(
+        &self,
+        side: Option<&OrderType>,
+    ) -> CryptoMktResult<Vec<Order>> {
+        let orders: Vec<Order> = self
+            .user_orders_by_state_stream(OrderState::Active, PageOptions::default())
+            .try_collect()
+            .await?;
+        Ok(orders
+            .into_iter()
+            .filter(|order| is_side(order, side))
+            .collect())
+    }
+
+    ///
+    /// Cancel the orders one by one
+    ///
+    pub(crate) async fn cancel_orders(&self, orders: Vec<Order>) -> Vec<CancelOutcome> {
+        let mut outcomes = Vec::with_capacity(orders.len());
+        for order in orders {
+            outcomes.push(CancelOutcome {
+                result: self.cancel_order(&order.id).await,
+                order_id: order.id,
+                market: self.name.clone(),
+            });
+        }
+        outcomes
+    }
+
+    ///
+    /// Get order instant
+    ///
+    /// An instant order corresponds to a purchase or sale request within the
+    /// Instant Exchange of CryptoMarket.
+    ///
+    pub async fn get_order_instant(
+        &self,
+        order_type: OrderType,
+        amount: Decimal,
+    ) -> CryptoMktResult<OrdersInstant> {
+        let mut params = HashMap::new();
+        params.insert("market".to_string(), self.name.clone());
+        params.insert("amount".to_string(), amount.to_string());
+        params.insert("type".to_string(), order_type.to_string());
+
+        let resp = self
+            .api
+            .call::<OrdersInstantResponse>(RequestMethod::Get(false), "orders/instant/get", params)
+            .await;
+        match resp {
+            Ok(value) => Ok(value.data),
+            Err(e) => Err(e),
+        }
+    }
+
+    ///
+    /// Create an instant order in the Instant Exchange of CryptoMarket
+    ///
+    /// The amount is rounded down: purchases are given in the quote currency and
+    /// rounded to its decimals, sales are given in the base currency and rounded to
+    /// the amount step of the market
+    ///
+    pub async fn create_order_instant(
+        &self,
+        order_type: OrderType,
+        amount: Decimal,
+    ) -> CryptoMktResult<String> {
+        let amount = self.info.instant_amount(amount, &order_type)?;
+        let mut params = HashMap::new();
+        params.insert("market".to_string(), self.name.clone());
+        params.insert("amount".to_string(), amount.to_string());
+        params.insert("type".to_string(), order_type.to_string());
+
+        let resp = self
+            .api
+            .call::<EmptyResponse>(RequestMethod::Post, "orders/instant/create", params)
+            .await;
+        match resp {
+            Ok(value) => Ok(value.data),
+            Err(e) => Err(e),
+        }
+    }
+
+    ///
+    /// Estimate the execution of an instant order from the Instant Exchange quote,
+    /// against the mid price of the current ticker
+    ///
+    /// See [`SlippageEstimate`](struct.SlippageEstimate.html), or
+    /// [`SlippageEstimate::from_order_book`](struct.SlippageEstimate.html#method.from_order_book)
+    /// to estimate it from a full order book
+    ///
+    pub async fn estimate_order_instant(
+        &self,
+        order_type: OrderType,
+        amount: Decimal,
+    ) -> CryptoMktResult<SlippageEstimate> {
+        let quote = self.get_order_instant(order_type.clone(), amount).await?;
+        let ticker = self.get_current_ticker().await?;
+        Ok(SlippageEstimate::from_quote(
+            order_type,
+            amount,
+            &quote,
+            mid_price(&ticker),
+        ))
+    }
+
+    ///
+    /// Create an instant order only if the estimated slippage versus the mid price is
+    /// at most `max_slippage` (Ej. 0.01 = 1%) and the market can fill the whole amount,
+    /// otherwise the order is not sent and a `SlippageExceeded` error is returned.
+    /// The amount is rounded as in `create_order_instant` before it is quoted
+    ///
+    pub async fn create_order_instant_with_max_slippage(
+        &self,
+        order_type: OrderType,
+        amount: Decimal,
+        max_slippage: Decimal,
+    ) -> CryptoMktResult<String> {
+        let amount = self.info.instant_amount(amount, &order_type)?;
+        let estimate = self.estimate_order_instant(order_type.clone(), amount).await?;
+        estimate.check(max_slippage)?;
+        self.create_order_instant(order_type, amount).await
+    }
+
+    ///
+    /// Create order from `f32` values
+    ///
+    #[deprecated(since = "0.3.0", note = "use `create_order` with `Decimal` values")]
+    pub async fn create_order_f32(
+        &self,
+        order_type: OrderType,
+        amount: f32,
+        price: f32,
+    ) -> CryptoMktResult<Vec<Order>> {
+        self.create_order(
+            order_type,
+            decimal_from_f32(amount)?,
+            decimal_from_f32(price)?,
+        )
+        .await
+    }
+
+    ///
+    /// Get order instant from an `f32` amount
+    ///
+    #[deprecated(
+        since = "0.3.0",
+        note = "use `get_order_instant` with a `Decimal` amount"
+    )]
+    pub async fn get_order_instant_f32(
+        &self,
+        order_type: OrderType,
+        amount: f32,
+    ) -> CryptoMktResult<OrdersInstant> {
+        self.get_order_instant(order_type, decimal_from_f32(amount)?)
+            .await
+    }
+
+    ///
+    /// Create an instant order from an `f32` amount
+    ///
+    #[deprecated(
+        since = "0.3.0",
+        note = "use `create_order_instant` with a `Decimal` amount"
+    )]
+    pub async fn create_order_instant_f32(
+        &self,
+        order_type: OrderType,
+        amount: f32,
+    ) -> CryptoMktResult<String> {
+        self.create_order_instant(order_type, decimal_from_f32(amount)?)
+            .await
+    }
+}
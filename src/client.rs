@@ -11,7 +11,13 @@
 //! const API_SECRET: &'static str = "<API SECRET>";
 //!
 //! let client = CryptoMktClient::new(API_KEY, API_SECRET);
-//! let markets = client.get_markets();
+//! let markets = match client.get_markets() {
+//!     Ok(markets) => markets,
+//!     Err(e) => {
+//!         println!("{}", e);
+//!         return;
+//!     }
+//! };
 //! for m in markets.iter() {
 //!     println!("{}", m.get_name());
 //!
@@ -29,20 +35,29 @@
 //!
 
 use crate::api::{CryptoMktApi, RequestMethod};
-use crate::market::Market;
+use crate::market::{decimal_from_f32, Market};
 
+use crate::internal::dates::{QueryDate, PAYMENT_DATE_FORMAT};
 use crate::internal::errors::CryptoMktResult;
-use crate::internal::models::{Balance, Payment};
+use crate::internal::markets::MarketRegistry;
+use crate::internal::models::{Balance, OrderType, Payment, SocketAuth};
+use crate::internal::order::CancelOutcome;
+use crate::internal::pagination::{Page, PageOptions, Paginator};
+use crate::internal::payment::PaymentOrderRequest;
+use crate::internal::request::HttpRequest;
 use crate::internal::response::{
-    BalanceResponse, MarketResponse, PaymentListResponse, PaymentResponse,
+    BalanceResponse, MarketResponse, PaymentListResponse, PaymentResponse, SocketAuthResponse,
 };
 use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
 
 ///
 /// CryptoMkt Client
 ///
+#[derive(Debug, Clone)]
 pub struct CryptoMktClient {
     api: CryptoMktApi,
+    markets: Arc<RwLock<MarketRegistry>>,
 }
 
 impl CryptoMktClient {
@@ -50,36 +65,87 @@ impl CryptoMktClient {
     /// Create the new Client instance
     ///
     pub fn new<'a>(api_key: &'a str, secret_key: &'a str) -> Self {
+        CryptoMktClient::with_api(CryptoMktApi::new(api_key, secret_key))
+    }
+    ///
+    /// Create the new Client instance over a custom HTTP transport
+    ///
+    /// See [`CryptoMktApi::with_transport`](struct.CryptoMktApi.html#method.with_transport)
+    ///
+    pub fn with_transport<'a, R>(api_key: &'a str, secret_key: &'a str, transport: Box<R>) -> Self
+    where
+        R: HttpRequest<Result = CryptoMktResult<String>> + Send + Sync + 'static,
+    {
+        CryptoMktClient::with_api(CryptoMktApi::with_transport(api_key, secret_key, transport))
+    }
+    ///
+    /// Create the new Client instance from a configured API, see
+    /// [`CryptoMktApi::builder`](struct.CryptoMktApi.html#method.builder)
+    ///
+    pub fn with_api(api: CryptoMktApi) -> Self {
         CryptoMktClient {
-            api: CryptoMktApi::new(api_key, secret_key),
+            api,
+            markets: Arc::new(RwLock::new(MarketRegistry::bundled())),
         }
     }
     ///
+    /// Replace the market registry used to round and check the orders, by default
+    /// the markets bundled with the crate
+    ///
+    pub fn with_market_registry(mut self, registry: MarketRegistry) -> Self {
+        self.markets = Arc::new(RwLock::new(registry));
+        self
+    }
+    ///
+    /// Get a copy of the market registry, it is refreshed by `get_markets`
+    ///
+    pub fn market_registry(&self) -> MarketRegistry {
+        self.markets.read().unwrap().clone()
+    }
+    ///
     /// Get Market List
     ///
-    pub fn get_markets(&self) -> Vec<Market> {
+    /// The markets not yet known are added to the market registry
+    ///
+    pub fn get_markets(&self) -> CryptoMktResult<Vec<Market>> {
         let resp =
             self.api
                 .call::<MarketResponse>(RequestMethod::Get(true), "market", HashMap::new());
         match resp {
             Ok(value) => {
-                let mut market_list = Vec::new();
-                for it in value.data {
-                    market_list.push(Market::new(self.api.clone(), it.clone().as_str()));
-                }
-                market_list
-            }
-            Err(e) => {
-                println!("{:?}", e);
-                Vec::new()
+                self.markets.write().unwrap().refresh(&value.data);
+                Ok(value.data.iter().map(|name| self.create_market(name)).collect())
             }
+            Err(e) => Err(e),
         }
     }
     ///
-    /// Return a new market from NAME
+    /// Return a new market from NAME, with the metadata of the market registry
+    ///
+    pub fn create_market(&self, name: &str) -> Market {
+        let info = self.markets.read().unwrap().get_or_default(name);
+        Market::new(self.api.clone(), name).with_market_info(info)
+    }
+
+    ///
+    /// Cancel the active orders of every market, or only the purchases or the sales
+    ///
+    /// The active orders of all the markets are listed before cancelling any of
+    /// them, so a listing error leaves every order in place. Each order gets its own
+    /// outcome, see
+    /// [`Market::cancel_all_orders`](struct.Market.html#method.cancel_all_orders)
     ///
-    pub fn create_market<'a>(&self, name: &'a str) -> Market {
-        Market::new(self.api.clone(), name)
+    pub fn cancel_all(&self, side: Option<OrderType>) -> CryptoMktResult<Vec<CancelOutcome>> {
+        let mut pending = Vec::new();
+        for market in self.get_markets()? {
+            let orders = market.active_orders(side.as_ref())?;
+            pending.push((market, orders));
+        }
+        let mut outcomes = Vec::new();
+        for (market, orders) in pending {
+            outcomes.extend(market.cancel_orders(orders));
+        }
+        Ok(outcomes)
     }
 
     ///
@@ -96,57 +162,88 @@ impl CryptoMktClient {
         }
     }
 
+    ///
+    /// Credentials of the real-time socket connection, signed with the API key
+    ///
+    pub fn get_socket_auth(&self) -> CryptoMktResult<SocketAuth> {
+        let resp = self.api.call::<SocketAuthResponse>(
+            RequestMethod::Get(false),
+            "socket/auth",
+            HashMap::new(),
+        );
+        match resp {
+            Ok(value) => Ok(value.data),
+            Err(e) => Err(e),
+        }
+    }
+
     ///
     /// It allows you to create a payment order, delivering QRs and urls to pay.
     ///
-    pub fn create_payment_order<'a>(
+    /// The request is validated when it is built, see
+    /// [`PaymentOrderRequest`](struct.PaymentOrderRequest.html)
+    ///
+    pub fn create_payment_order(
+        &self,
+        request: &PaymentOrderRequest,
+    ) -> CryptoMktResult<Payment> {
+        let resp = self.api.call::<PaymentResponse>(
+            RequestMethod::Post,
+            "payment/new_order",
+            request.to_params(),
+        );
+        match resp {
+            Ok(value) => Ok(value.data),
+            Err(e) => Err(e),
+        }
+    }
+
+    ///
+    /// Create a payment order from an `f32` amount
+    ///
+    #[deprecated(
+        since = "0.3.0",
+        note = "use `create_payment_order` with a `PaymentOrderRequest`"
+    )]
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_payment_order_f32(
         &self,
         to_receive: f32,
-        to_receive_currency: &'a str,
-        payment_receiver: &'a str,
+        to_receive_currency: &str,
+        payment_receiver: &str,
         external_id: Option<String>,
         callback_url: Option<String>,
         error_url: Option<String>,
         success_url: Option<String>,
         refund_email: Option<String>,
     ) -> CryptoMktResult<Payment> {
-        let mut params = HashMap::new();
-        params.insert("to_receive".to_string(), format!("{}", to_receive));
-        params.insert(
-            "to_receive_currency".to_string(),
-            to_receive_currency.to_string(),
+        let mut builder = PaymentOrderRequest::builder(
+            decimal_from_f32(to_receive)?,
+            to_receive_currency,
+            payment_receiver,
         );
-        params.insert("payment_receiver".to_string(), payment_receiver.to_string());
-
         if let Some(external_id) = external_id {
-            params.insert("external_id".to_string(), external_id);
+            builder = builder.external_id(&external_id);
         }
         if let Some(callback_url) = callback_url {
-            params.insert("callback_url".to_string(), callback_url);
+            builder = builder.callback_url(&callback_url);
         }
         if let Some(error_url) = error_url {
-            params.insert("error_url".to_string(), error_url);
+            builder = builder.error_url(&error_url);
         }
         if let Some(success_url) = success_url {
-            params.insert("success_url".to_string(), success_url);
+            builder = builder.success_url(&success_url);
         }
         if let Some(refund_email) = refund_email {
-            params.insert("refund_email".to_string(), refund_email);
-        }
-
-        let resp =
-            self.api
-                .call::<PaymentResponse>(RequestMethod::Post, "payment/new_order", params);
-        match resp {
-            Ok(value) => Ok(value.data),
-            Err(e) => Err(e),
+            builder = builder.refund_email(&refund_email);
         }
+        self.create_payment_order(&builder.build()?)
     }
 
     ///
     /// Returns the status of a payment order
     ///
-    pub fn payment_order_status<'a>(&self, id: &'a str) -> CryptoMktResult<Payment> {
+    pub fn payment_order_status(&self, id: &str) -> CryptoMktResult<Payment> {
         let mut params = HashMap::new();
         params.insert("id".to_string(), id.to_string());
 
@@ -163,16 +260,43 @@ impl CryptoMktClient {
     ///
     /// Returns the list of generated payment orders
     ///
-    pub fn get_payment_orders<'a>(
+    pub fn get_payment_orders<S, E>(
+        &self,
+        start_date: &S,
+        end_date: &E,
+        page: Option<i32>,
+        limit: Option<i32>,
+    ) -> CryptoMktResult<Vec<Payment>>
+    where
+        S: QueryDate + ?Sized,
+        E: QueryDate + ?Sized,
+    {
+        self.get_payment_orders_page(start_date, end_date, page, limit).map(|page| page.items)
+    }
+
+    ///
+    /// Returns the list of generated payment orders, with the pagination metadata
+    ///
+    pub fn get_payment_orders_page<S, E>(
         &self,
-        start_date: &'a str,
-        end_date: &'a str,
+        start_date: &S,
+        end_date: &E,
         page: Option<i32>,
         limit: Option<i32>,
-    ) -> CryptoMktResult<Vec<Payment>> {
+    ) -> CryptoMktResult<Page<Payment>>
+    where
+        S: QueryDate + ?Sized,
+        E: QueryDate + ?Sized,
+    {
         let mut params = HashMap::new();
-        params.insert("start_date".to_string(), start_date.to_string());
-        params.insert("end_date".to_string(), end_date.to_string());
+        params.insert(
+            "start_date".to_string(),
+            start_date.to_query_date(PAYMENT_DATE_FORMAT)?,
+        );
+        params.insert(
+            "end_date".to_string(),
+            end_date.to_query_date(PAYMENT_DATE_FORMAT)?,
+        );
 
         if let Some(page) = page {
             params.insert("page".to_string(), format!("{}", page));
@@ -183,13 +307,58 @@ impl CryptoMktClient {
 
         let resp = self.api.call::<PaymentListResponse>(
             RequestMethod::Get(false),
-            "payment/status",
+            "payment/orders",
             params,
         );
 
         match resp {
-            Ok(value) => Ok(value.data),
+            Ok(value) => Ok(Page::from(value)),
             Err(e) => Err(e),
         }
     }
+
+    ///
+    /// Returns the generated payment orders of every page, as a lazy iterator
+    ///
+    pub fn iter_payment_orders<S, E>(
+        &self,
+        start_date: &S,
+        end_date: &E,
+        options: PageOptions,
+    ) -> Paginator<Payment>
+    where
+        S: QueryDate + ?Sized,
+        E: QueryDate + ?Sized,
+    {
+        let mut params = HashMap::new();
+        let mut error = None;
+        match (
+            start_date.to_query_date(PAYMENT_DATE_FORMAT),
+            end_date.to_query_date(PAYMENT_DATE_FORMAT),
+        ) {
+            (Ok(start_date), Ok(end_date)) => {
+                params.insert("start_date".to_string(), start_date);
+                params.insert("end_date".to_string(), end_date);
+            }
+            (Err(e), _) | (_, Err(e)) => error = Some(e),
+        }
+        let api = self.api.clone();
+        Paginator::new(options, move |page, limit| {
+            if let Some(e) = error.take() {
+                return Err(e);
+            }
+            let mut params = params.clone();
+            params.insert("page".to_string(), format!("{}", page));
+            params.insert("limit".to_string(), format!("{}", limit));
+            let resp = api.call::<PaymentListResponse>(
+                RequestMethod::Get(false),
+                "payment/orders",
+                params,
+            );
+            match resp {
+                Ok(value) => Ok((value.data, value.pagination.next)),
+                Err(e) => Err(e),
+            }
+        })
+    }
 }
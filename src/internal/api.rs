@@ -1,43 +1,115 @@
 use std::collections::HashMap;
-use std::fmt::Write;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::fmt::{self, Debug, Formatter, Write};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use reqwest::{header::{HeaderMap, HeaderValue}, Url};
-use ring::hmac::{sign, Key, HMAC_SHA384};
+use reqwest::{header::{HeaderMap, HeaderName, HeaderValue}, Url};
+use ring::hmac::{sign, verify, Key, HMAC_SHA384};
 
 use serde::de::DeserializeOwned;
 use serde_json;
 // use log::Level;
 
 /// X-MKT-APIKEY: La API key como un string
-const X_MKT_APIKEY: &'static str = "X-MKT-APIKEY";
+pub(crate) const X_MKT_APIKEY: &str = "X-MKT-APIKEY";
 /// X-MKT-SIGNATURE: El mensaje firmado generado por el usuario (ver abajo)
-const X_MKT_SIGNATURE: &'static str = "X-MKT-SIGNATURE";
+const X_MKT_SIGNATURE: &str = "X-MKT-SIGNATURE";
 /// X-MKT-TIMESTAMP: Un timestamp para tu llamada
-const X_MKT_TIMESTAMP: &'static str = "X-MKT-TIMESTAMP";
+const X_MKT_TIMESTAMP: &str = "X-MKT-TIMESTAMP";
 
-use crate::internal::errors::{CryptoMktErrorType, CryptoMktResult};
-use crate::internal::request::HttpRequest;
+use crate::internal::errors::{CryptoMktError, CryptoMktErrorType, CryptoMktResult};
+use crate::internal::rate_limit::{RateLimitScope, RateLimiter};
+use crate::internal::request::{AsyncHttpRequest, HttpRequest};
+use crate::internal::response::response_status_error;
+
+///
+/// Firma el mensaje con HMAC-SHA384 y la llave secreta, devuelve la firma en hexadecimal
+///
+/// Argumentos
+///     secret_key: Llave secreta de la cuenta
+///     msg: cadena de texto que se requiere firmar
+///
+pub(crate) fn sign_message(secret_key: &str, msg: &str) -> String {
+    let s_key = Key::new(HMAC_SHA384, secret_key.as_bytes());
+    let sign = sign(&s_key, msg.as_bytes());
+
+    let mut output = String::new();
+    for byte in sign.as_ref() {
+        write!(output, "{:02x}", byte).unwrap();
+    }
+
+    output
+}
+
+///
+/// Verifica en tiempo constante una firma hexadecimal generada con `sign_message`
+///
+/// Argumentos
+///     secret_key: Llave secreta de la cuenta
+///     msg: Mensaje firmado
+///     signature: Firma en hexadecimal
+///
+pub(crate) fn verify_message(secret_key: &str, msg: &str, signature: &str) -> bool {
+    let signature = signature.trim().as_bytes();
+    let mut tag = Vec::with_capacity(signature.len() / 2);
+    for pair in signature.chunks(2) {
+        let byte = std::str::from_utf8(pair)
+            .ok()
+            .filter(|pair| pair.len() == 2)
+            .and_then(|pair| u8::from_str_radix(pair, 16).ok());
+        match byte {
+            Some(byte) => tag.push(byte),
+            None => return false,
+        }
+    }
+    let s_key = Key::new(HMAC_SHA384, secret_key.as_bytes());
+    verify(&s_key, msg.as_bytes(), &tag).is_ok()
+}
 
 ///
 /// API Interna
 ///
-#[derive(Debug, Clone)]
-pub struct Api<R>
-where
-    R: HttpRequest<Result=CryptoMktResult<String>>
-{
+/// El transporte `R` puede ser bloqueante (`HttpRequest`) o asíncrono (`AsyncHttpRequest`),
+/// se comparte entre todos los clones de la instancia
+///
+pub struct Api<R: ?Sized> {
     api_key: String,
     secret_key: String,
     domain: String,
     api_version: String,
-    req: Box<R>,
+    default_headers: HeaderMap,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    req: Arc<R>,
 }
 
-impl<R> Api<R>
-where
-    R: HttpRequest<Result=CryptoMktResult<String>>
-{
+impl<R: ?Sized> Clone for Api<R> {
+    fn clone(&self) -> Self {
+        Api {
+            api_key: self.api_key.clone(),
+            secret_key: self.secret_key.clone(),
+            domain: self.domain.clone(),
+            api_version: self.api_version.clone(),
+            default_headers: self.default_headers.clone(),
+            rate_limiter: self.rate_limiter.clone(),
+            req: self.req.clone(),
+        }
+    }
+}
+
+impl<R: ?Sized> Debug for Api<R> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("Api")
+            .field("api_key", &self.api_key)
+            .field("domain", &self.domain)
+            .field("api_version", &self.api_version)
+            .field("default_headers", &self.default_headers)
+            .field("rate_limiter", &self.rate_limiter)
+            .finish()
+    }
+}
+
+impl<R: ?Sized> Api<R> {
     ///
     /// Crea una instancia de tipo API
     ///
@@ -52,7 +124,9 @@ where
             secret_key: secret_key.to_string(),
             domain: "https://api.cryptomkt.com/".to_string(),
             api_version: "v1".to_string(),
-            req: http_transport,
+            default_headers: HeaderMap::new(),
+            rate_limiter: None,
+            req: Arc::from(http_transport),
         }
     }
     /// Devuelve el dominio
@@ -66,81 +140,117 @@ where
     }
 
     ///
-    /// Construye la URL
+    /// Cambia el dominio del API, por ejemplo para usar un servidor de pruebas
     ///
     /// Argumentos
-    ///     endpoint: Endpoint desde donde se va a extraer los datos
-    ///     params: Parámetros de la url
+    ///     domain: URL base, debe ser absoluta (http://localhost:8080/)
     ///
-    pub fn build_url<'a>(&self, endpoint: &'a str, params: &HashMap<String, String>) -> Url {
-        let mut api_url = Url::parse(&self.domain).unwrap();
-        // Adiciona la version de la API
-        api_url = api_url
-            .join(format!("{}/", &self.api_version).as_str())
-            .unwrap();
-        // Adiciona el endpoint
-        api_url = api_url.join(endpoint).unwrap();
+    pub fn set_domain(&mut self, domain: &str) -> CryptoMktResult<()> {
+        let mut domain = domain.trim().to_string();
+        if !domain.ends_with('/') {
+            domain.push('/');
+        }
+        match Url::parse(&domain) {
+            Ok(url) if !url.cannot_be_a_base() => {
+                self.domain = domain;
+                Ok(())
+            }
+            _ => {
+                error!(target: "cryptomkt", "Invalid domain: {:?}", domain);
+                Err(CryptoMktErrorType::InvalidConfiguration.into())
+            }
+        }
+    }
 
-        for (key, value) in params {
-            api_url
-                .query_pairs_mut()
-                .append_pair(key.as_str(), value.as_str());
+    ///
+    /// Cambia el prefijo de versión del API, una cadena vacía elimina el prefijo
+    ///
+    /// Argumentos
+    ///     api_version: Versión del API (v1)
+    ///
+    pub fn set_api_version(&mut self, api_version: &str) {
+        self.api_version = api_version.trim_matches('/').to_string();
+    }
+
+    ///
+    /// Adiciona una cabecera que se envía en todas las peticiones
+    ///
+    /// Argumentos
+    ///     name: Nombre de la cabecera
+    ///     value: Valor de la cabecera
+    ///
+    pub fn add_default_header(&mut self, name: &str, value: &str) -> CryptoMktResult<()> {
+        match (
+            HeaderName::from_bytes(name.as_bytes()),
+            HeaderValue::from_str(value),
+        ) {
+            (Ok(name), Ok(value)) => {
+                self.default_headers.insert(name, value);
+                Ok(())
+            }
+            _ => {
+                error!(target: "cryptomkt", "Invalid default header: {:?}", name);
+                Err(CryptoMktErrorType::InvalidConfiguration.into())
+            }
         }
-        api_url
     }
 
     ///
+    /// Limita las peticiones de esta instancia y de todos sus clones
+    ///
+    /// Argumentos
+    ///     rate_limiter: Limitador compartido
+    ///
+    pub fn set_rate_limiter(&mut self, rate_limiter: Arc<RateLimiter>) {
+        self.rate_limiter = Some(rate_limiter);
+    }
+
+    /// Devuelve el limitador de peticiones, si existe
+    pub fn rate_limiter(&self) -> Option<Arc<RateLimiter>> {
+        self.rate_limiter.clone()
+    }
+
+    ///
+    /// Reserva una petición en el limitador y devuelve la espera necesaria
     ///
     /// Argumentos
-    ///     endpoint: Endpoint desde donde se va a extraer los datos
-    ///     params: Parámetros de la url
     ///     is_public: indica si el endpoint es public
     ///
-    pub fn get_edge<'a, T>(
-        &self,
-        endpoint: &'a str,
-        params: HashMap<String, String>,
-        is_public: bool,
-    ) -> CryptoMktResult<T>
-    where
-        T: DeserializeOwned,
-    {
-        let api_url = self.build_url(endpoint, &params);
-        let headers = self.build_headers(endpoint, &params, is_public, true);
-        let result = self.req.get(api_url, headers)?;
-        match serde_json::from_str(&result) {
-            Ok(sr) => Ok(sr),
-            Err(e) => {
-                println!("{:?}", e);
-                Err(CryptoMktErrorType::MalformedResource)
-            }
+    fn reserve_request(&self, is_public: bool) -> CryptoMktResult<Duration> {
+        match &self.rate_limiter {
+            Some(rate_limiter) => rate_limiter.reserve(if is_public {
+                RateLimitScope::Public
+            } else {
+                RateLimitScope::Private
+            }),
+            None => Ok(Duration::from_secs(0)),
         }
     }
+
     ///
+    /// Construye la URL
     ///
     /// Argumentos
     ///     endpoint: Endpoint desde donde se va a extraer los datos
     ///     params: Parámetros de la url
-    ///     is_public: indica si el endpoint es public
     ///
-    pub fn post_edge<'a, T>(
-        &self,
-        endpoint: &'a str,
-        payload: HashMap<String, String>,
-    ) -> CryptoMktResult<T>
-    where
-        T: DeserializeOwned,
-    {
-        let api_url = self.build_url(endpoint, &HashMap::new());
-        let headers = self.build_headers(endpoint, &payload, false, false);
-        let result = self.req.post(api_url, headers, payload)?;
-        match serde_json::from_str(&result) {
-            Ok(sr) => Ok(sr),
-            Err(e) => {
-                println!("{:?}", e);
-                Err(CryptoMktErrorType::MalformedResource)
-            }
+    pub fn build_url(&self, endpoint: &str, params: &HashMap<String, String>) -> Url {
+        let mut api_url = Url::parse(&self.domain).unwrap();
+        // Adiciona la version de la API
+        if !self.api_version.is_empty() {
+            api_url = api_url
+                .join(format!("{}/", &self.api_version).as_str())
+                .unwrap();
+        }
+        // Adiciona el endpoint
+        api_url = api_url.join(endpoint).unwrap();
+
+        for (key, value) in params {
+            api_url
+                .query_pairs_mut()
+                .append_pair(key.as_str(), value.as_str());
         }
+        api_url
     }
 
     ///
@@ -151,9 +261,9 @@ where
     ///     payload: Parámetros de la URL
     ///     is_get: Define si el método de encuesta es GET
     ///
-    pub fn build_signature_format<'a>(
+    pub fn build_signature_format(
         &self,
-        endpoint: &'a str,
+        endpoint: &str,
         payload: &HashMap<String, String>,
         is_get: bool,
     ) -> String {
@@ -163,7 +273,11 @@ where
             Err(_) => "".to_string(),
         };
         // Adiciona /api_version/endpoint
-        signature += format!("/{}/{}", &self.api_version, &endpoint).as_str();
+        if self.api_version.is_empty() {
+            signature += format!("/{}", &endpoint).as_str();
+        } else {
+            signature += format!("/{}/{}", &self.api_version, &endpoint).as_str();
+        }
         // si es POST se adicionan los valores de las llaves
         if !is_get {
             let mut keys = payload.keys().collect::<Vec<_>>();
@@ -182,16 +296,31 @@ where
     /// Argumentos
     ///     msg: cadena de texto que se requiere firmar
     ///
-    pub fn sign_msg<'a>(&self, msg: &'a str) -> String {
-        let s_key = Key::new(HMAC_SHA384, self.secret_key.as_bytes());
-        let sign = sign(&s_key, msg.as_bytes());
-
-        let mut output = String::new();
-        for byte in sign.as_ref() {
-            write!(output, "{:02x}", byte).unwrap();
-        }
+    pub fn sign_msg(&self, msg: &str) -> String {
+        sign_message(&self.secret_key, msg)
+    }
 
-        output
+    ///
+    /// Credenciales firmadas de un canal privado del socket, el mensaje firmado es
+    /// `timestamp + canal` (Ej. `1530000000account`)
+    ///
+    /// Argumentos
+    ///     channel: Nombre del canal privado
+    ///
+    pub fn sign_channel(&self, channel: &str) -> HashMap<String, String> {
+        let timestamp = match SystemTime::now().duration_since(UNIX_EPOCH) {
+            Ok(n) => n.as_secs().to_string(),
+            Err(_) => "".to_string(),
+        };
+        let mut credentials = HashMap::new();
+        credentials.insert("channel".to_string(), channel.to_string());
+        credentials.insert("api_key".to_string(), self.api_key.clone());
+        credentials.insert(
+            "signature".to_string(),
+            self.sign_msg(&format!("{}{}", timestamp, channel)),
+        );
+        credentials.insert("timestamp".to_string(), timestamp);
+        credentials
     }
     ///
     /// Conforma los headers para realizar la petición al servidor, en caso de no ser publica
@@ -203,17 +332,17 @@ where
     ///     is_public: indica si el endpoint es public
     ///     is_get: Define si el método de encuesta es GET
     ///
-    fn build_headers<'a>(
+    pub fn build_headers(
         &self,
-        endpoint: &'a str,
+        endpoint: &str,
         payload: &HashMap<String, String>,
         is_public: bool,
         is_get: bool,
     ) -> HeaderMap {
-        let mut headers = HeaderMap::new();
+        let mut headers = self.default_headers.clone();
         if !is_public {
-            let msg_to_sign = self.build_signature_format(endpoint, &payload, is_get);
-            let timestamp = msg_to_sign.split("/").collect::<Vec<&str>>();
+            let msg_to_sign = self.build_signature_format(endpoint, payload, is_get);
+            let timestamp = msg_to_sign.split('/').collect::<Vec<&str>>();
             headers.insert(
                 X_MKT_APIKEY,
                 HeaderValue::from_str(self.api_key.as_str()).unwrap(),
@@ -230,3 +359,157 @@ where
         headers
     }
 }
+
+///
+/// Deserializa la respuesta del servidor, las respuestas con `"status": "error"`
+/// se devuelven como error antes de leer `data`
+///
+/// Argumentos
+///     body: Cuerpo de la respuesta
+///     endpoint: Endpoint de la petición
+///     method: Método de la petición
+///
+fn parse_response<T>(body: &str, endpoint: &str, method: &str) -> CryptoMktResult<T>
+where
+    T: DeserializeOwned,
+{
+    if let Some(e) = response_status_error(body) {
+        warn!(target: "cryptomkt", "{} {}: {}", method, endpoint, e);
+        return Err(e.with_endpoint(endpoint).with_method(method));
+    }
+    match serde_json::from_str(body) {
+        Ok(sr) => Ok(sr),
+        Err(e) => {
+            error!(target: "cryptomkt", "{:?}", e);
+            Err(CryptoMktError::from(CryptoMktErrorType::MalformedResource)
+                .with_endpoint(endpoint)
+                .with_method(method)
+                .with_source(e))
+        }
+    }
+}
+
+#[cfg_attr(not(feature = "blocking"), allow(dead_code))]
+impl<R> Api<R>
+where
+    R: HttpRequest<Result = CryptoMktResult<String>> + ?Sized,
+{
+    ///
+    ///
+    /// Argumentos
+    ///     endpoint: Endpoint desde donde se va a extraer los datos
+    ///     params: Parámetros de la url
+    ///     is_public: indica si el endpoint es public
+    ///
+    pub fn get_edge<T>(
+        &self,
+        endpoint: &str,
+        params: HashMap<String, String>,
+        is_public: bool,
+    ) -> CryptoMktResult<T>
+    where
+        T: DeserializeOwned,
+    {
+        let api_url = self.build_url(endpoint, &params);
+        let wait = self.reserve_request(is_public)?;
+        if wait > Duration::from_secs(0) {
+            thread::sleep(wait);
+        }
+        let headers = self.build_headers(endpoint, &params, is_public, true);
+        let result = self
+            .req
+            .get(api_url, headers)
+            .map_err(|e| e.with_endpoint(endpoint).with_method("GET"))?;
+        parse_response(&result, endpoint, "GET")
+    }
+    ///
+    ///
+    /// Argumentos
+    ///     endpoint: Endpoint desde donde se va a extraer los datos
+    ///     params: Parámetros de la url
+    ///     is_public: indica si el endpoint es public
+    ///
+    pub fn post_edge<T>(
+        &self,
+        endpoint: &str,
+        payload: HashMap<String, String>,
+    ) -> CryptoMktResult<T>
+    where
+        T: DeserializeOwned,
+    {
+        let api_url = self.build_url(endpoint, &HashMap::new());
+        let wait = self.reserve_request(false)?;
+        if wait > Duration::from_secs(0) {
+            thread::sleep(wait);
+        }
+        let headers = self.build_headers(endpoint, &payload, false, false);
+        let result = self
+            .req
+            .post(api_url, headers, payload)
+            .map_err(|e| e.with_endpoint(endpoint).with_method("POST"))?;
+        parse_response(&result, endpoint, "POST")
+    }
+}
+
+impl<R> Api<R>
+where
+    R: AsyncHttpRequest + ?Sized,
+{
+    ///
+    ///
+    /// Argumentos
+    ///     endpoint: Endpoint desde donde se va a extraer los datos
+    ///     params: Parámetros de la url
+    ///     is_public: indica si el endpoint es public
+    ///
+    pub async fn get_edge_async<T>(
+        &self,
+        endpoint: &str,
+        params: HashMap<String, String>,
+        is_public: bool,
+    ) -> CryptoMktResult<T>
+    where
+        T: DeserializeOwned,
+    {
+        let api_url = self.build_url(endpoint, &params);
+        let wait = self.reserve_request(is_public)?;
+        if wait > Duration::from_secs(0) {
+            tokio::time::delay_for(wait).await;
+        }
+        let headers = self.build_headers(endpoint, &params, is_public, true);
+        let result = self
+            .req
+            .get(api_url, headers)
+            .await
+            .map_err(|e| e.with_endpoint(endpoint).with_method("GET"))?;
+        parse_response(&result, endpoint, "GET")
+    }
+    ///
+    ///
+    /// Argumentos
+    ///     endpoint: Endpoint desde donde se va a extraer los datos
+    ///     params: Parámetros de la url
+    ///     is_public: indica si el endpoint es public
+    ///
+    pub async fn post_edge_async<T>(
+        &self,
+        endpoint: &str,
+        payload: HashMap<String, String>,
+    ) -> CryptoMktResult<T>
+    where
+        T: DeserializeOwned,
+    {
+        let api_url = self.build_url(endpoint, &HashMap::new());
+        let wait = self.reserve_request(false)?;
+        if wait > Duration::from_secs(0) {
+            tokio::time::delay_for(wait).await;
+        }
+        let headers = self.build_headers(endpoint, &payload, false, false);
+        let result = self
+            .req
+            .post(api_url, headers, payload)
+            .await
+            .map_err(|e| e.with_endpoint(endpoint).with_method("POST"))?;
+        parse_response(&result, endpoint, "POST")
+    }
+}
@@ -0,0 +1,166 @@
+//!
+//! Fechas del API: tipo `Timestamp` de los modelos y formato de las fechas
+//! usadas como parámetros de las consultas
+//!
+//! Con la feature `chrono` las fechas se leen como `DateTime<Utc>`, sin ella se
+//! mantienen las cadenas enviadas por el exchange
+//!
+
+use crate::internal::errors::{CryptoMktError, CryptoMktErrorType, CryptoMktResult};
+#[cfg(feature = "chrono")]
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone, Utc};
+#[cfg(feature = "chrono")]
+use std::fmt::Display;
+
+/// Formato de las fechas del endpoint `trades` (Ej. 2018-05-15)
+pub const TRADES_DATE_FORMAT: &str = "%Y-%m-%d";
+/// Formato de las fechas del endpoint `payment/orders` (Ej. 15/05/2018)
+pub const PAYMENT_DATE_FORMAT: &str = "%d/%m/%Y";
+
+/// Fecha de los modelos, `DateTime<Utc>` con la feature `chrono`
+#[cfg(feature = "chrono")]
+pub type Timestamp = DateTime<Utc>;
+
+/// Fecha de los modelos, la cadena enviada por el exchange sin la feature `chrono`
+#[cfg(not(feature = "chrono"))]
+pub type Timestamp = String;
+
+///
+/// Lee una fecha del exchange, con o sin zona horaria. Las fechas sin zona
+/// horaria se consideran UTC
+///
+/// Argumentos
+///     value: Fecha (Ej. 2017-08-29T15:44:17.267526)
+///
+#[cfg(feature = "chrono")]
+pub fn parse_timestamp(value: &str) -> Option<Timestamp> {
+    let value = value.trim();
+    if let Ok(date) = DateTime::parse_from_rfc3339(value) {
+        return Some(date.with_timezone(&Utc));
+    }
+    for format in &[
+        "%Y-%m-%dT%H:%M:%S%.f",
+        "%Y-%m-%d %H:%M:%S%.f",
+        "%Y-%m-%d%H:%M:%S%.f",
+    ] {
+        if let Ok(date) = NaiveDateTime::parse_from_str(value, format) {
+            return Some(Utc.from_utc_datetime(&date));
+        }
+    }
+    None
+}
+
+///
+/// Deserialización de las fechas de los modelos
+///
+pub(crate) mod timestamp {
+    use super::Timestamp;
+    use serde::{Deserialize, Deserializer};
+
+    #[cfg(feature = "chrono")]
+    fn parse<E: serde::de::Error>(value: &str) -> Result<Timestamp, E> {
+        super::parse_timestamp(value)
+            .ok_or_else(|| E::custom(format!("invalid timestamp: {}", value)))
+    }
+
+    #[cfg(not(feature = "chrono"))]
+    fn parse<E: serde::de::Error>(value: &str) -> Result<Timestamp, E> {
+        Ok(value.to_string())
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Timestamp, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        parse(&String::deserialize(deserializer)?)
+    }
+
+    pub fn option<'de, D>(deserializer: D) -> Result<Option<Timestamp>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match Option::<String>::deserialize(deserializer)? {
+            Some(value) if !value.trim().is_empty() => parse(&value).map(Some),
+            _ => Ok(None),
+        }
+    }
+}
+
+///
+/// Fecha usada como parámetro de una consulta (Ej. `Market::get_trades`)
+///
+/// Las cadenas se envían tal cual, con la feature `chrono` se validan y se pueden usar
+/// `DateTime`, `NaiveDate` y `NaiveDateTime`, que se formatean como lo espera el API
+///
+pub trait QueryDate {
+    ///
+    /// Devuelve la fecha en el formato del endpoint
+    ///
+    /// Argumentos
+    ///     format: Formato de la fecha (Ej. %Y-%m-%d)
+    ///
+    fn to_query_date(&self, format: &str) -> CryptoMktResult<String>;
+}
+
+fn invalid_date(value: &str, format: &str) -> CryptoMktError {
+    CryptoMktError::from(CryptoMktErrorType::BadRequest).with_message(&format!(
+        "invalid date {}, expected format {}",
+        value, format
+    ))
+}
+
+impl QueryDate for str {
+    #[cfg(feature = "chrono")]
+    fn to_query_date(&self, format: &str) -> CryptoMktResult<String> {
+        let value = self.trim();
+        for input_format in &[format, TRADES_DATE_FORMAT] {
+            if let Ok(date) = NaiveDate::parse_from_str(value, input_format) {
+                return Ok(date.format(format).to_string());
+            }
+        }
+        match parse_timestamp(value) {
+            Some(date) => Ok(date.format(format).to_string()),
+            None => Err(invalid_date(value, format)),
+        }
+    }
+
+    #[cfg(not(feature = "chrono"))]
+    fn to_query_date(&self, format: &str) -> CryptoMktResult<String> {
+        let value = self.trim();
+        if value.is_empty() {
+            return Err(invalid_date(value, format));
+        }
+        Ok(value.to_string())
+    }
+}
+
+impl QueryDate for String {
+    fn to_query_date(&self, format: &str) -> CryptoMktResult<String> {
+        self.as_str().to_query_date(format)
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl<Tz> QueryDate for DateTime<Tz>
+where
+    Tz: TimeZone,
+    Tz::Offset: Display,
+{
+    fn to_query_date(&self, format: &str) -> CryptoMktResult<String> {
+        Ok(self.with_timezone(&Utc).format(format).to_string())
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl QueryDate for NaiveDate {
+    fn to_query_date(&self, format: &str) -> CryptoMktResult<String> {
+        Ok(self.format(format).to_string())
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl QueryDate for NaiveDateTime {
+    fn to_query_date(&self, format: &str) -> CryptoMktResult<String> {
+        Ok(self.format(format).to_string())
+    }
+}
@@ -1,4 +1,8 @@
-#[derive(Debug)]
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CryptoMktErrorType {
     // 401 => Tu API key es errónea
     RequestUnauthorized,
@@ -24,7 +28,250 @@ pub enum CryptoMktErrorType {
     BadRequest,
     //
     MalformedResource,
+    // Configuración inválida del cliente (dominio, versión o cabeceras por defecto)
+    InvalidConfiguration,
+    // Se agotó la cuota local de peticiones (RateLimitMode::FailFast)
+    RateLimitExceeded,
+    // Parámetro inválido, detectado antes de enviar la petición
+    InvalidArgument,
+    // La petición no llegó al servidor o no hubo respuesta: conexión, DNS o tiempo de espera
+    NetworkError,
+    // La conexión del socket se cerró o no se pudo establecer
+    ConnectionClosed,
+    // La estimación del deslizamiento del precio supera el máximo permitido
+    SlippageExceeded,
+    // {"status": "error"} => Saldo insuficiente para realizar la operación
+    InsufficientFunds,
+    // {"status": "error"} => El mercado no existe
+    InvalidMarket,
+    // {"status": "error"} => El monto es menor que el mínimo permitido
+    AmountBelowMinimum,
+    // {"status": "error"} => La firma de la petición no es válida
+    InvalidSignature,
+    // {"status": "error"} => Error del exchange con un mensaje no reconocido
+    ExchangeError,
+}
+
+impl CryptoMktErrorType {
+    ///
+    /// Traduce el mensaje de una respuesta `{"status": "error"}` al tipo de error
+    ///
+    /// Argumentos
+    ///     message: Mensaje enviado por el exchange
+    ///
+    pub fn from_exchange_message(message: &str) -> Self {
+        let message = message.to_lowercase();
+        if message.contains("insufficient") || message.contains("not enough") {
+            CryptoMktErrorType::InsufficientFunds
+        } else if message.contains("signature") {
+            CryptoMktErrorType::InvalidSignature
+        } else if message.contains("minimum")
+            || message.contains("min amount")
+            || message.contains("too small")
+        {
+            CryptoMktErrorType::AmountBelowMinimum
+        } else if message.contains("market")
+            && (message.contains("invalid")
+                || message.contains("not found")
+                || message.contains("exist"))
+        {
+            CryptoMktErrorType::InvalidMarket
+        } else {
+            CryptoMktErrorType::ExchangeError
+        }
+    }
+
+    ///
+    /// Indica si el error es transitorio y la petición se puede repetir más tarde
+    ///
+    pub fn is_retryable(self) -> bool {
+        matches!(
+            self,
+            CryptoMktErrorType::RequestTooManyRequests
+                | CryptoMktErrorType::RequestServiceUnavailable
+                | CryptoMktErrorType::RequestInternalServerError
+                | CryptoMktErrorType::NetworkError
+        )
+    }
+}
+
+impl Display for CryptoMktErrorType {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let description = match self {
+            CryptoMktErrorType::RequestUnauthorized => "invalid API key",
+            CryptoMktErrorType::RequestForbidden => "resource only available for administrators",
+            CryptoMktErrorType::RequestNotFound => "resource not found",
+            CryptoMktErrorType::RequestMethodNotAllowed => "method not allowed",
+            CryptoMktErrorType::RequestNotAcceptable => "requested format is not JSON",
+            CryptoMktErrorType::RequestGone => "resource removed from the server",
+            CryptoMktErrorType::RequestTeapot => "I'm a teapot",
+            CryptoMktErrorType::RequestTooManyRequests => "too many requests",
+            CryptoMktErrorType::RequestInternalServerError => "internal server error",
+            CryptoMktErrorType::RequestServiceUnavailable => "service unavailable",
+            CryptoMktErrorType::BadRequest => "bad request",
+            CryptoMktErrorType::MalformedResource => "malformed response",
+            CryptoMktErrorType::InvalidConfiguration => "invalid client configuration",
+            CryptoMktErrorType::RateLimitExceeded => "client rate limit exceeded",
+            CryptoMktErrorType::InvalidArgument => "invalid argument",
+            CryptoMktErrorType::NetworkError => "network error",
+            CryptoMktErrorType::ConnectionClosed => "connection closed",
+            CryptoMktErrorType::SlippageExceeded => "slippage above the maximum",
+            CryptoMktErrorType::InsufficientFunds => "insufficient funds",
+            CryptoMktErrorType::InvalidMarket => "invalid market",
+            CryptoMktErrorType::AmountBelowMinimum => "amount below the minimum",
+            CryptoMktErrorType::InvalidSignature => "invalid signature",
+            CryptoMktErrorType::ExchangeError => "exchange error",
+        };
+        write!(f, "{}", description)
+    }
+}
+
+///
+/// Error devuelto por el API
+///
+/// Además del tipo de error conserva, cuando se conocen, el código HTTP, el endpoint,
+/// el método, el mensaje enviado por el exchange y el error de origen (reqwest o serde)
+///
+#[derive(Debug)]
+pub struct CryptoMktError {
+    kind: CryptoMktErrorType,
+    status: Option<u16>,
+    endpoint: Option<String>,
+    method: Option<String>,
+    message: Option<String>,
+    retry_after: Option<Duration>,
+    source: Option<Box<dyn Error + Send + Sync>>,
+}
+
+impl CryptoMktError {
+    ///
+    /// Crea un error del tipo indicado sin más detalles
+    ///
+    pub fn new(kind: CryptoMktErrorType) -> Self {
+        CryptoMktError {
+            kind,
+            status: None,
+            endpoint: None,
+            method: None,
+            message: None,
+            retry_after: None,
+            source: None,
+        }
+    }
+
+    /// Adiciona el código HTTP de la respuesta
+    pub fn with_status(mut self, status: u16) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    /// Adiciona el endpoint de la petición, si no se conocía
+    pub fn with_endpoint(mut self, endpoint: &str) -> Self {
+        if self.endpoint.is_none() {
+            self.endpoint = Some(endpoint.to_string());
+        }
+        self
+    }
+
+    /// Adiciona el método HTTP de la petición, si no se conocía
+    pub fn with_method(mut self, method: &str) -> Self {
+        if self.method.is_none() {
+            self.method = Some(method.to_string());
+        }
+        self
+    }
+
+    /// Adiciona el mensaje enviado por el exchange
+    pub fn with_message(mut self, message: &str) -> Self {
+        self.message = Some(message.to_string());
+        self
+    }
+
+    /// Adiciona la espera solicitada por el servidor (cabecera `Retry-After`)
+    pub fn with_retry_after(mut self, retry_after: Duration) -> Self {
+        self.retry_after = Some(retry_after);
+        self
+    }
+
+    /// Adiciona el error de origen
+    pub fn with_source<E>(mut self, source: E) -> Self
+    where
+        E: Error + Send + Sync + 'static,
+    {
+        self.source = Some(Box::new(source));
+        self
+    }
+
+    /// Devuelve el tipo de error
+    pub fn kind(&self) -> CryptoMktErrorType {
+        self.kind
+    }
+
+    /// Devuelve el código HTTP de la respuesta
+    pub fn status(&self) -> Option<u16> {
+        self.status
+    }
+
+    /// Devuelve el endpoint de la petición
+    pub fn endpoint(&self) -> Option<&str> {
+        self.endpoint.as_deref()
+    }
+
+    /// Devuelve el método HTTP de la petición
+    pub fn method(&self) -> Option<&str> {
+        self.method.as_deref()
+    }
+
+    /// Devuelve el mensaje enviado por el exchange
+    pub fn message(&self) -> Option<&str> {
+        self.message.as_deref()
+    }
+
+    /// Devuelve la espera solicitada por el servidor
+    pub fn retry_after(&self) -> Option<Duration> {
+        self.retry_after
+    }
+
+    ///
+    /// Indica si el error es transitorio (429, 500, 503) y la petición se puede repetir
+    ///
+    pub fn is_retryable(&self) -> bool {
+        self.kind.is_retryable()
+    }
+}
+
+impl From<CryptoMktErrorType> for CryptoMktError {
+    fn from(kind: CryptoMktErrorType) -> Self {
+        CryptoMktError::new(kind)
+    }
+}
+
+impl Display for CryptoMktError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", self.kind)?;
+        if let Some(status) = self.status {
+            write!(f, " (HTTP {})", status)?;
+        }
+        match (&self.method, &self.endpoint) {
+            (Some(method), Some(endpoint)) => write!(f, " on {} {}", method, endpoint)?,
+            (None, Some(endpoint)) => write!(f, " on {}", endpoint)?,
+            _ => {}
+        }
+        if let Some(message) = &self.message {
+            write!(f, ": {}", message)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for CryptoMktError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.source {
+            Some(source) => Some(source.as_ref()),
+            None => None,
+        }
+    }
 }
 
-// Define alea generico al Result para  CryptoMktErrorType
-pub type CryptoMktResult<T> = Result<T, CryptoMktErrorType>;
+// Define alea generico al Result para  CryptoMktError
+pub type CryptoMktResult<T> = Result<T, CryptoMktError>;
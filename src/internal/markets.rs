@@ -0,0 +1,356 @@
+//!
+//! Registro de los mercados: monedas, tamaño del tick del precio, paso de la
+//! cantidad y monto mínimo de las órdenes
+//!
+//! El registro incluye un archivo (`markets.json`) con los datos que tienen una fuente:
+//! las monedas de cada mercado y los decimales de la moneda de cotización según ISO 4217.
+//! CryptoMarket no documenta el tick, el paso ni los montos mínimos, quedan sin definir
+//! hasta que se cargan desde un archivo JSON o TOML propio
+//!
+
+use crate::internal::errors::{CryptoMktError, CryptoMktErrorType, CryptoMktResult};
+use crate::internal::models::OrderType;
+use crate::internal::order::OrderLimits;
+use crate::market::split_market_name;
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Mercados incluidos en el crate, ver el campo `source` del archivo
+const BUNDLED_MARKETS: &str = include_str!("markets.json");
+
+///
+/// Información de un mercado
+///
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct MarketInfo {
+    /// Nombre del mercado (Ej. ETHCLP)
+    pub name: String,
+    /// Moneda base (Ej. ETH)
+    #[serde(default)]
+    pub base_currency: String,
+    /// Moneda de cotización (Ej. CLP)
+    #[serde(default)]
+    pub quote_currency: String,
+    /// Variación mínima del precio
+    #[serde(default)]
+    pub price_tick: Option<Decimal>,
+    /// Variación mínima de la cantidad
+    #[serde(default)]
+    pub amount_step: Option<Decimal>,
+    /// Cantidad mínima de una orden, en la moneda base
+    #[serde(default)]
+    pub min_amount: Option<Decimal>,
+    /// Decimales de la moneda de cotización, usados en las compras instantáneas
+    #[serde(default)]
+    pub quote_decimals: Option<u32>,
+    /// Monto mínimo de una orden (precio por cantidad), en la moneda de cotización
+    #[serde(default)]
+    pub min_notional: Option<Decimal>,
+}
+
+impl MarketInfo {
+    ///
+    /// Información de un mercado sin límites conocidos, las monedas se obtienen del nombre
+    ///
+    /// Argumentos
+    ///     name: Nombre del mercado (Ej. ETHCLP)
+    ///
+    pub fn new(name: &str) -> Self {
+        let (base_currency, quote_currency) = split_market_name(name);
+        MarketInfo {
+            name: name.to_string(),
+            base_currency,
+            quote_currency,
+            price_tick: None,
+            amount_step: None,
+            min_amount: None,
+            quote_decimals: None,
+            min_notional: None,
+        }
+    }
+
+    ///
+    /// Redondea el precio al tick del mercado: hacia abajo para las compras y hacia
+    /// arriba para las ventas, así la orden nunca es peor que el precio indicado
+    ///
+    pub fn round_price(&self, price: Decimal, order_type: &OrderType) -> Decimal {
+        match (self.price_tick, order_type) {
+            (Some(tick), OrderType::Sell) => round_to(price, tick, Decimal::ceil),
+            (Some(tick), _) => round_to(price, tick, Decimal::floor),
+            (None, _) => price,
+        }
+    }
+
+    ///
+    /// Redondea la cantidad hacia abajo al paso del mercado
+    ///
+    pub fn round_amount(&self, amount: Decimal) -> Decimal {
+        match self.amount_step {
+            Some(step) => round_to(amount, step, Decimal::floor),
+            None => amount,
+        }
+    }
+
+    ///
+    /// Redondea hacia abajo el monto de una orden instantánea: las compras se indican
+    /// en la moneda de cotización y se redondean a sus decimales, las ventas se
+    /// indican en la moneda base y se redondean al paso del mercado
+    ///
+    pub fn round_instant_amount(&self, amount: Decimal, order_type: &OrderType) -> Decimal {
+        match (order_type, self.quote_decimals) {
+            (OrderType::Buy, Some(decimals)) => amount
+                .round_dp_with_strategy(decimals, RoundingStrategy::ToZero)
+                .normalize(),
+            (OrderType::Buy, None) => amount,
+            _ => self.round_amount(amount),
+        }
+    }
+
+    ///
+    /// Monto redondeado de una orden instantánea, falla con `InvalidArgument` si el
+    /// redondeo lo deja en cero
+    ///
+    pub(crate) fn instant_amount(
+        &self,
+        amount: Decimal,
+        order_type: &OrderType,
+    ) -> CryptoMktResult<Decimal> {
+        let rounded = self.round_instant_amount(amount, order_type);
+        if rounded <= Decimal::ZERO {
+            let message = format!(
+                "instant {} amount {} rounds to zero on {}",
+                order_type, amount, self.name
+            );
+            return Err(
+                CryptoMktError::from(CryptoMktErrorType::InvalidArgument).with_message(&message)
+            );
+        }
+        Ok(rounded)
+    }
+
+    ///
+    /// Límites con los que se validan las órdenes del mercado
+    ///
+    pub fn order_limits(&self) -> OrderLimits {
+        OrderLimits {
+            amount_step: self.amount_step,
+            min_amount: self.min_amount,
+            price_decimals: self.price_tick.map(|tick| tick.normalize().scale()),
+            min_notional: self.min_notional,
+        }
+    }
+
+    ///
+    /// Completa los datos desconocidos con los de otro registro del mismo mercado
+    ///
+    fn merge(&mut self, other: MarketInfo) {
+        if !other.base_currency.is_empty() {
+            self.base_currency = other.base_currency;
+        }
+        if !other.quote_currency.is_empty() {
+            self.quote_currency = other.quote_currency;
+        }
+        self.price_tick = other.price_tick.or(self.price_tick);
+        self.amount_step = other.amount_step.or(self.amount_step);
+        self.min_amount = other.min_amount.or(self.min_amount);
+        self.quote_decimals = other.quote_decimals.or(self.quote_decimals);
+        self.min_notional = other.min_notional.or(self.min_notional);
+    }
+}
+
+///
+/// Redondea el valor a un múltiplo del paso, con la función indicada (floor / ceil)
+///
+fn round_to(value: Decimal, step: Decimal, round: fn(&Decimal) -> Decimal) -> Decimal {
+    if step <= Decimal::ZERO {
+        return value;
+    }
+    (round(&(value / step)) * step)
+        .round_dp(step.normalize().scale())
+        .normalize()
+}
+
+/// Formato de los archivos de mercados
+#[derive(Deserialize)]
+struct MarketsFile {
+    markets: Vec<MarketInfo>,
+}
+
+///
+/// Registro de la información de los mercados, indexado por nombre
+///
+/// ```
+/// extern crate cryptomkt;
+/// use cryptomkt::{Decimal, MarketRegistry, OrderType};
+///
+/// let mut registry = MarketRegistry::bundled();
+/// registry
+///     .merge_toml(
+///         r#"
+///         [[markets]]
+///         name = "ETHCLP"
+///         price_tick = "10"
+///         "#,
+///     )
+///     .unwrap();
+/// let info = registry.get("ETHCLP").unwrap();
+/// assert_eq!(
+///     info.round_price(Decimal::new(250_004, 0), &OrderType::Buy),
+///     Decimal::new(250_000, 0)
+/// );
+/// ```
+///
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MarketRegistry {
+    markets: HashMap<String, MarketInfo>,
+}
+
+impl MarketRegistry {
+    ///
+    /// Registro vacío
+    ///
+    pub fn new() -> Self {
+        MarketRegistry::default()
+    }
+
+    ///
+    /// Registro con los mercados incluidos en el crate, usado por defecto en los clientes
+    ///
+    pub fn bundled() -> Self {
+        MarketRegistry::from_json(BUNDLED_MARKETS).expect("invalid bundled markets file")
+    }
+
+    ///
+    /// Crea el registro desde un JSON `{"markets": [{"name": "ETHCLP", ...}]}`
+    ///
+    pub fn from_json(content: &str) -> CryptoMktResult<Self> {
+        let mut registry = MarketRegistry::new();
+        registry.merge_json(content)?;
+        Ok(registry)
+    }
+
+    ///
+    /// Crea el registro desde un TOML con una lista `[[markets]]`
+    ///
+    pub fn from_toml(content: &str) -> CryptoMktResult<Self> {
+        let mut registry = MarketRegistry::new();
+        registry.merge_toml(content)?;
+        Ok(registry)
+    }
+
+    ///
+    /// Agrega o actualiza los mercados de un JSON, los datos no indicados se mantienen
+    ///
+    pub fn merge_json(&mut self, content: &str) -> CryptoMktResult<()> {
+        let file: MarketsFile = serde_json::from_str(content).map_err(invalid_file)?;
+        self.merge_all(file.markets);
+        Ok(())
+    }
+
+    ///
+    /// Agrega o actualiza los mercados de un TOML, los datos no indicados se mantienen
+    ///
+    pub fn merge_toml(&mut self, content: &str) -> CryptoMktResult<()> {
+        let file: MarketsFile = toml::from_str(content).map_err(invalid_file)?;
+        self.merge_all(file.markets);
+        Ok(())
+    }
+
+    ///
+    /// Agrega o actualiza los mercados de un archivo `.json` o `.toml`
+    ///
+    /// Argumentos
+    ///     path: Ruta del archivo
+    ///
+    pub fn merge_file<P: AsRef<Path>>(&mut self, path: P) -> CryptoMktResult<()> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path).map_err(|e| {
+            CryptoMktError::from(CryptoMktErrorType::InvalidConfiguration)
+                .with_message(&format!("cannot read {}", path.display()))
+                .with_source(e)
+        })?;
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("toml") => self.merge_toml(&content),
+            _ => self.merge_json(&content),
+        }
+    }
+
+    ///
+    /// Información del mercado, `None` si no está registrado
+    ///
+    pub fn get(&self, name: &str) -> Option<&MarketInfo> {
+        self.markets.get(&name.to_uppercase())
+    }
+
+    ///
+    /// Información del mercado, o una sin límites si no está registrado
+    ///
+    pub fn get_or_default(&self, name: &str) -> MarketInfo {
+        self.get(name)
+            .cloned()
+            .unwrap_or_else(|| MarketInfo::new(name))
+    }
+
+    ///
+    /// Agrega o reemplaza la información de un mercado
+    ///
+    pub fn insert(&mut self, info: MarketInfo) {
+        self.markets.insert(info.name.to_uppercase(), info);
+    }
+
+    ///
+    /// Nombres de los mercados registrados, ordenados
+    ///
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.markets.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    ///
+    /// Registra los mercados listados por el exchange que aún no se conocen,
+    /// conservando los límites de los mercados ya registrados
+    ///
+    /// Argumentos
+    ///     names: Nombres de los mercados (Ej. respuesta de `market`)
+    ///
+    pub fn refresh<S: AsRef<str>>(&mut self, names: &[S]) {
+        for name in names {
+            let name = name.as_ref();
+            if self.get(name).is_none() {
+                self.insert(MarketInfo::new(name));
+            }
+        }
+    }
+
+    fn merge_all(&mut self, markets: Vec<MarketInfo>) {
+        for mut info in markets {
+            let key = info.name.to_uppercase();
+            match self.markets.get_mut(&key) {
+                Some(current) => current.merge(info),
+                None => {
+                    let defaults = MarketInfo::new(&info.name);
+                    if info.base_currency.is_empty() {
+                        info.base_currency = defaults.base_currency;
+                    }
+                    if info.quote_currency.is_empty() {
+                        info.quote_currency = defaults.quote_currency;
+                    }
+                    self.markets.insert(key, info);
+                }
+            }
+        }
+    }
+}
+
+fn invalid_file<E>(error: E) -> CryptoMktError
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    CryptoMktError::from(CryptoMktErrorType::InvalidConfiguration)
+        .with_message("invalid markets file")
+        .with_source(error)
+}
@@ -5,28 +5,70 @@
 //! al API junto con sus respectivas pruebas
 //!
 pub mod api;
+pub mod dates;
 pub mod errors;
+pub mod markets;
 pub mod models;
+pub mod order;
+pub mod order_book;
+pub mod pagination;
+pub mod payment;
+pub mod rate_limit;
 pub mod request;
 pub mod response;
+pub mod retry;
+pub mod slippage;
+#[cfg(feature = "websocket")]
+pub mod socket;
+#[cfg(feature = "websocket")]
+pub mod streams;
+pub mod tracker;
+pub mod watcher;
+pub mod webhook;
 
 #[cfg(test)]
 mod tests {
     use crate::internal::api::Api;
-    use crate::internal::errors::CryptoMktResult;
-    use crate::internal::request::HttpRequest;
+    use crate::internal::errors::{CryptoMktError, CryptoMktErrorType, CryptoMktResult};
+    use std::error::Error;
+    #[cfg(feature = "blocking")]
+    use crate::internal::request::CryptoMktRequest;
+    use crate::internal::request::{AsyncHttpRequest, HttpRequest};
+    use crate::internal::rate_limit::{Quota, RateLimitMode, RateLimitScope, RateLimiter};
+    use crate::internal::retry::{retry_after, RetryPolicy};
+    use async_trait::async_trait;
+    use rust_decimal::Decimal;
     use serde_json::json;
 
     use reqwest::header::HeaderMap;
     use reqwest::Url;
     use std::collections::HashMap;
+    #[cfg(any(feature = "blocking", feature = "webhook-server"))]
+    use std::io::{Read, Write};
+    #[cfg(any(feature = "blocking", feature = "webhook-server"))]
+    use std::net::TcpListener;
+    use std::sync::Arc;
+    #[cfg(any(feature = "blocking", feature = "webhook-server"))]
+    use std::thread;
+    use std::time::Duration;
 
     use crate::response::{
         BalanceResponse, BookResponse, EmptyResponse, MarketResponse, OrderResponse,
         OrdersInstantResponse, SimpleOrderResponse, TickerResponse, TradeResponse,
     };
-    const API_KEY: &'static str = "FS24FJ7";
-    const SECRET_KEY: &'static str = "SFT23GSD";
+    const API_KEY: &str = "FS24FJ7";
+    const SECRET_KEY: &str = "SFT23GSD";
+
+    ///
+    /// Fecha esperada, leída igual que en los modelos
+    ///
+    fn timestamp(value: &serde_json::Value) -> crate::Timestamp {
+        crate::internal::dates::timestamp::deserialize(value.clone()).unwrap()
+    }
+
+    fn optional_timestamp(value: &serde_json::Value) -> Option<crate::Timestamp> {
+        crate::internal::dates::timestamp::option(value.clone()).unwrap()
+    }
 
     ///
     ///
@@ -41,7 +83,7 @@ mod tests {
         ///
         /// Inicializador de la clase
         ///
-        pub fn new<'m>(resp_for_get: &'m str, resp_for_post: &'m str) -> Self {
+        pub fn new(resp_for_get: &str, resp_for_post: &str) -> Self {
             MockRequest {
                 resp_for_get: resp_for_get.to_string(),
                 resp_for_post: resp_for_post.to_string(),
@@ -75,6 +117,395 @@ mod tests {
             Ok(self.resp_for_post.clone())
         }
     }
+
+    #[async_trait]
+    impl AsyncHttpRequest for MockRequest {
+        async fn get(&self, _url: Url, _headers: HeaderMap) -> CryptoMktResult<String> {
+            Ok(self.resp_for_get.clone())
+        }
+
+        async fn post(
+            &self,
+            _url: Url,
+            _headers: HeaderMap,
+            _payload: HashMap<String, String>,
+        ) -> CryptoMktResult<String> {
+            Ok(self.resp_for_post.clone())
+        }
+    }
+
+    ///
+    /// Transporte que responde según el parámetro `page` de la URL y registra
+    /// las páginas solicitadas y los parámetros de los POST. Las páginas sin
+    /// respuesta devuelven `BadRequest`
+    ///
+    #[derive(Clone, Default)]
+    struct PagedMock {
+        pages: Arc<HashMap<u32, String>>,
+        requested: Arc<std::sync::Mutex<Vec<u32>>>,
+        paths: Arc<std::sync::Mutex<Vec<String>>>,
+        payloads: Arc<std::sync::Mutex<Vec<HashMap<String, String>>>>,
+    }
+
+    impl PagedMock {
+        fn new(pages: Vec<(u32, &str)>) -> Self {
+            PagedMock {
+                pages: Arc::new(
+                    pages
+                        .into_iter()
+                        .map(|(page, body)| (page, body.to_string()))
+                        .collect(),
+                ),
+                requested: Arc::default(),
+                paths: Arc::default(),
+                payloads: Arc::default(),
+            }
+        }
+
+        fn requested(&self) -> Vec<u32> {
+            self.requested.lock().unwrap().clone()
+        }
+
+        fn paths(&self) -> Vec<String> {
+            self.paths.lock().unwrap().clone()
+        }
+
+        #[cfg(feature = "blocking")]
+        fn payloads(&self) -> Vec<HashMap<String, String>> {
+            self.payloads.lock().unwrap().clone()
+        }
+
+        fn respond(&self, url: &Url) -> CryptoMktResult<String> {
+            let page = url
+                .query_pairs()
+                .find(|(key, _)| key == "page")
+                .and_then(|(_, value)| value.parse().ok())
+                .unwrap_or(0);
+            self.requested.lock().unwrap().push(page);
+            self.paths.lock().unwrap().push(url.path().to_string());
+            self.pages
+                .get(&page)
+                .cloned()
+                .ok_or_else(|| CryptoMktErrorType::BadRequest.into())
+        }
+    }
+
+    impl HttpRequest for PagedMock {
+        type Result = CryptoMktResult<String>;
+
+        fn get(&self, url: Url, _headers: HeaderMap) -> CryptoMktResult<String> {
+            self.respond(&url)
+        }
+
+        fn post(
+            &self,
+            url: Url,
+            _headers: HeaderMap,
+            payload: HashMap<String, String>,
+        ) -> CryptoMktResult<String> {
+            self.payloads.lock().unwrap().push(payload);
+            self.respond(&url)
+        }
+    }
+
+    #[async_trait]
+    impl AsyncHttpRequest for PagedMock {
+        async fn get(&self, url: Url, _headers: HeaderMap) -> CryptoMktResult<String> {
+            self.respond(&url)
+        }
+
+        async fn post(
+            &self,
+            url: Url,
+            _headers: HeaderMap,
+            payload: HashMap<String, String>,
+        ) -> CryptoMktResult<String> {
+            self.payloads.lock().unwrap().push(payload);
+            self.respond(&url)
+        }
+    }
+
+    ///
+    /// Transporte que responde a cada `id` (de la URL o de los parámetros del POST)
+    /// con una secuencia de respuestas, la última respuesta se repite
+    ///
+    type MockResponse = Result<String, CryptoMktErrorType>;
+
+    #[derive(Clone, Default)]
+    struct SequenceMock {
+        responses: Arc<std::sync::Mutex<HashMap<String, Vec<MockResponse>>>>,
+        requests: Arc<std::sync::Mutex<Vec<String>>>,
+        amounts: Arc<std::sync::Mutex<Vec<String>>>,
+    }
+
+    impl SequenceMock {
+        fn respond_with(self, id: &str, responses: Vec<MockResponse>) -> Self {
+            self.responses.lock().unwrap().insert(id.to_string(), responses);
+            self
+        }
+
+        fn requests(&self) -> Vec<String> {
+            self.requests.lock().unwrap().clone()
+        }
+
+        ///
+        /// Parámetro `amount` de las peticiones que lo indican, en orden
+        ///
+        #[cfg(feature = "blocking")]
+        fn amounts(&self) -> Vec<String> {
+            self.amounts.lock().unwrap().clone()
+        }
+
+        fn respond(
+            &self,
+            url: &Url,
+            payload: Option<&HashMap<String, String>>,
+        ) -> CryptoMktResult<String> {
+            let id = url
+                .query_pairs()
+                .find(|(key, _)| key == "id")
+                .map(|(_, value)| value.to_string())
+                .or_else(|| payload.and_then(|payload| payload.get("id").cloned()))
+                .unwrap_or_default();
+            self.requests.lock().unwrap().push(id.clone());
+            let amount = url
+                .query_pairs()
+                .find(|(key, _)| key == "amount")
+                .map(|(_, value)| value.to_string())
+                .or_else(|| payload.and_then(|payload| payload.get("amount").cloned()));
+            self.amounts.lock().unwrap().extend(amount);
+            let mut responses = self.responses.lock().unwrap();
+            let queue = responses
+                .get_mut(&id)
+                .ok_or_else(|| CryptoMktError::from(CryptoMktErrorType::RequestNotFound))?;
+            let response = if queue.len() > 1 {
+                queue.remove(0)
+            } else {
+                queue[0].clone()
+            };
+            response.map_err(CryptoMktError::from)
+        }
+    }
+
+    impl HttpRequest for SequenceMock {
+        type Result = CryptoMktResult<String>;
+
+        fn get(&self, url: Url, _headers: HeaderMap) -> CryptoMktResult<String> {
+            self.respond(&url, None)
+        }
+
+        fn post(
+            &self,
+            url: Url,
+            _headers: HeaderMap,
+            payload: HashMap<String, String>,
+        ) -> CryptoMktResult<String> {
+            self.respond(&url, Some(&payload))
+        }
+    }
+
+    #[async_trait]
+    impl AsyncHttpRequest for SequenceMock {
+        async fn get(&self, url: Url, _headers: HeaderMap) -> CryptoMktResult<String> {
+            self.respond(&url, None)
+        }
+
+        async fn post(
+            &self,
+            url: Url,
+            _headers: HeaderMap,
+            payload: HashMap<String, String>,
+        ) -> CryptoMktResult<String> {
+            self.respond(&url, Some(&payload))
+        }
+    }
+
+    ///
+    /// Respuesta de `payment/status` con el estado indicado
+    ///
+    fn payment_status(id: i32, status: i32) -> MockResponse {
+        Ok(json!({
+            "status": "success",
+            "data": {
+                "id": id, "external_id": format!("ORDER-{}", id), "status": status,
+                "to_receive": "1000", "to_receive_currency": "CLP", "expected_amount": "0.001",
+                "expected_currency": "ETH", "deposit_address": "", "refund_email": "", "qr": "",
+                "obs": "", "callback_url": "", "error_url": "", "success_url": "", "payment_url": "",
+                "created_at": "2018-04-23T21:09:15", "updated_at": "2018-04-23T21:09:15"
+            }
+        })
+        .to_string())
+    }
+
+    fn fast_watch() -> crate::WatchOptions {
+        crate::WatchOptions::default()
+            .initial_interval(Duration::from_millis(1))
+            .max_interval(Duration::from_millis(4))
+            .max_errors(2)
+    }
+
+    ///
+    /// Orden límite de ETHCLP con el estado y las cantidades indicadas
+    ///
+    fn order_json(id: &str, status: &str, amounts: (&str, &str, &str), avg: &str) -> serde_json::Value {
+        let (original, remaining, executed) = amounts;
+        json!({
+            "id": id, "status": status, "type": "buy", "price": "100", "market": "ETHCLP",
+            "amount": {"original": original, "remaining": remaining, "executed": executed},
+            "execution_price": null, "avg_execution_price": avg,
+            "created_at": "2017-09-01T14:01:56.887272", "updated_at": "2017-09-01T14:01:56.887272"
+        })
+    }
+
+    ///
+    /// Respuesta de `orders/status`
+    ///
+    fn order_status(id: &str, status: &str, amounts: (&str, &str, &str), avg: &str) -> MockResponse {
+        Ok(json!({"status": "success", "data": order_json(id, status, amounts, avg)}).to_string())
+    }
+
+    ///
+    /// Respuesta de `orders/executed`
+    ///
+    fn executed_orders(orders: Vec<serde_json::Value>) -> MockResponse {
+        Ok(json!({
+            "status": "success",
+            "pagination": {"previous": null, "limit": 100, "page": 0, "next": null},
+            "data": orders
+        })
+        .to_string())
+    }
+
+    ///
+    /// Página de `orders/active` con las órdenes `(id, tipo)` indicadas
+    ///
+    fn active_orders(orders: &[(&str, &str)], page: u32, next: Option<u32>) -> MockResponse {
+        let data: Vec<serde_json::Value> = orders
+            .iter()
+            .map(|(id, order_type)| {
+                let mut order = order_json(id, "active", ("1", "1", "0"), "0");
+                order["type"] = json!(order_type);
+                order
+            })
+            .collect();
+        Ok(json!({
+            "status": "success",
+            "pagination": {"previous": null, "limit": 100, "page": page, "next": next},
+            "data": data
+        })
+        .to_string())
+    }
+
+    ///
+    /// Página de `book` con los niveles `(precio, cantidad)` indicados
+    ///
+    #[cfg(feature = "blocking")]
+    fn book_page(levels: &[(&str, &str)], page: u32, next: Option<u32>) -> MockResponse {
+        let data: Vec<serde_json::Value> = levels
+            .iter()
+            .map(|(price, amount)| {
+                json!({"price": price, "amount": amount, "timestamp": "2017-08-31T12:31:58.782060"})
+            })
+            .collect();
+        Ok(json!({
+            "status": "success",
+            "pagination": {"previous": null, "limit": 100, "page": page, "next": next},
+            "data": data
+        })
+        .to_string())
+    }
+
+    ///
+    /// Respuestas de `orders/instant/get` y `ticker` para estimar una orden instantánea
+    ///
+    fn instant_quote(obtained: &str, required: &str) -> Vec<MockResponse> {
+        vec![
+            Ok(json!({"status": "success", "data": {"obtained": obtained, "required": required}})
+                .to_string()),
+            Ok(json!({"status": "success", "data": [{
+                "high": "110", "low": "90", "ask": "101", "bid": "99", "last_price": "100",
+                "volume": "10", "timestamp": "2017-08-29T15:44:17.267526", "market": "ETHCLP"
+            }]})
+            .to_string()),
+        ]
+    }
+
+    ///
+    /// Descripción de un evento de órdenes para comparar en las pruebas
+    ///
+    fn order_event_name(event: &crate::OrderEvent) -> String {
+        use crate::OrderEvent;
+        match event {
+            OrderEvent::Fill { fill, .. } => format!(
+                "fill({}, {:?}, {})",
+                fill.amount,
+                fill.price.map(|price| price.to_string()),
+                fill.remaining
+            ),
+            OrderEvent::Executed(_) => "executed".to_string(),
+            OrderEvent::Cancelled(_) => "cancelled".to_string(),
+            OrderEvent::Error { error, terminal, .. } => {
+                format!("error({}, {})", error.kind(), terminal)
+            }
+        }
+    }
+
+    ///
+    /// Página de trades con los precios indicados y la página siguiente
+    ///
+    fn trades_page(prices: &[u32], page: u32, next: Option<u32>) -> String {
+        let data: Vec<serde_json::Value> = prices
+            .iter()
+            .map(|price| {
+                json!({
+                    "market_taker": "buy", "price": price.to_string(), "amount": "1",
+                    "tid": "", "timestamp": "2017-08-29T15:44:17", "market": "ETHCLP"
+                })
+            })
+            .collect();
+        json!({
+            "status": "success",
+            "pagination": {"page": page, "limit": prices.len(), "previous": null, "next": next},
+            "data": data
+        })
+        .to_string()
+    }
+
+    fn paged_trades() -> PagedMock {
+        PagedMock::new(vec![
+            (0, &trades_page(&[1, 2], 0, Some(1))),
+            (1, &trades_page(&[3, 4], 1, Some(2))),
+            (2, &trades_page(&[5], 2, None)),
+        ])
+    }
+
+    ///
+    /// Páginas de órdenes de pago con los ids indicados
+    ///
+    fn paged_payments() -> PagedMock {
+        let page = |ids: &[u32], page: u32, next: Option<u32>| {
+            let data: Vec<serde_json::Value> = ids
+                .iter()
+                .map(|id| {
+                    json!({
+                        "id": id, "external_id": "", "status": "0", "to_receive": "1000",
+                        "to_receive_currency": "CLP", "expected_amount": "0.001",
+                        "expected_currency": "ETH", "deposit_address": "", "refund_email": "",
+                        "qr": "", "obs": "", "callback_url": "", "error_url": "",
+                        "success_url": "", "payment_url": "",
+                        "created_at": "2018-05-15T21:09:15", "updated_at": "2018-05-15T21:09:15"
+                    })
+                })
+                .collect();
+            json!({
+                "status": "success",
+                "pagination": {"page": page, "limit": 2, "previous": null, "next": next},
+                "data": data
+            })
+            .to_string()
+        };
+        PagedMock::new(vec![(0, &page(&[1, 2], 0, Some(1))), (1, &page(&[3], 1, None))])
+    }
     ///
     /// Configura la instancia de API para los diferentes Test que no requieren de
     /// una respuesta
@@ -104,11 +535,67 @@ mod tests {
         );
     }
 
+    #[test]
+    fn build_url_with_custom_domain_and_version() {
+        let mut api = setup_test();
+        api.set_domain("http://localhost:8080/mock").unwrap();
+        api.set_api_version("/v2/");
+        assert_eq!(api.domain(), "http://localhost:8080/mock/");
+        assert_eq!(api.api_version(), "v2");
+        assert_eq!(
+            api.build_url("market", &HashMap::new()).as_str(),
+            "http://localhost:8080/mock/v2/market"
+        );
+    }
+
+    #[test]
+    fn build_url_without_version() {
+        let mut api = setup_test();
+        api.set_api_version("");
+        assert_eq!(
+            api.build_url("market", &HashMap::new()).as_str(),
+            "https://api.cryptomkt.com/market"
+        );
+        let resp = api.build_signature_format("orders/active", &HashMap::new(), true);
+        assert!(resp.ends_with("/orders/active"));
+        assert!(!resp.contains("/v1/"));
+    }
+
+    #[test]
+    fn set_invalid_domain() {
+        let mut api = setup_test();
+        assert!(api.set_domain("not a url").is_err());
+        assert_eq!(api.domain(), "https://api.cryptomkt.com/");
+    }
+
+    #[test]
+    fn build_api_signature_format_with_custom_version() {
+        let mut api = setup_test();
+        api.set_api_version("v2");
+        let resp = api.build_signature_format("orders/active", &HashMap::new(), true);
+        assert!(resp.ends_with("/v2/orders/active"));
+    }
+
+    #[test]
+    fn build_headers_with_default_headers() {
+        let mut api = setup_test();
+        api.add_default_header("User-Agent", "cryptomkt-rs").unwrap();
+        assert!(api.add_default_header("Bad Header", "value").is_err());
+
+        let headers = api.build_headers("market", &HashMap::new(), true, true);
+        assert_eq!(headers.len(), 1);
+        assert_eq!(headers["User-Agent"], "cryptomkt-rs");
+
+        let headers = api.build_headers("balance", &HashMap::new(), false, true);
+        assert_eq!(headers.len(), 4);
+        assert_eq!(headers["X-MKT-APIKEY"], API_KEY);
+    }
+
     #[test]
     fn build_api_signature_format() {
         let api = setup_test();
         let resp = api.build_signature_format("orders/active", &HashMap::new(), true);
-        assert_eq!(resp.ends_with("/v1/orders/active"), true);
+        assert!(resp.ends_with("/v1/orders/active"));
     }
 
     #[test]
@@ -121,7 +608,7 @@ mod tests {
         params.insert("type".to_string(), "buy".to_string());
         let resp = api.build_signature_format("orders/create", &params, true);
 
-        assert_eq!(resp.ends_with("/v1/orders/create"), true);
+        assert!(resp.ends_with("/v1/orders/create"));
     }
 
     #[test]
@@ -203,7 +690,7 @@ mod tests {
 
         for it in 1..resp.data.len() {
             assert_eq!(
-                resp.data[it].high, expected["data"][it]["high"],
+                json!(resp.data[it].high), expected["data"][it]["high"],
                 "Los mercados no coinciden: Valor respondido {:?} Valor esperado: {:?}",
                 resp, expected
             );
@@ -240,7 +727,7 @@ mod tests {
 
         for it in 1..resp.data.len() {
             assert_eq!(
-                resp.data[it].timestamp, expected["data"][it]["timestamp"],
+                resp.data[it].timestamp, timestamp(&expected["data"][it]["timestamp"]),
                 "Los datos no coinciden: Valor respondido {:?} Valor esperado: {:?}",
                 resp, expected
             );
@@ -286,12 +773,12 @@ mod tests {
 
         for it in 1..resp.data.len() {
             assert_eq!(
-                resp.data[it].market_taker, expected["data"][it]["market_taker"],
+                json!(resp.data[it].market_taker), expected["data"][it]["market_taker"],
                 "Los datos no coinciden: Valor respondido {:?} Valor esperado: {:?}",
                 resp, expected
             );
             assert_eq!(
-                resp.data[it].timestamp, expected["data"][it]["timestamp"],
+                resp.data[it].timestamp, timestamp(&expected["data"][it]["timestamp"]),
                 "Los datos no coinciden: Valor respondido {:?} Valor esperado: {:?}",
                 resp, expected
             );
@@ -341,7 +828,7 @@ mod tests {
 
         for it in 1..resp.data.len() {
             assert_eq!(
-                resp.data[it].created_at, expected["data"][it]["created_at"],
+                resp.data[it].created_at, optional_timestamp(&expected["data"][it]["created_at"]),
                 "Los datos no coinciden: Valor respondido {:?} Valor esperado: {:?}",
                 resp, expected
             );
@@ -386,17 +873,17 @@ mod tests {
 
         for it in 1..resp.data.len() {
             assert_eq!(
-                resp.data[it].created_at, expected["data"][it]["created_at"],
+                resp.data[it].created_at, optional_timestamp(&expected["data"][it]["created_at"]),
                 "Los datos no coinciden: Valor respondido {:?} Valor esperado: {:?}",
                 resp, expected
             );
             assert_eq!(
-                resp.data[it].status, expected["data"][it]["status"],
+                json!(resp.data[it].status), expected["data"][it]["status"],
                 "Los datos no coinciden: Valor respondido {:?} Valor esperado: {:?}",
                 resp, expected
             );
             assert_eq!(
-                resp.data[it].execution_price, expected["data"][it]["execution_price"],
+                json!(resp.data[it].execution_price), expected["data"][it]["execution_price"],
                 "Los datos no coinciden: Valor respondido {:?} Valor esperado: {:?}",
                 resp, expected
             );
@@ -444,17 +931,17 @@ mod tests {
         );
 
         assert_eq!(
-            resp.data.created_at, expected["data"]["created_at"],
+            resp.data.created_at, optional_timestamp(&expected["data"]["created_at"]),
             "Los datos no coinciden: Valor respondido {:?} Valor esperado: {:?}",
             resp, expected
         );
         assert_eq!(
-            resp.data.status, expected["data"]["status"],
+            json!(resp.data.status), expected["data"]["status"],
             "Los datos no coinciden: Valor respondido {:?} Valor esperado: {:?}",
             resp, expected
         );
         assert_eq!(
-            resp.data.execution_price, expected["data"]["execution_price"],
+            json!(resp.data.execution_price), expected["data"]["execution_price"],
             "Los datos no coinciden: Valor respondido {:?} Valor esperado: {:?}",
             resp, expected
         );
@@ -485,17 +972,17 @@ mod tests {
             resp, expected
         );
         assert_eq!(
-            resp.data.created_at, expected["data"]["created_at"],
+            resp.data.created_at, optional_timestamp(&expected["data"]["created_at"]),
             "Los datos no coinciden: Valor respondido {:?} Valor esperado: {:?}",
             resp, expected
         );
         assert_eq!(
-            resp.data.status, expected["data"]["status"],
+            json!(resp.data.status), expected["data"]["status"],
             "Los datos no coinciden: Valor respondido {:?} Valor esperado: {:?}",
             resp, expected
         );
         assert_eq!(
-            resp.data.execution_price, expected["data"]["execution_price"],
+            json!(resp.data.execution_price), expected["data"]["execution_price"],
             "Los datos no coinciden: Valor respondido {:?} Valor esperado: {:?}",
             resp, expected
         );
@@ -529,17 +1016,17 @@ mod tests {
             resp, expected
         );
         assert_eq!(
-            resp.data.created_at, expected["data"]["created_at"],
+            resp.data.created_at, optional_timestamp(&expected["data"]["created_at"]),
             "Los datos no coinciden: Valor respondido {:?} Valor esperado: {:?}",
             resp, expected
         );
         assert_eq!(
-            resp.data.status, expected["data"]["status"],
+            json!(resp.data.status), expected["data"]["status"],
             "Los datos no coinciden: Valor respondido {:?} Valor esperado: {:?}",
             resp, expected
         );
         assert_eq!(
-            resp.data.execution_price, expected["data"]["execution_price"],
+            json!(resp.data.execution_price), expected["data"]["execution_price"],
             "Los datos no coinciden: Valor respondido {:?} Valor esperado: {:?}",
             resp, expected
         );
@@ -572,12 +1059,12 @@ mod tests {
             resp, expected
         );
         assert_eq!(
-            resp.data.obtained, expected["data"]["obtained"],
+            json!(resp.data.obtained), expected["data"]["obtained"],
             "Los datos no coinciden: Valor respondido {:?} Valor esperado: {:?}",
             resp, expected
         );
         assert_eq!(
-            resp.data.required, expected["data"]["required"],
+            json!(resp.data.required), expected["data"]["required"],
             "Los datos no coinciden: Valor respondido {:?} Valor esperado: {:?}",
             resp, expected
         );
@@ -631,7 +1118,7 @@ mod tests {
 
         for it in 1..resp.data.len() {
             assert_eq!(
-                resp.data[it].available, expected["data"][it]["available"],
+                json!(resp.data[it].available), expected["data"][it]["available"],
                 "Los datos no coinciden: Valor respondido {:?} Valor esperado: {:?}",
                 resp, expected
             );
@@ -641,10 +1128,2364 @@ mod tests {
                 resp, expected
             );
             assert_eq!(
-                resp.data[it].balance, expected["data"][it]["balance"],
+                json!(resp.data[it].balance), expected["data"][it]["balance"],
                 "Los datos no coinciden: Valor respondido {:?} Valor esperado: {:?}",
                 resp, expected
             );
         }
     }
+
+    #[tokio::test]
+    async fn test_async_response_for_ticker() {
+        let mock_transport = MockRequest::new(
+            "{\"status\":\"success\",\"data\":[{\"high\":\"6888\",\"volume\":\"13.03\",\"low\":\"6303\",\"ask\":\"6887\",\"timestamp\":\"2017-08-2915:44:17.267526\",\"bid\":\"6416\",\"last_price\":\"6610\",\"market\":\"ETHARS\"}]}",
+            "",
+        );
+        let api = Api::<MockRequest>::new(API_KEY, SECRET_KEY, Box::new(mock_transport));
+
+        let mut ticker_params = HashMap::new();
+        ticker_params.insert("market".to_string(), "ETHARS".to_string());
+        let resp = api
+            .get_edge_async::<TickerResponse>("ticker", ticker_params, true)
+            .await
+            .unwrap();
+
+        assert_eq!(resp.status, "success");
+        assert_eq!(resp.data[0].high, Decimal::new(6888, 0));
+        assert_eq!(resp.data[0].market, "ETHARS");
+    }
+
+    #[tokio::test]
+    async fn test_async_response_for_orders_cancel() {
+        let mock_transport = MockRequest::new(
+            "",
+            "{\"status\":\"success\",\"data\":{\"status\":\"cancelled\",\"created_at\":\"2017-09-01T14:02:36.386967\",\"amount\":{\"executed\":\"0\",\"original\":\"1.25\"},\"avg_execution_price\":\"0\",\"price\":\"8000\",\"type\":\"buy\",\"id\":\"M103967\",\"market\":\"ETHCLP\",\"updated_at\":\"2017-09-01T14:02:36.386967\"}}"
+        );
+        let api = Api::<MockRequest>::new(API_KEY, SECRET_KEY, Box::new(mock_transport));
+
+        let mut params = HashMap::new();
+        params.insert("id".to_string(), "M103967".to_string());
+
+        let resp = api
+            .post_edge_async::<SimpleOrderResponse>("orders/cancel", params)
+            .await
+            .unwrap();
+
+        assert_eq!(resp.status, "success");
+        assert_eq!(resp.data.id, "M103967");
+        assert_eq!(
+            resp.data.status,
+            crate::OrderState::Other("cancelled".to_string())
+        );
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_client_with_custom_transport() {
+        let mock_transport = MockRequest::new(
+            "{\"status\":\"success\",\"data\":[{\"available\":\"120347\",\"wallet\":\"CLP\",\"balance\":\"120347\"}]}",
+            "",
+        );
+        let client =
+            crate::CryptoMktClient::with_transport(API_KEY, SECRET_KEY, Box::new(mock_transport));
+
+        let balance = client.get_balance().unwrap();
+        assert_eq!(balance.len(), 1);
+        assert_eq!(balance[0].wallet, "CLP");
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_client_get_markets() {
+        let client = crate::CryptoMktClient::with_transport(
+            API_KEY,
+            SECRET_KEY,
+            Box::new(MockRequest::new(
+                "{\"status\":\"success\",\"data\":[\"ETHCLP\",\"BTCARS\",\"XLMEUR\"]}",
+                "",
+            )),
+        );
+        let markets = client.get_markets().unwrap();
+        assert_eq!(markets.len(), 3);
+        assert_eq!(markets[0].get_name(), "ETHCLP");
+        assert_eq!(markets[0].get_base_currency(), "ETH");
+        assert_eq!(markets[0].get_quote_currency(), "CLP");
+        assert_eq!(markets[2].get_base_currency(), "XLM");
+        assert_eq!(markets[2].get_quote_currency(), "EUR");
+
+        let client = crate::CryptoMktClient::with_transport(
+            API_KEY,
+            SECRET_KEY,
+            Box::new(MockRequest::new("not json", "")),
+        );
+        assert_eq!(
+            client.get_markets().unwrap_err().kind(),
+            CryptoMktErrorType::MalformedResource
+        );
+    }
+
+    #[tokio::test]
+    async fn test_async_client_get_markets() {
+        let client = crate::AsyncCryptoMktClient::with_transport(
+            API_KEY,
+            SECRET_KEY,
+            Box::new(MockRequest::new(
+                "{\"status\":\"success\",\"data\":[\"BTCUSDT\"]}",
+                "",
+            )),
+        );
+        let markets = client.get_markets().await.unwrap();
+        assert_eq!(markets[0].get_base_currency(), "BTC");
+        assert_eq!(markets[0].get_quote_currency(), "USDT");
+    }
+
+    #[test]
+    fn models_decimals_from_strings_and_numbers() {
+        use crate::internal::models::{Balance, Order};
+        use std::str::FromStr;
+
+        let balance: Balance = serde_json::from_str(
+            "{\"wallet\":\"BTC\",\"available\":\"0.00000001\",\"balance\":12345678901.25}",
+        )
+        .unwrap();
+        assert_eq!(balance.available, Decimal::new(1, 8));
+        assert_eq!(balance.balance, Decimal::from_str("12345678901.25").unwrap());
+
+        let order: Order = serde_json::from_str(
+            "{\"type\":\"buy\",\"price\":\"\",\"execution_price\":null,\"amount\":{\"original\":1.5,\"remaining\":\"0.3\"}}",
+        )
+        .unwrap();
+        assert_eq!(order.price, Decimal::new(0, 0));
+        assert_eq!(order.execution_price, None);
+        assert_eq!(order.amount.original, Decimal::new(15, 1));
+        assert_eq!(order.amount.remaining, Some(Decimal::new(3, 1)));
+        assert_eq!(order.amount.executed, None);
+
+        let invalid = serde_json::from_str::<Order>(
+            "{\"type\":\"buy\",\"price\":\"abc\",\"amount\":{}}",
+        );
+        assert!(invalid.is_err());
+    }
+
+    #[test]
+    fn decimal_from_f32_keeps_printed_digits() {
+        use crate::market::decimal_from_f32;
+        assert_eq!(decimal_from_f32(0.1).unwrap().to_string(), "0.1");
+        assert_eq!(decimal_from_f32(159.0).unwrap(), Decimal::new(159, 0));
+        assert_eq!(
+            decimal_from_f32(f32::NAN).unwrap_err().kind(),
+            CryptoMktErrorType::BadRequest
+        );
+    }
+
+    #[cfg(not(feature = "chrono"))]
+    #[test]
+    fn query_dates_are_sent_as_given() {
+        use crate::internal::dates::{QueryDate, PAYMENT_DATE_FORMAT, TRADES_DATE_FORMAT};
+        assert_eq!(
+            "2018-05-15".to_query_date(TRADES_DATE_FORMAT).unwrap(),
+            "2018-05-15"
+        );
+        assert_eq!(
+            "15/05/2018".to_string().to_query_date(PAYMENT_DATE_FORMAT).unwrap(),
+            "15/05/2018"
+        );
+        assert!(" ".to_query_date(TRADES_DATE_FORMAT).is_err());
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn query_dates_are_formatted_for_the_endpoint() {
+        use crate::internal::dates::{QueryDate, PAYMENT_DATE_FORMAT, TRADES_DATE_FORMAT};
+        use chrono::{FixedOffset, NaiveDate, TimeZone, Utc};
+
+        let date = Utc.with_ymd_and_hms(2018, 5, 15, 23, 30, 0).unwrap();
+        assert_eq!(date.to_query_date(TRADES_DATE_FORMAT).unwrap(), "2018-05-15");
+        assert_eq!(date.to_query_date(PAYMENT_DATE_FORMAT).unwrap(), "15/05/2018");
+
+        // Las fechas con zona horaria se convierten a UTC
+        let santiago = FixedOffset::west_opt(4 * 3600).unwrap();
+        let local = santiago.with_ymd_and_hms(2018, 5, 15, 22, 0, 0).unwrap();
+        assert_eq!(local.to_query_date(TRADES_DATE_FORMAT).unwrap(), "2018-05-16");
+
+        let day = NaiveDate::from_ymd_opt(2018, 5, 15).unwrap();
+        assert_eq!(day.to_query_date(PAYMENT_DATE_FORMAT).unwrap(), "15/05/2018");
+
+        assert_eq!(
+            "2018-05-15".to_query_date(PAYMENT_DATE_FORMAT).unwrap(),
+            "15/05/2018"
+        );
+        assert_eq!(
+            "2018-05-15T10:00:00Z".to_query_date(TRADES_DATE_FORMAT).unwrap(),
+            "2018-05-15"
+        );
+        let error = "15 de mayo".to_query_date(TRADES_DATE_FORMAT).unwrap_err();
+        assert_eq!(error.kind(), CryptoMktErrorType::BadRequest);
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn model_timestamps_are_parsed_as_utc() {
+        use crate::internal::dates::parse_timestamp;
+        use crate::internal::models::Order;
+        use chrono::{TimeZone, Timelike, Utc};
+
+        let order: Order = serde_json::from_str(
+            "{\"type\":\"buy\",\"amount\":{},\"created_at\":\"2017-08-31T21:37:42.282102\",\"updated_at\":\"\",\"executed_at\":null}",
+        )
+        .unwrap();
+        let created_at = order.created_at.unwrap();
+        assert_eq!(created_at.with_nanosecond(0).unwrap(), Utc.with_ymd_and_hms(2017, 8, 31, 21, 37, 42).unwrap());
+        assert_eq!(created_at.nanosecond(), 282_102_000);
+        assert_eq!(order.updated_at, None);
+        assert_eq!(order.executed_at, None);
+
+        assert_eq!(
+            parse_timestamp("2018-04-23T21:09:15-03:00"),
+            Some(Utc.with_ymd_and_hms(2018, 4, 24, 0, 9, 15).unwrap())
+        );
+        assert_eq!(parse_timestamp("yesterday"), None);
+    }
+
+    #[test]
+    fn order_enums_keep_unknown_values() {
+        use crate::internal::models::{Order, Payment, Trade};
+        use crate::{OrderState, OrderType, PaymentStatus};
+
+        let order: Order = serde_json::from_str(
+            "{\"status\":\"executed\",\"type\":\"sell\",\"amount\":{}}",
+        )
+        .unwrap();
+        assert_eq!(order.status, OrderState::Executed);
+        assert_eq!(order.order_type, OrderType::Sell);
+
+        let order: Order =
+            serde_json::from_str("{\"status\":\"partially_filled\",\"type\":\"stop\",\"amount\":{}}")
+                .unwrap();
+        assert_eq!(order.status, OrderState::Other("partially_filled".to_string()));
+        assert_eq!(order.order_type, OrderType::Other("stop".to_string()));
+        assert_eq!(json!(order.status), json!("partially_filled"));
+
+        let order: Order = serde_json::from_str("{\"type\":\"BUY\",\"amount\":{}}").unwrap();
+        assert_eq!(order.order_type, OrderType::Buy);
+        assert_eq!(order.status, OrderState::default());
+
+        let trade: Trade = serde_json::from_str(
+            "{\"market_taker\":\"buy\",\"price\":\"1\",\"amount\":\"1\",\"timestamp\":\"2017-08-29T15:44:17\",\"market\":\"ETHCLP\"}",
+        )
+        .unwrap();
+        assert_eq!(trade.market_taker, OrderType::Buy);
+        assert_eq!(OrderType::Sell.to_string(), "sell");
+
+        let payment = json!({
+            "id": 1, "external_id": "", "status": "3", "to_receive": "1000",
+            "to_receive_currency": "CLP", "expected_amount": "0.001", "expected_currency": "ETH",
+            "deposit_address": "", "refund_email": "", "qr": "", "obs": "", "callback_url": "",
+            "error_url": "", "success_url": "", "payment_url": "",
+            "created_at": "2018-04-23T21:09:15", "updated_at": "2018-04-23T21:09:15"
+        });
+        let parsed: Payment = serde_json::from_value(payment.clone()).unwrap();
+        assert_eq!(parsed.status, PaymentStatus::Successful);
+        assert!(parsed.status.is_final());
+        assert_eq!(json!(parsed.status), json!(3));
+
+        let mut waiting = payment.clone();
+        waiting["status"] = json!(-1);
+        let parsed: Payment = serde_json::from_value(waiting).unwrap();
+        assert_eq!(parsed.status, PaymentStatus::Expired);
+
+        let mut unknown = payment;
+        unknown["status"] = json!("7");
+        let parsed: Payment = serde_json::from_value(unknown).unwrap();
+        assert_eq!(parsed.status, PaymentStatus::Other("7".to_string()));
+        assert_eq!(parsed.status.code(), None);
+        assert!(!parsed.status.is_final());
+    }
+
+    #[test]
+    fn pagination_pages_are_typed() {
+        use crate::response::Pagination;
+
+        let pagination: Pagination =
+            serde_json::from_str(r#"{"previous":"null","limit":20,"page":0,"next":"1"}"#).unwrap();
+        assert_eq!(pagination.previous, None);
+        assert_eq!(pagination.next, Some(1));
+
+        let pagination: Pagination =
+            serde_json::from_str(r#"{"previous":1,"limit":20,"page":2,"next":null}"#).unwrap();
+        assert_eq!(pagination.previous, Some(1));
+        assert_eq!(pagination.next, None);
+
+        let pagination: Pagination = serde_json::from_str(r#"{"limit":20}"#).unwrap();
+        assert_eq!(pagination.page, 0);
+        assert_eq!(pagination.next, None);
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn page_keeps_pagination_metadata() {
+        let client = crate::CryptoMktClient::with_transport(
+            API_KEY,
+            SECRET_KEY,
+            Box::new(paged_trades()),
+        );
+        let market = client.create_market("ETHCLP");
+
+        let page = market.get_trades_page("2018-05-15", "2018-05-16", 1, 2).unwrap();
+        assert_eq!(page.page, 1);
+        assert_eq!(page.limit, 2);
+        assert_eq!(page.previous, None);
+        assert_eq!(page.next, Some(2));
+        assert!(page.has_next());
+        assert_eq!(page.items.len(), 2);
+
+        let page = market.get_trades_page("2018-05-15", "2018-05-16", 2, 2).unwrap();
+        assert!(!page.has_next());
+        assert_eq!(page.into_iter().count(), 1);
+    }
+
+    #[tokio::test]
+    async fn async_page_keeps_pagination_metadata() {
+        let client = crate::AsyncCryptoMktClient::with_transport(
+            API_KEY,
+            SECRET_KEY,
+            Box::new(paged_trades()),
+        );
+        let page = client
+            .create_market("ETHCLP")
+            .get_trades_page("2018-05-15", "2018-05-16", 0, 2)
+            .await
+            .unwrap();
+        assert_eq!((page.page, page.next), (0, Some(1)));
+        assert_eq!(page.items[1].price, Decimal::new(2, 0));
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn paginator_follows_next_page() {
+        use crate::PageOptions;
+
+        let transport = paged_trades();
+        let client =
+            crate::CryptoMktClient::with_transport(API_KEY, SECRET_KEY, Box::new(transport.clone()));
+        let market = client.create_market("ETHCLP");
+        let prices: Vec<Decimal> = market
+            .iter_trades("2018-05-15", "2018-05-16", PageOptions::default().page_size(2))
+            .map(|trade| trade.unwrap().price)
+            .collect();
+        assert_eq!(prices, (1..=5).map(|p| Decimal::new(p, 0)).collect::<Vec<_>>());
+        assert_eq!(transport.requested(), vec![0, 1, 2]);
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn paginator_caps_and_laziness() {
+        use crate::PageOptions;
+
+        let transport = paged_trades();
+        let client =
+            crate::CryptoMktClient::with_transport(API_KEY, SECRET_KEY, Box::new(transport.clone()));
+        let market = client.create_market("ETHCLP");
+
+        // Las páginas se solicitan a medida que se consumen los elementos
+        let mut trades = market.iter_trades("2018-05-15", "2018-05-16", PageOptions::default());
+        assert!(transport.requested().is_empty());
+        trades.next().unwrap().unwrap();
+        assert_eq!(transport.requested(), vec![0]);
+
+        let transport = paged_trades();
+        let client =
+            crate::CryptoMktClient::with_transport(API_KEY, SECRET_KEY, Box::new(transport.clone()));
+        let market = client.create_market("ETHCLP");
+        let count = market
+            .iter_trades("2018-05-15", "2018-05-16", PageOptions::default().max_items(3))
+            .count();
+        assert_eq!(count, 3);
+        assert_eq!(transport.requested(), vec![0, 1]);
+
+        let transport = paged_trades();
+        let client =
+            crate::CryptoMktClient::with_transport(API_KEY, SECRET_KEY, Box::new(transport.clone()));
+        let market = client.create_market("ETHCLP");
+        let count = market
+            .iter_trades("2018-05-15", "2018-05-16", PageOptions::default().max_pages(1))
+            .count();
+        assert_eq!(count, 2);
+        assert_eq!(transport.requested(), vec![0]);
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn paginator_stops_after_an_error() {
+        use crate::PageOptions;
+
+        let transport = PagedMock::new(vec![(0, &trades_page(&[1], 0, Some(1)))]);
+        let client =
+            crate::CryptoMktClient::with_transport(API_KEY, SECRET_KEY, Box::new(transport.clone()));
+        let trades: Vec<_> = client
+            .create_market("ETHCLP")
+            .iter_trades("2018-05-15", "2018-05-16", PageOptions::default())
+            .collect();
+        assert_eq!(trades.len(), 2);
+        assert!(trades[0].is_ok());
+        assert_eq!(
+            trades[1].as_ref().unwrap_err().kind(),
+            CryptoMktErrorType::BadRequest
+        );
+
+        // Las fechas inválidas se entregan como error sin realizar peticiones
+        let transport = paged_trades();
+        let client =
+            crate::CryptoMktClient::with_transport(API_KEY, SECRET_KEY, Box::new(transport.clone()));
+        let mut trades = client
+            .create_market("ETHCLP")
+            .iter_trades("", "2018-05-16", PageOptions::default());
+        assert!(trades.next().unwrap().is_err());
+        assert!(trades.next().is_none());
+        assert!(transport.requested().is_empty());
+    }
+
+    #[tokio::test]
+    async fn page_stream_follows_next_page() {
+        use crate::PageOptions;
+        use futures::stream::StreamExt;
+
+        let transport = paged_trades();
+        let client = crate::AsyncCryptoMktClient::with_transport(
+            API_KEY,
+            SECRET_KEY,
+            Box::new(transport.clone()),
+        );
+        let market = client.create_market("ETHCLP");
+        let prices: Vec<Decimal> = market
+            .trades_stream("2018-05-15", "2018-05-16", PageOptions::default().max_items(4))
+            .map(|trade| trade.unwrap().price)
+            .collect()
+            .await;
+        assert_eq!(prices, (1..=4).map(|p| Decimal::new(p, 0)).collect::<Vec<_>>());
+        assert_eq!(transport.requested(), vec![0, 1]);
+
+        let transport = PagedMock::new(vec![(
+            0,
+            "{\"status\":\"success\",\"pagination\":{\"page\":0,\"limit\":2,\"next\":null},\"data\":[]}",
+        )]);
+        let client = crate::AsyncCryptoMktClient::with_transport(
+            API_KEY,
+            SECRET_KEY,
+            Box::new(transport.clone()),
+        );
+        let payments: Vec<_> = client
+            .payment_orders_stream("2018-05-15", "2018-05-16", PageOptions::default())
+            .collect()
+            .await;
+        assert!(payments.is_empty());
+        assert_eq!(transport.requested(), vec![0]);
+    }
+
+    #[test]
+    fn payment_order_request_validation() {
+        use crate::PaymentOrderRequest;
+
+        let request = PaymentOrderRequest::builder(Decimal::new(150005, 1), "CLP", "shop@example.com")
+            .external_id("ORDER-1")
+            .callback_url("https://example.com/callback?id=1")
+            .success_url("http://localhost:8080/ok")
+            .refund_email(" buyer@mail.example.com ")
+            .build()
+            .unwrap();
+        let params = request.to_params();
+        assert_eq!(params["to_receive"], "15000.5");
+        assert_eq!(params["to_receive_currency"], "CLP");
+        assert_eq!(params["payment_receiver"], "shop@example.com");
+        assert_eq!(params["external_id"], "ORDER-1");
+        assert_eq!(params["refund_email"], "buyer@mail.example.com");
+        assert!(!params.contains_key("error_url"));
+        assert_eq!(params.len(), 7);
+
+        let invalid = vec![
+            PaymentOrderRequest::builder(Decimal::new(0, 0), "CLP", "shop@example.com"),
+            PaymentOrderRequest::builder(Decimal::new(1, 0), "", "shop@example.com"),
+            PaymentOrderRequest::builder(Decimal::new(1, 0), "CLP", "shop.example.com"),
+            PaymentOrderRequest::builder(Decimal::new(1, 0), "CLP", "shop@localhost"),
+            PaymentOrderRequest::builder(Decimal::new(1, 0), "CLP", "shop@example.com")
+                .refund_email("a b@example.com"),
+            PaymentOrderRequest::builder(Decimal::new(1, 0), "CLP", "shop@example.com")
+                .callback_url("example.com/callback"),
+            PaymentOrderRequest::builder(Decimal::new(1, 0), "CLP", "shop@example.com")
+                .error_url("ftp://example.com/error"),
+        ];
+        for builder in invalid {
+            let error = builder.clone().build().unwrap_err();
+            assert_eq!(error.kind(), CryptoMktErrorType::InvalidArgument, "{:?}", builder);
+        }
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn payment_orders_use_the_listing_endpoint() {
+        use crate::PageOptions;
+
+        let transport = PagedMock::new(vec![(
+            1,
+            "{\"status\":\"success\",\"pagination\":{\"page\":1,\"limit\":20,\"previous\":0,\"next\":null},\"data\":[]}",
+        )]);
+        let client =
+            crate::CryptoMktClient::with_transport(API_KEY, SECRET_KEY, Box::new(transport.clone()));
+        let page = client
+            .get_payment_orders_page("15/05/2018", "16/05/2018", Some(1), Some(20))
+            .unwrap();
+        assert_eq!(page.previous, Some(0));
+        assert!(client
+            .iter_payment_orders("15/05/2018", "16/05/2018", PageOptions::default().start_page(1))
+            .next()
+            .is_none());
+        assert_eq!(transport.paths(), vec!["/v1/payment/orders", "/v1/payment/orders"]);
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn iter_payment_orders_uses_the_listing_endpoint() {
+        use crate::PageOptions;
+
+        let transport = paged_payments();
+        let client =
+            crate::CryptoMktClient::with_transport(API_KEY, SECRET_KEY, Box::new(transport.clone()));
+        let ids: Vec<i32> = client
+            .iter_payment_orders("15/05/2018", "16/05/2018", PageOptions::default())
+            .map(|payment| payment.unwrap().id)
+            .collect();
+        assert_eq!(ids, vec![1, 2, 3]);
+        assert_eq!(transport.requested(), vec![0, 1]);
+        assert_eq!(transport.paths(), vec!["/v1/payment/orders", "/v1/payment/orders"]);
+    }
+
+    #[tokio::test]
+    async fn payment_orders_stream_uses_the_listing_endpoint() {
+        use crate::PageOptions;
+        use futures::stream::StreamExt;
+
+        let transport = paged_payments();
+        let client = crate::AsyncCryptoMktClient::with_transport(
+            API_KEY,
+            SECRET_KEY,
+            Box::new(transport.clone()),
+        );
+        let ids: Vec<i32> = client
+            .payment_orders_stream("15/05/2018", "16/05/2018", PageOptions::default())
+            .map(|payment| payment.unwrap().id)
+            .collect()
+            .await;
+        assert_eq!(ids, vec![1, 2, 3]);
+        assert_eq!(transport.paths(), vec!["/v1/payment/orders", "/v1/payment/orders"]);
+    }
+
+    #[test]
+    fn payment_notification_parse_and_verify() {
+        use crate::internal::api::sign_message;
+        use crate::{PaymentNotification, PaymentStatus};
+
+        let signature = sign_message(SECRET_KEY, "P135653");
+        let body = format!(
+            "id=P13565&external_id=ORDER-1&status=3&to_receive=15000.5&to_receive_currency=CLP\
+             &expected_amount=0.0002&expected_currency=BTC&refund_email=buyer%40example.com\
+             &updated_at=2018-05-16T14%3A02%3A36.386967&signature={}",
+            signature
+        );
+        let notification = PaymentNotification::parse_verified(&body, SECRET_KEY).unwrap();
+        assert_eq!(notification.id, "P13565");
+        assert_eq!(notification.external_id.as_deref(), Some("ORDER-1"));
+        assert_eq!(notification.status, PaymentStatus::Successful);
+        assert!(notification.is_paid());
+        assert_eq!(notification.to_receive, Some(Decimal::new(150005, 1)));
+        assert_eq!(notification.expected_amount, Some(Decimal::new(2, 4)));
+        assert_eq!(notification.refund_email.as_deref(), Some("buyer@example.com"));
+        assert_eq!(notification.created_at, None);
+        assert_eq!(
+            notification.updated_at,
+            optional_timestamp(&json!("2018-05-16T14:02:36.386967"))
+        );
+        assert_eq!(notification.expected_signature(SECRET_KEY), signature);
+
+        // Otra llave, otro estado o una firma truncada no son válidos
+        assert_eq!(
+            notification.verify(API_KEY).unwrap_err().kind(),
+            CryptoMktErrorType::InvalidSignature
+        );
+        let tampered = body.replace("status=3", "status=-1");
+        assert!(PaymentNotification::parse_verified(&tampered, SECRET_KEY).is_err());
+        let truncated = body.replace(&signature, &signature[..10]);
+        assert!(PaymentNotification::parse_verified(&truncated, SECRET_KEY).is_err());
+
+        let json = json!({"id": "P13565", "status": -1, "signature": sign_message(SECRET_KEY, "P13565-1")});
+        let notification =
+            PaymentNotification::parse_verified(&json.to_string(), SECRET_KEY).unwrap();
+        assert!(notification.is_expired());
+        assert_eq!(notification.to_receive, None);
+
+        for body in &["status=3&signature=ff", "id=1&status=3&signature=ff&to_receive=abc", "{"] {
+            assert_eq!(
+                PaymentNotification::parse(body).unwrap_err().kind(),
+                CryptoMktErrorType::MalformedResource
+            );
+        }
+    }
+
+    #[cfg(feature = "webhook-server")]
+    #[tokio::test]
+    async fn payment_webhook_server() {
+        use crate::internal::api::sign_message;
+        use crate::PaymentWebhook;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/callback", listener.local_addr().unwrap());
+        let received = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let notifications = received.clone();
+        tokio::spawn(PaymentWebhook::new(SECRET_KEY).serve(listener, move |notification| {
+            notifications.lock().unwrap().push(notification.external_id.unwrap());
+        }));
+
+        let client = reqwest::Client::new();
+        let post = |body: String| {
+            client
+                .post(&url)
+                .header("Content-Type", "application/x-www-form-urlencoded")
+                .body(body)
+                .send()
+        };
+        let valid = format!(
+            "id=P1&status=3&external_id=ORDER-1&signature={}",
+            sign_message(SECRET_KEY, "P13")
+        );
+        assert_eq!(post(valid).await.unwrap().status(), 200);
+        let forged = format!(
+            "id=P1&status=3&external_id=ORDER-2&signature={}",
+            sign_message(API_KEY, "P13")
+        );
+        assert_eq!(post(forged).await.unwrap().status(), 401);
+        assert_eq!(post("status=3".to_string()).await.unwrap().status(), 400);
+        assert_eq!(client.get(&url).send().await.unwrap().status(), 405);
+        assert_eq!(*received.lock().unwrap(), vec!["ORDER-1".to_string()]);
+    }
+
+    #[cfg(feature = "webhook-server")]
+    #[tokio::test]
+    async fn payment_webhook_rejects_large_bodies() {
+        use crate::PaymentWebhook;
+        use futures::channel::oneshot;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(PaymentWebhook::new(SECRET_KEY).max_body_size(1024).serve(listener, |_| {}));
+
+        let client = reqwest::Client::new();
+        let url = format!("http://{}/callback", addr);
+        let large = format!("id=P1&status=3&obs={}", "x".repeat(2048));
+        assert_eq!(client.post(&url).body(large).send().await.unwrap().status(), 413);
+        assert_eq!(client.post(&url).body("status=3").send().await.unwrap().status(), 400);
+
+        // Sin Content-Length el límite se aplica mientras se leen las partes
+        let (sender, receiver) = oneshot::channel();
+        thread::spawn(move || {
+            let mut stream = std::net::TcpStream::connect(addr).unwrap();
+            let chunk = "x".repeat(512);
+            let mut request = "POST /callback HTTP/1.1\r\nHost: localhost\r\n\
+                               Transfer-Encoding: chunked\r\n\r\n"
+                .to_string();
+            for _ in 0..4 {
+                request.push_str(&format!("{:x}\r\n{}\r\n", chunk.len(), chunk));
+            }
+            request.push_str("0\r\n\r\n");
+            stream.write_all(request.as_bytes()).unwrap();
+            let mut response = [0u8; 12];
+            stream.read_exact(&mut response).unwrap();
+            sender.send(String::from_utf8_lossy(&response).to_string()).unwrap();
+        });
+        assert_eq!(receiver.await.unwrap(), "HTTP/1.1 413");
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn payment_watcher_emits_transitions_until_final() {
+        use crate::{PaymentEvent, PaymentWatcher};
+
+        let transport = SequenceMock::default()
+            .respond_with(
+                "1",
+                vec![
+                    payment_status(1, 0),
+                    payment_status(1, 0),
+                    payment_status(1, 1),
+                    Err(CryptoMktErrorType::RequestServiceUnavailable),
+                    payment_status(1, 1),
+                    payment_status(1, 3),
+                ],
+            )
+            .respond_with("2", vec![payment_status(2, 0), payment_status(2, -1)])
+            .respond_with("3", vec![payment_status(3, -4)])
+            .respond_with("4", vec![Err(CryptoMktErrorType::RequestUnauthorized)]);
+        let client =
+            crate::CryptoMktClient::with_transport(API_KEY, SECRET_KEY, Box::new(transport.clone()));
+        let mut watcher = PaymentWatcher::new(client, fast_watch());
+        watcher.watch("1").watch("2").watch("3").watch("4").watch("1");
+        assert_eq!(watcher.watched(), vec!["1", "2", "3", "4"]);
+
+        let mut events = HashMap::new();
+        watcher.run(|event| {
+            let name = match &event {
+                PaymentEvent::Waiting(payment) => format!("waiting({})", payment.status),
+                PaymentEvent::Paid(_) => "paid".to_string(),
+                PaymentEvent::Expired(_) => "expired".to_string(),
+                PaymentEvent::Refunded(_) => "refunded".to_string(),
+                PaymentEvent::Partial(_) => "partial".to_string(),
+                PaymentEvent::Error { error, terminal, .. } => {
+                    format!("error({}, {})", error.kind(), terminal)
+                }
+            };
+            events.entry(event.id()).or_insert_with(Vec::new).push(name);
+        });
+
+        assert!(watcher.watched().is_empty());
+        assert_eq!(
+            events["1"],
+            vec!["waiting(0)", "waiting(1)", "error(service unavailable, false)", "paid"]
+        );
+        assert_eq!(events["2"], vec!["waiting(0)", "expired"]);
+        assert_eq!(events["3"], vec!["refunded"]);
+        assert_eq!(
+            events["4"],
+            vec!["error(invalid API key, false)", "error(invalid API key, true)"]
+        );
+        // Las órdenes finalizadas no se vuelven a consultar
+        let requests = transport.requests();
+        assert_eq!(requests.iter().filter(|id| *id == "1").count(), 6);
+        assert_eq!(requests.iter().filter(|id| *id == "3").count(), 1);
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn payment_watcher_channel() {
+        use crate::{PaymentEvent, PaymentWatcher};
+
+        let transport = SequenceMock::default()
+            .respond_with("7", vec![payment_status(7, 2), payment_status(7, -3)]);
+        let client = crate::CryptoMktClient::with_transport(API_KEY, SECRET_KEY, Box::new(transport));
+        let mut watcher = PaymentWatcher::new(client, fast_watch());
+        watcher.watch("7");
+        let (events, handle) = watcher.spawn();
+        let events: Vec<PaymentEvent> = events.iter().collect();
+        handle.join().unwrap();
+        assert_eq!(events.len(), 2);
+        assert!(!events[0].is_terminal());
+        match &events[1] {
+            PaymentEvent::Partial(payment) => assert_eq!(payment.external_id, "ORDER-7"),
+            other => panic!("unexpected event {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn async_payment_watcher_stream() {
+        use crate::{AsyncPaymentWatcher, PaymentEvent};
+        use futures::stream::StreamExt;
+
+        let transport = SequenceMock::default()
+            .respond_with("5", vec![payment_status(5, 0), payment_status(5, 3)])
+            .respond_with("6", vec![payment_status(6, -2)]);
+        let client =
+            crate::AsyncCryptoMktClient::with_transport(API_KEY, SECRET_KEY, Box::new(transport));
+        let mut watcher = AsyncPaymentWatcher::new(client, fast_watch());
+        watcher.watch("5").watch("6");
+        let events: Vec<PaymentEvent> = watcher.into_stream().collect().await;
+        let names: Vec<(String, bool)> = events
+            .iter()
+            .map(|event| (event.id(), event.is_terminal()))
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                ("5".to_string(), false),
+                ("6".to_string(), true),
+                ("5".to_string(), true)
+            ]
+        );
+    }
+
+    #[test]
+    fn order_request_validation() {
+        use crate::{OrderLimits, OrderRequest, OrderType};
+
+        let limits = OrderLimits {
+            amount_step: Some(Decimal::new(1, 4)),
+            min_amount: Some(Decimal::new(1, 3)),
+            price_decimals: Some(2),
+            min_notional: None,
+        };
+        let order = OrderRequest::builder(OrderType::Buy, Decimal::new(500, 3), Decimal::new(712050, 2))
+            .limits(limits)
+            .build()
+            .unwrap();
+        let params = order.to_params("ETHEUR");
+        assert_eq!(params["amount"], "0.5");
+        assert_eq!(params["price"], "7120.5");
+        assert_eq!(params["type"], "buy");
+        assert_eq!(params["market"], "ETHEUR");
+
+        let invalid = vec![
+            (Decimal::new(0, 0), Decimal::new(7120, 0), "amount must be positive"),
+            (Decimal::new(-1, 0), Decimal::new(7120, 0), "amount must be positive"),
+            (Decimal::new(1, 0), Decimal::new(0, 0), "price must be positive"),
+            (Decimal::new(9, 4), Decimal::new(7120, 0), "below the minimum 0.001"),
+            (Decimal::new(10001, 5), Decimal::new(7120, 0), "not a multiple of the step 0.0001"),
+            (Decimal::new(1, 0), Decimal::new(7120001, 3), "more than 2 decimals"),
+        ];
+        for (amount, price, message) in invalid {
+            let error = OrderRequest::builder(OrderType::Sell, amount, price)
+                .market("ETHEUR")
+                .limits(limits)
+                .build()
+                .unwrap_err();
+            assert_eq!(error.kind(), CryptoMktErrorType::InvalidArgument);
+            let description = error.message().unwrap();
+            assert!(description.starts_with("invalid sell order on ETHEUR"), "{}", description);
+            assert!(description.contains(message), "{}", description);
+        }
+
+        // Sin límites solo se valida que la cantidad y el precio sean positivos
+        assert!(OrderRequest::builder(OrderType::Buy, Decimal::new(1, 8), Decimal::new(1, 8))
+            .build()
+            .is_ok());
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn invalid_orders_are_not_sent() {
+        use crate::{OrderLimits, OrderType};
+
+        let transport = PagedMock::new(vec![(
+            0,
+            "{\"status\":\"success\",\"data\":[{\"status\":\"active\",\"created_at\":\"2017-09-01T14:01:56.887272\",\"amount\":{\"original\":\"1\",\"remaining\":\"1\"},\"execution_price\":null,\"price\":\"7120\",\"type\":\"buy\",\"id\":\"M103966\",\"market\":\"ETHCLP\",\"updated_at\":\"2017-09-01T14:01:56.887272\"}]}",
+        )]);
+        // Sin límites en el registro los precios no se redondean antes de validar
+        let client =
+            crate::CryptoMktClient::with_transport(API_KEY, SECRET_KEY, Box::new(transport.clone()));
+        let market = client.create_market("ETHCLP").with_order_limits(OrderLimits {
+            amount_step: None,
+            min_amount: Some(Decimal::new(1, 2)),
+            price_decimals: Some(0),
+            min_notional: None,
+        });
+
+        let error = market
+            .create_order(OrderType::Buy, Decimal::new(1, 3), Decimal::new(7120, 0))
+            .unwrap_err();
+        assert_eq!(error.kind(), CryptoMktErrorType::InvalidArgument);
+        assert!(market
+            .create_order(OrderType::Buy, Decimal::new(1, 0), Decimal::new(71205, 1))
+            .is_err());
+        assert!(transport.paths().is_empty());
+
+        let orders = market
+            .create_order(OrderType::Buy, Decimal::new(1, 0), Decimal::new(7120, 0))
+            .unwrap();
+        assert_eq!(orders[0].id, "M103966");
+        assert_eq!(transport.paths(), vec!["/v1/orders/create"]);
+    }
+
+    ///
+    /// Registro de prueba con los límites de ETHCLP
+    ///
+    fn test_markets() -> crate::MarketRegistry {
+        crate::MarketRegistry::from_json(
+            r#"{"markets": [{"name": "ETHCLP", "price_tick": "5", "amount_step": "0.0001",
+                "min_amount": "0.001", "min_notional": "1000"}]}"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn market_registry_bundled_file() {
+        use crate::MarketRegistry;
+
+        // Solo los datos con fuente: monedas y decimales ISO 4217 de la cotización
+        let registry = MarketRegistry::bundled();
+        assert_eq!(registry.names().len(), 16);
+        let info = registry.get("ethclp").unwrap();
+        assert_eq!((info.base_currency.as_str(), info.quote_currency.as_str()), ("ETH", "CLP"));
+        assert_eq!(info.quote_decimals, Some(0));
+        assert_eq!(registry.get("XLMEUR").unwrap().quote_decimals, Some(2));
+        for name in registry.names() {
+            let info = registry.get(&name).unwrap();
+            assert_eq!(format!("{}{}", info.base_currency, info.quote_currency), name);
+            assert_eq!(
+                (info.price_tick, info.amount_step, info.min_amount, info.min_notional),
+                (None, None, None, None),
+                "{}",
+                name
+            );
+        }
+        assert!(MarketRegistry::new().names().is_empty());
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn clients_use_the_bundled_registry() {
+        use crate::OrderType;
+
+        let transport = PagedMock::new(vec![(0, "{\"status\":\"success\",\"data\":\"\"}")]);
+        let client =
+            crate::CryptoMktClient::with_transport(API_KEY, SECRET_KEY, Box::new(transport.clone()));
+        assert_eq!(client.market_registry(), crate::MarketRegistry::bundled());
+        let market = client.create_market("ETHCLP");
+
+        // Las compras instantáneas en CLP se envían sin decimales
+        market.create_order_instant(OrderType::Buy, Decimal::new(15007, 1)).unwrap();
+        assert_eq!(transport.payloads()[0]["amount"], "1500");
+    }
+
+    #[test]
+    fn market_registry_files_and_overrides() {
+        use crate::{MarketInfo, MarketRegistry};
+
+        let registry = test_markets();
+        let info = registry.get("ethclp").unwrap();
+        assert_eq!(info.base_currency, "ETH");
+        assert_eq!(info.quote_currency, "CLP");
+        assert_eq!(info.price_tick, Some(Decimal::new(5, 0)));
+        assert_eq!(info.amount_step, Some(Decimal::new(1, 4)));
+        assert_eq!(info.min_amount, Some(Decimal::new(1, 3)));
+
+        // TOML: cambia el tick y agrega un mercado, el resto se mantiene
+        let mut registry = test_markets();
+        registry
+            .merge_toml(
+                r#"
+                [[markets]]
+                name = "ETHCLP"
+                price_tick = "10"
+
+                [[markets]]
+                name = "XRPCLP"
+                amount_step = "0.1"
+                "#,
+            )
+            .unwrap();
+        let info = registry.get("ETHCLP").unwrap();
+        assert_eq!(info.price_tick, Some(Decimal::new(10, 0)));
+        assert_eq!(info.amount_step, Some(Decimal::new(1, 4)));
+        let info = registry.get("XRPCLP").unwrap();
+        assert_eq!((info.base_currency.as_str(), info.quote_currency.as_str()), ("XRP", "CLP"));
+        assert_eq!(info.price_tick, None);
+
+        // JSON
+        let registry = MarketRegistry::from_json(
+            r#"{"markets": [{"name": "ETHEUR", "price_tick": "0.01", "min_notional": "5"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(registry.names(), vec!["ETHEUR"]);
+        assert_eq!(registry.get("ETHEUR").unwrap().min_notional, Some(Decimal::new(5, 0)));
+        assert_eq!(registry.get_or_default("BTCARS"), MarketInfo::new("BTCARS"));
+
+        let error = MarketRegistry::from_json("{\"markets\": {}}").unwrap_err();
+        assert_eq!(error.kind(), CryptoMktErrorType::InvalidConfiguration);
+        let error = MarketRegistry::from_toml("markets = 1").unwrap_err();
+        assert_eq!(error.kind(), CryptoMktErrorType::InvalidConfiguration);
+        let error = MarketRegistry::new().merge_file("/nonexistent/markets.toml").unwrap_err();
+        assert_eq!(error.kind(), CryptoMktErrorType::InvalidConfiguration);
+
+        // Los mercados listados por el exchange se agregan sin perder los límites
+        let mut registry = test_markets();
+        registry.refresh(&["ETHCLP", "XLMUSD"]);
+        assert_eq!(registry.get("ETHCLP").unwrap().price_tick, Some(Decimal::new(5, 0)));
+        assert_eq!(registry.get("XLMUSD").unwrap(), &MarketInfo::new("XLMUSD"));
+    }
+
+    #[test]
+    fn market_info_rounding() {
+        use crate::{MarketInfo, OrderType};
+
+        let mut info = MarketInfo::new("ETHCLP");
+        let price = Decimal::new(2500037, 1);
+        assert_eq!(info.round_price(price, &OrderType::Buy), price);
+        assert_eq!(info.round_amount(Decimal::new(123456, 6)), Decimal::new(123456, 6));
+
+        info.price_tick = Some(Decimal::new(5, 0));
+        info.amount_step = Some(Decimal::new(1, 4));
+        info.min_amount = Some(Decimal::new(1, 3));
+        info.min_notional = Some(Decimal::new(1000, 0));
+        assert_eq!(info.round_price(price, &OrderType::Buy), Decimal::new(250000, 0));
+        assert_eq!(info.round_price(price, &OrderType::Sell), Decimal::new(250005, 0));
+        assert_eq!(
+            info.round_price(Decimal::new(250005, 0), &OrderType::Sell),
+            Decimal::new(250005, 0)
+        );
+        assert_eq!(info.round_amount(Decimal::new(123456, 6)), Decimal::new(1234, 4));
+        assert_eq!(info.round_amount(Decimal::new(9, 5)), Decimal::ZERO);
+
+        info.price_tick = Some(Decimal::new(25, 2));
+        assert_eq!(info.round_price(Decimal::new(10013, 3), &OrderType::Buy), Decimal::new(10, 0));
+        assert_eq!(info.round_price(Decimal::new(10013, 3), &OrderType::Sell), Decimal::new(1025, 2));
+
+        // Las compras instantáneas se indican en CLP, las ventas en ETH
+        let amount = Decimal::new(15007, 1);
+        assert_eq!(info.round_instant_amount(amount, &OrderType::Buy), amount);
+        info.quote_decimals = Some(0);
+        assert_eq!(info.round_instant_amount(amount, &OrderType::Buy), Decimal::new(1500, 0));
+        assert_eq!(
+            info.round_instant_amount(Decimal::new(123456, 6), &OrderType::Sell),
+            Decimal::new(1234, 4)
+        );
+
+        let limits = info.order_limits();
+        assert_eq!(limits.amount_step, Some(Decimal::new(1, 4)));
+        assert_eq!(limits.min_amount, Some(Decimal::new(1, 3)));
+        assert_eq!(limits.price_decimals, Some(2));
+        assert_eq!(limits.min_notional, Some(Decimal::new(1000, 0)));
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn orders_are_rounded_to_the_market() {
+        use crate::OrderType;
+
+        let transport = PagedMock::new(vec![(
+            0,
+            "{\"status\":\"success\",\"data\":[{\"status\":\"active\",\"created_at\":\"2017-09-01T14:01:56.887272\",\"amount\":{\"original\":\"0.1234\",\"remaining\":\"0.1234\"},\"execution_price\":null,\"price\":\"250000\",\"type\":\"buy\",\"id\":\"M103966\",\"market\":\"ETHCLP\",\"updated_at\":\"2017-09-01T14:01:56.887272\"}]}",
+        )]);
+        let client =
+            crate::CryptoMktClient::with_transport(API_KEY, SECRET_KEY, Box::new(transport.clone()));
+        assert_eq!(client.create_market("ETHCLP").get_market_info().price_tick, None);
+        let client = client.with_market_registry(test_markets());
+        let market = client.create_market("ETHCLP");
+        assert_eq!(market.get_market_info().price_tick, Some(Decimal::new(5, 0)));
+
+        market
+            .create_order(OrderType::Buy, Decimal::new(123456, 6), Decimal::new(2500037, 1))
+            .unwrap();
+        market
+            .create_order(OrderType::Sell, Decimal::new(123456, 6), Decimal::new(2500037, 1))
+            .unwrap();
+        let payloads = transport.payloads();
+        assert_eq!(payloads[0]["amount"], "0.1234");
+        assert_eq!(payloads[0]["price"], "250000");
+        assert_eq!(payloads[1]["price"], "250005");
+
+        // El monto queda bajo el mínimo de 1000 CLP
+        let error = market
+            .create_order(OrderType::Buy, Decimal::new(1, 3), Decimal::new(250000, 0))
+            .unwrap_err();
+        assert_eq!(error.kind(), CryptoMktErrorType::InvalidArgument);
+        assert!(error.message().unwrap().contains("notional"));
+
+        // La cantidad se redondea a cero
+        let error = market
+            .create_order_instant(OrderType::Sell, Decimal::new(5, 5))
+            .unwrap_err();
+        assert_eq!(error.kind(), CryptoMktErrorType::InvalidArgument);
+        assert_eq!(transport.paths().len(), 2);
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn instant_orders_are_rounded_by_currency() {
+        use crate::{MarketRegistry, OrderType};
+
+        let transport = PagedMock::new(vec![(0, "{\"status\":\"success\",\"data\":\"\"}")]);
+        let registry = MarketRegistry::from_json(
+            r#"{"markets": [{"name": "XLMEUR", "amount_step": "1", "quote_decimals": 2}]}"#,
+        )
+        .unwrap();
+        let client =
+            crate::CryptoMktClient::with_transport(API_KEY, SECRET_KEY, Box::new(transport.clone()))
+                .with_market_registry(registry);
+        let market = client.create_market("XLMEUR");
+
+        // Compras en EUR con dos decimales, ventas en XLM al paso de 1
+        market.create_order_instant(OrderType::Buy, Decimal::new(1050, 2)).unwrap();
+        market.create_order_instant(OrderType::Buy, Decimal::new(10567, 3)).unwrap();
+        market.create_order_instant(OrderType::Sell, Decimal::new(107, 1)).unwrap();
+        let amounts: Vec<String> = transport
+            .payloads()
+            .iter()
+            .map(|payload| payload["amount"].clone())
+            .collect();
+        assert_eq!(amounts, vec!["10.5", "10.56", "10"]);
+
+        let error = market
+            .create_order_instant(OrderType::Buy, Decimal::new(4, 3))
+            .unwrap_err();
+        assert_eq!(error.kind(), CryptoMktErrorType::InvalidArgument);
+        assert_eq!(error.message(), Some("instant buy amount 0.004 rounds to zero on XLMEUR"));
+        assert_eq!(transport.paths().len(), 3);
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn get_markets_refreshes_the_registry() {
+        let transport = PagedMock::new(vec![(
+            0,
+            "{\"status\":\"success\",\"data\":[\"ETHCLP\",\"XLMUSD\"]}",
+        )]);
+        let client = crate::CryptoMktClient::with_transport(API_KEY, SECRET_KEY, Box::new(transport));
+        let client = client.with_market_registry(test_markets());
+        assert!(client.market_registry().get("XLMUSD").is_none());
+
+        let markets = client.get_markets().unwrap();
+        assert_eq!(markets[0].get_market_info().price_tick, Some(Decimal::new(5, 0)));
+        assert_eq!(markets[1].get_base_currency(), "XLM");
+        assert_eq!(markets[1].get_quote_currency(), "USD");
+        assert!(client.market_registry().get("XLMUSD").is_some());
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn order_tracker_reports_fills_until_final() {
+        use crate::OrderTracker;
+
+        let transport = SequenceMock::default()
+            .respond_with(
+                "",
+                vec![
+                    executed_orders(vec![]),
+                    executed_orders(vec![]),
+                    executed_orders(vec![]),
+                    executed_orders(vec![]),
+                    executed_orders(vec![order_json("A", "executed", ("1", "0", "1"), "115")]),
+                ],
+            )
+            .respond_with(
+                "A",
+                vec![
+                    order_status("A", "active", ("1", "1", "0"), "0"),
+                    order_status("A", "active", ("1", "0.6", "0"), "100"),
+                    order_status("A", "active", ("1", "0.6", "0"), "100"),
+                    order_status("A", "active", ("1", "0.2", "0"), "110"),
+                ],
+            )
+            .respond_with(
+                "B",
+                vec![
+                    order_status("B", "active", ("2", "2", "0"), "0"),
+                    order_status("B", "cancelled", ("2", "1.5", "0"), "50"),
+                ],
+            )
+            .respond_with("C", vec![Err(CryptoMktErrorType::RequestUnauthorized)]);
+        let client =
+            crate::CryptoMktClient::with_transport(API_KEY, SECRET_KEY, Box::new(transport.clone()));
+        let mut tracker = OrderTracker::new(client.create_market("ETHCLP"), fast_watch());
+        tracker.track("A").track("B").track("C").track("A");
+        assert_eq!(tracker.tracked(), vec!["A", "B", "C"]);
+
+        let mut events = HashMap::new();
+        tracker.run(|event| {
+            events
+                .entry(event.id())
+                .or_insert_with(Vec::new)
+                .push(order_event_name(&event));
+        });
+
+        assert!(tracker.tracked().is_empty());
+        assert_eq!(
+            events["A"],
+            vec![
+                "fill(0.4, Some(\"100\"), 0.6)",
+                "fill(0.4, Some(\"120\"), 0.2)",
+                "fill(0.2, Some(\"135\"), 0)",
+                "executed"
+            ]
+        );
+        assert_eq!(events["B"], vec!["fill(0.5, Some(\"50\"), 1.5)", "cancelled"]);
+        assert_eq!(
+            events["C"],
+            vec!["error(invalid API key, false)", "error(invalid API key, true)"]
+        );
+        // La orden ejecutada se detectó con `orders/executed`, sin consultar su estado
+        let requests = transport.requests();
+        assert_eq!(requests.iter().filter(|id| *id == "A").count(), 4);
+        assert_eq!(requests.iter().filter(|id| id.is_empty()).count(), 5);
+    }
+
+    #[tokio::test]
+    async fn async_order_tracker_snapshots() {
+        use crate::internal::models::Order;
+        use crate::AsyncOrderTracker;
+        use futures::stream::StreamExt;
+
+        let snapshot = |status: &str, amounts: (&str, &str, &str), avg: &str| -> Order {
+            serde_json::from_value(order_json("D", status, amounts, avg)).unwrap()
+        };
+        let transport = SequenceMock::default()
+            .respond_with("", vec![executed_orders(vec![])])
+            .respond_with("E", vec![order_status("E", "executed", ("3", "0", "3"), "10")]);
+        let client =
+            crate::AsyncCryptoMktClient::with_transport(API_KEY, SECRET_KEY, Box::new(transport));
+        let mut tracker = AsyncOrderTracker::new(client.create_market("ETHCLP"), fast_watch());
+
+        // La versión de `create_order` es el punto de partida, las ejecuciones
+        // anteriores no se informan
+        tracker.track_order(&snapshot("active", ("1", "0.7", "0"), "100"));
+        assert!(tracker
+            .update(snapshot("active", ("1", "0.7", "0"), "100"))
+            .is_empty());
+        let events = tracker.update(snapshot("active", ("1", "0.5", "0"), "0"));
+        let names: Vec<String> = events.iter().map(order_event_name).collect();
+        assert_eq!(names, vec!["fill(0.2, None, 0.5)"]);
+        let events = tracker.update(snapshot("executed", ("1", "0", "1"), "0"));
+        let names: Vec<String> = events.iter().map(order_event_name).collect();
+        assert_eq!(names, vec!["fill(0.5, None, 0)", "executed"]);
+        assert!(tracker.tracked().is_empty());
+        // Las órdenes que no se siguen se ignoran
+        assert!(tracker
+            .update(snapshot("active", ("1", "0.5", "0"), "0"))
+            .is_empty());
+
+        tracker.track("E");
+        let events: Vec<String> = tracker
+            .into_stream()
+            .map(|event| order_event_name(&event))
+            .collect()
+            .await;
+        assert_eq!(events, vec!["fill(3, Some(\"10\"), 0)", "executed"]);
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn cancel_all_orders_reports_each_order() {
+        use crate::OrderType;
+
+        let cancelled = |id: &str| order_status(id, "cancelled", ("1", "1", "0"), "0");
+        let transport = SequenceMock::default()
+            .respond_with(
+                "",
+                vec![
+                    active_orders(&[("O1", "buy"), ("O2", "sell")], 0, Some(1)),
+                    active_orders(&[("O3", "buy")], 1, None),
+                ],
+            )
+            .respond_with("O1", vec![cancelled("O1")])
+            .respond_with("O3", vec![Err(CryptoMktErrorType::RequestServiceUnavailable)]);
+        let client =
+            crate::CryptoMktClient::with_transport(API_KEY, SECRET_KEY, Box::new(transport.clone()));
+        let market = client.create_market("ETHCLP");
+
+        let outcomes = market.cancel_all_orders(Some(OrderType::Buy)).unwrap();
+        let results: Vec<(&str, &str, bool)> = outcomes
+            .iter()
+            .map(|outcome| {
+                (
+                    outcome.order_id.as_str(),
+                    outcome.market.as_str(),
+                    outcome.is_cancelled(),
+                )
+            })
+            .collect();
+        assert_eq!(results, vec![("O1", "ETHCLP", true), ("O3", "ETHCLP", false)]);
+        assert_eq!(
+            outcomes[1].result.as_ref().unwrap_err().kind(),
+            CryptoMktErrorType::RequestServiceUnavailable
+        );
+        // Se listan todas las páginas antes de cancelar, la venta no se cancela
+        assert_eq!(transport.requests(), vec!["", "", "O1", "O3"]);
+
+        // Sin poder listar las órdenes no se cancela ninguna
+        let transport = SequenceMock::default()
+            .respond_with("", vec![Err(CryptoMktErrorType::RequestUnauthorized)]);
+        let client =
+            crate::CryptoMktClient::with_transport(API_KEY, SECRET_KEY, Box::new(transport.clone()));
+        let error = client.create_market("ETHCLP").cancel_all_orders(None).unwrap_err();
+        assert_eq!(error.kind(), CryptoMktErrorType::RequestUnauthorized);
+        assert_eq!(transport.requests(), vec![""]);
+    }
+
+    #[tokio::test]
+    async fn async_cancel_all_across_markets() {
+        let transport = SequenceMock::default()
+            .respond_with(
+                "",
+                vec![
+                    Ok("{\"status\":\"success\",\"data\":[\"ETHCLP\",\"BTCCLP\"]}".to_string()),
+                    active_orders(&[("O1", "buy")], 0, None),
+                    active_orders(&[("O2", "sell"), ("O3", "buy")], 0, None),
+                ],
+            )
+            .respond_with("O1", vec![order_status("O1", "cancelled", ("1", "1", "0"), "0")])
+            .respond_with("O2", vec![Err(CryptoMktErrorType::RequestNotFound)])
+            .respond_with("O3", vec![order_status("O3", "cancelled", ("1", "1", "0"), "0")]);
+        let client = crate::AsyncCryptoMktClient::with_transport(
+            API_KEY,
+            SECRET_KEY,
+            Box::new(transport.clone()),
+        );
+
+        let outcomes = client.cancel_all(None).await.unwrap();
+        let results: Vec<(&str, &str, bool)> = outcomes
+            .iter()
+            .map(|outcome| {
+                (
+                    outcome.order_id.as_str(),
+                    outcome.market.as_str(),
+                    outcome.is_cancelled(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            results,
+            vec![("O1", "ETHCLP", true), ("O2", "BTCCLP", false), ("O3", "BTCCLP", true)]
+        );
+        assert_eq!(transport.requests(), vec!["", "", "", "O1", "O2", "O3"]);
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn replace_order_reports_each_step() {
+        // Orden cancelada sin algunos campos de `amount`
+        let cancelled_without = |id: &str, missing: &[&str], executed: &str| -> MockResponse {
+            let mut order = order_json(id, "cancelled", ("1", "0.6", executed), "0");
+            for field in missing {
+                order["amount"].as_object_mut().unwrap().remove(*field);
+            }
+            Ok(json!({"status": "success", "data": order}).to_string())
+        };
+        let created = |id: &str, amount: &str| -> MockResponse {
+            Ok(json!({
+                "status": "success",
+                "data": [order_json(id, "active", (amount, amount, "0"), "0")]
+            })
+            .to_string())
+        };
+        let transport = SequenceMock::default()
+            .respond_with(
+                "R1",
+                vec![
+                    order_status("R1", "cancelled", ("1", "0.6", "0.4"), "0"),
+                    order_status("R1", "cancelled", ("1", "0.6", "0.4"), "0"),
+                ],
+            )
+            .respond_with(
+                "R2",
+                vec![
+                    Err(CryptoMktErrorType::RequestServiceUnavailable),
+                    order_status("R2", "active", ("1", "1", "0"), "0"),
+                ],
+            )
+            .respond_with(
+                "R3",
+                vec![
+                    Err(CryptoMktErrorType::RequestNotFound),
+                    order_status("R3", "executed", ("1", "0", "1"), "100"),
+                ],
+            )
+            .respond_with("R5", vec![cancelled_without("R5", &["remaining"], "0.25")])
+            .respond_with("R6", vec![cancelled_without("R6", &["remaining", "executed"], "")])
+            .respond_with(
+                "R7",
+                vec![
+                    order_status("R7", "", ("1", "0.6", "0.4"), "0"),
+                    order_status("R7", "pending_cancel", ("1", "0.6", "0.4"), "0"),
+                ],
+            )
+            .respond_with(
+                "",
+                vec![created("N1", "0.6"), created("N2", "1.6"), created("N3", "0.75")],
+            );
+        let client =
+            crate::CryptoMktClient::with_transport(API_KEY, SECRET_KEY, Box::new(transport.clone()));
+        let market = client.create_market("ETHCLP");
+
+        // Se cancela, se confirma y se crea la nueva orden con la cantidad pendiente
+        let outcome = market
+            .replace_order("R1", Decimal::new(250000, 0), None)
+            .unwrap();
+        assert!(outcome.cancel.is_ok());
+        assert!(outcome.is_cancelled());
+        assert_eq!(outcome.amount, Some(Decimal::new(6, 1)));
+        assert!(outcome.is_replaced());
+        assert_eq!(outcome.new_order().unwrap().id, "N1");
+        assert_eq!(transport.requests(), vec!["R1", "R1", ""]);
+
+        // La cantidad total indicada descuenta lo ya ejecutado
+        let outcome = market
+            .replace_order("R1", Decimal::new(250000, 0), Some(Decimal::new(2, 0)))
+            .unwrap();
+        assert_eq!(outcome.amount, Some(Decimal::new(16, 1)));
+        assert_eq!(outcome.new_order().unwrap().id, "N2");
+
+        // La orden sigue activa: no se crea la nueva orden
+        let outcome = market.replace_order("R2", Decimal::new(250000, 0), None).unwrap();
+        assert_eq!(
+            outcome.cancel.as_ref().unwrap_err().kind(),
+            CryptoMktErrorType::RequestServiceUnavailable
+        );
+        assert!(outcome.confirmation.is_ok());
+        assert!(!outcome.is_cancelled());
+        assert_eq!(outcome.amount, None);
+        assert!(outcome.placement.is_none());
+
+        // La orden se ejecutó antes de cancelarla: no queda cantidad pendiente
+        let outcome = market.replace_order("R3", Decimal::new(250000, 0), None).unwrap();
+        assert!(outcome.cancel.is_err());
+        assert!(outcome.is_cancelled());
+        assert_eq!(outcome.amount, Some(Decimal::ZERO));
+        assert!(!outcome.is_replaced());
+
+        // Sin `remaining` la cantidad ejecutada se toma de `executed`
+        let outcome = market.replace_order("R5", Decimal::new(250000, 0), None).unwrap();
+        assert_eq!(outcome.amount, Some(Decimal::new(75, 2)));
+        assert_eq!(outcome.new_order().unwrap().id, "N3");
+
+        // Sin `remaining` ni `executed` no se conoce lo ejecutado: no se crea la orden
+        let requests = transport.requests().len();
+        let outcome = market.replace_order("R6", Decimal::new(250000, 0), None).unwrap();
+        assert!(outcome.is_cancelled());
+        assert_eq!(outcome.amount, None);
+        let error = outcome.placement.unwrap().unwrap_err();
+        assert_eq!(error.kind(), CryptoMktErrorType::InvalidArgument);
+        assert_eq!(error.message(), Some("cannot tell the executed amount of order R6"));
+        assert_eq!(transport.requests().len(), requests + 2);
+
+        // Un estado vacío o desconocido no confirma la cancelación
+        for _ in 0..2 {
+            let requests = transport.requests().len();
+            let outcome = market.replace_order("R7", Decimal::new(250000, 0), None).unwrap();
+            assert!(outcome.confirmation.is_ok());
+            assert!(!outcome.is_cancelled());
+            assert!(outcome.placement.is_none());
+            assert_eq!(transport.requests().len(), requests + 2);
+        }
+
+        // Parámetros inválidos: no se envía ninguna petición
+        let requests = transport.requests().len();
+        let error = market
+            .replace_order("R1", Decimal::ZERO, None)
+            .unwrap_err();
+        assert_eq!(error.kind(), CryptoMktErrorType::InvalidArgument);
+        assert!(market
+            .replace_order("R1", Decimal::new(250000, 0), Some(Decimal::new(-1, 0)))
+            .is_err());
+        assert_eq!(transport.requests().len(), requests);
+    }
+
+    #[tokio::test]
+    async fn async_replace_order_without_confirmation() {
+        let transport = SequenceMock::default().respond_with(
+            "R4",
+            vec![
+                order_status("R4", "cancelled", ("1", "1", "0"), "0"),
+                Err(CryptoMktErrorType::RequestServiceUnavailable),
+            ],
+        );
+        let client =
+            crate::AsyncCryptoMktClient::with_transport(API_KEY, SECRET_KEY, Box::new(transport));
+        let outcome = client
+            .create_market("ETHCLP")
+            .replace_order("R4", Decimal::new(250000, 0), None)
+            .await
+            .unwrap();
+        // Sin confirmar el estado no se crea la nueva orden
+        assert!(outcome.cancel.is_ok());
+        assert!(!outcome.is_cancelled());
+        assert!(outcome.placement.is_none());
+    }
+
+    /// Servidor Socket.IO local: por cada conexión devuelve los mensajes del cliente
+    /// recibidos antes de enviar `events` y luego cierra la conexión
+    #[cfg(feature = "websocket")]
+    async fn socket_server(
+        connections: Vec<(usize, Vec<String>)>,
+    ) -> (String, tokio::task::JoinHandle<Vec<Vec<String>>>) {
+        use futures::{SinkExt, StreamExt};
+        use tokio_tungstenite::tungstenite::Message;
+
+        let mut listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let mut received = Vec::new();
+            for (expected, events) in connections {
+                let (tcp, _) = listener.accept().await.unwrap();
+                let mut socket = tokio_tungstenite::accept_async(tcp).await.unwrap();
+                let open = "0{\"sid\":\"1\",\"pingInterval\":25000,\"pingTimeout\":60000}";
+                socket.send(Message::Text(open.to_string())).await.unwrap();
+                socket.send(Message::Text("40".to_string())).await.unwrap();
+                let mut messages = Vec::new();
+                while messages.len() < expected {
+                    match socket.next().await {
+                        Some(Ok(Message::Text(text))) => messages.push(text),
+                        Some(Ok(_)) => {}
+                        _ => break,
+                    }
+                }
+                for event in events {
+                    socket.send(Message::Text(event)).await.unwrap();
+                }
+                socket.close(None).await.unwrap();
+                received.push(messages);
+            }
+            received
+        });
+        (url, server)
+    }
+
+    #[cfg(feature = "websocket")]
+    #[tokio::test]
+    async fn market_stream_resubscribes_after_reconnect() {
+        use crate::{MarketEvent, MarketStream, SocketOptions};
+        use std::time::Duration;
+
+        let trades = json!(["trades", {"market": "ETHCLP", "data": [{
+            "market_taker": "buy", "price": "155000", "amount": "0.5",
+            "timestamp": "2017-05-29T22:14:00.419466", "market": "ETHCLP"
+        }]}]);
+        let first = vec![
+            format!("42{}", json!(["ticker", [{
+                "high": "6888", "low": "6303", "ask": "6887", "bid": "6416",
+                "last_price": "6610", "volume": "13.03",
+                "timestamp": "2017-08-29T15:44:17.267526", "market": "ETHCLP"
+            }]])),
+            format!("42{}", json!(["book", {"market": "ETHCLP", "type": "sell", "data": [
+                {"price": "252610", "amount": "0.6729", "timestamp": "2017-08-31T12:31:58.782060"},
+                {"price": "252900", "amount": "1.5", "timestamp": "2017-08-31T12:31:58.782060"}
+            ]}])),
+            format!("42{}", trades),
+        ];
+        let second = vec![format!("42{}", trades)];
+        let (url, server) = socket_server(vec![(2, first), (2, second)]).await;
+        let transport = SequenceMock::default().respond_with(
+            "",
+            vec![Ok(
+                "{\"status\":\"success\",\"data\":{\"socid\":\"S1\",\"uid\":42}}".to_string(),
+            )],
+        );
+        let client = crate::AsyncCryptoMktClient::with_transport(
+            API_KEY,
+            SECRET_KEY,
+            Box::new(transport.clone()),
+        );
+        let options = SocketOptions::default()
+            .url(&url)
+            .reconnect_delay(Duration::from_millis(10))
+            .max_reconnects(1);
+        let mut stream = MarketStream::new(client, options);
+        stream.subscribe("ethclp").await.unwrap();
+        assert_eq!(stream.subscriptions(), vec!["ETHCLP"]);
+
+        let mut events = Vec::new();
+        while let Some(event) = stream.next_event().await {
+            let name = match event {
+                MarketEvent::Connected { reconnected } => format!("connected {}", reconnected),
+                MarketEvent::Ticker(ticker) => format!("ticker {} {}", ticker.market, ticker.bid),
+                MarketEvent::Book {
+                    market,
+                    side,
+                    entries,
+                } => format!("book {} {:?} {}", market, side, entries.len()),
+                MarketEvent::Trades { market, trades } => {
+                    format!("trades {} {}", market, trades[0].amount)
+                }
+                MarketEvent::Other { event, .. } => format!("other {}", event),
+                MarketEvent::Disconnected { terminal, .. } => format!("disconnected {}", terminal),
+            };
+            events.push(name);
+            if events.len() == 7 {
+                break;
+            }
+        }
+        assert_eq!(
+            events,
+            vec![
+                "connected false",
+                "ticker ETHCLP 6416",
+                "book ETHCLP Sell 2",
+                "trades ETHCLP 0.5",
+                "disconnected false",
+                "connected true",
+                "trades ETHCLP 0.5",
+            ]
+        );
+        let handshake = vec![
+            "42[\"user-auth\",{\"socid\":\"S1\",\"uid\":\"42\"}]".to_string(),
+            "42[\"subscribe\",\"ETHCLP\"]".to_string(),
+        ];
+        assert_eq!(server.await.unwrap(), vec![handshake.clone(), handshake]);
+        assert_eq!(transport.requests(), vec!["", ""]);
+    }
+
+    #[cfg(feature = "websocket")]
+    #[tokio::test]
+    async fn account_stream_resyncs_after_reconnect() {
+        use crate::internal::api::sign_message;
+        use crate::{AccountEvent, AccountStream, SocketOptions};
+        use std::time::Duration;
+
+        let order_at = |id: &str, status: &str, updated_at: &str| {
+            let mut order = order_json(id, status, ("1", "1", "0"), "0");
+            order["updated_at"] = json!(updated_at);
+            order
+        };
+        let first = vec![
+            format!("42{}", json!(["balance", [
+                {"wallet": "CLP", "available": "1000", "balance": "1200"},
+                {"wallet": "ETH", "available": "0.5", "balance": "0.5"}
+            ]])),
+            format!("42{}", json!(["order", order_json("O1", "executed", ("1", "0", "1"), "100")])),
+            // Versión anterior de O1 que llega fuera de orden
+            format!("42{}", json!(["order", order_at("O1", "active", "2017-09-01T14:00:00")])),
+        ];
+        let second = vec![
+            // Anterior al estado completo obtenido al reconectar
+            format!("42{}", json!(["order", order_at("O2", "active", "2017-09-01T14:00:00")])),
+            format!("42{}", json!(["balance", {
+                "wallet": "CLP", "available": "900", "balance": "900"
+            }])),
+            format!("42{}", json!(["order", order_at("O2", "executed", "2017-09-01T14:05:00")])),
+        ];
+        let (url, server) = socket_server(vec![(2, first), (2, second)]).await;
+        let auth = "{\"status\":\"success\",\"data\":{\"socid\":\"S1\",\"uid\":\"42\"}}";
+        let balance = "{\"status\":\"success\",\"data\":[{\"wallet\":\"CLP\",\"available\":\"950\",\
+                       \"balance\":\"1000\"}]}";
+        let transport = SequenceMock::default().respond_with(
+            "",
+            vec![
+                Ok(auth.to_string()),
+                Ok(auth.to_string()),
+                Ok(balance.to_string()),
+                active_orders(&[("O2", "sell")], 0, None),
+            ],
+        );
+        let client = crate::AsyncCryptoMktClient::with_transport(
+            API_KEY,
+            SECRET_KEY,
+            Box::new(transport.clone()),
+        );
+        let options = SocketOptions::default()
+            .url(&url)
+            .reconnect_delay(Duration::from_millis(10))
+            .max_reconnects(1);
+        let mut stream = AccountStream::new(client, options).track_market("ethclp");
+        assert_eq!(stream.markets(), vec!["ETHCLP"]);
+
+        let mut events = Vec::new();
+        while let Some(event) = stream.next_event().await {
+            let name = match event {
+                AccountEvent::Connected { reconnected } => format!("connected {}", reconnected),
+                AccountEvent::BalanceUpdated(balance) => {
+                    format!("balance {} {}", balance.wallet, balance.available)
+                }
+                AccountEvent::OrderUpdated(order) => {
+                    format!("order {} {:?}", order.id, order.status)
+                }
+                AccountEvent::Snapshot(snapshot) => format!(
+                    "snapshot {} {} {}",
+                    snapshot.balances[0].available,
+                    snapshot.orders.len(),
+                    snapshot.orders[0].id
+                ),
+                AccountEvent::SnapshotFailed(e) => format!("snapshot failed {}", e),
+                AccountEvent::Other { event, .. } => format!("other {}", event),
+                AccountEvent::Disconnected { terminal, .. } => format!("disconnected {}", terminal),
+            };
+            events.push(name);
+            if events.len() == 9 {
+                break;
+            }
+        }
+        assert_eq!(
+            events,
+            vec![
+                "connected false",
+                "balance CLP 1000",
+                "balance ETH 0.5",
+                "order O1 Executed",
+                "disconnected false",
+                "connected true",
+                "snapshot 950 1 O2",
+                "balance CLP 900",
+                "order O2 Executed",
+            ]
+        );
+
+        let connections = server.await.unwrap();
+        assert_eq!(connections.len(), 2);
+        for messages in connections {
+            assert_eq!(messages[0], "42[\"user-auth\",{\"socid\":\"S1\",\"uid\":\"42\"}]");
+            let packet: serde_json::Value = serde_json::from_str(&messages[1][2..]).unwrap();
+            assert_eq!(packet[0], "private-subscribe");
+            let credentials = &packet[1];
+            assert_eq!(credentials["channel"], "account");
+            assert_eq!(credentials["api_key"], API_KEY);
+            let timestamp = credentials["timestamp"].as_str().unwrap();
+            assert_eq!(
+                credentials["signature"],
+                sign_message(SECRET_KEY, &format!("{}account", timestamp))
+            );
+        }
+        assert_eq!(transport.requests(), vec!["", "", "", ""]);
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn full_order_book_across_pages() {
+        use crate::{LevelChange, OrderType, PriceLevel};
+
+        let transport = SequenceMock::default().respond_with(
+            "",
+            vec![
+                book_page(&[("250000", "1"), ("249000", "2")], 0, Some(1)),
+                book_page(&[("250000", "0.5"), ("248000", "3")], 1, None),
+                book_page(&[("251000", "1"), ("252000", "2"), ("255000", "4")], 0, None),
+                book_page(&[("250000", "1.5"), ("248000", "3")], 0, None),
+                book_page(&[("251000", "1"), ("252000", "1.5"), ("255000", "4")], 0, None),
+            ],
+        );
+        let client =
+            crate::CryptoMktClient::with_transport(API_KEY, SECRET_KEY, Box::new(transport.clone()));
+        let market = client.create_market("ETHCLP");
+        let mut book = market.get_full_order_book().unwrap();
+        let level = |price: i64, amount: i64, scale: u32| PriceLevel {
+            price: Decimal::new(price, 0),
+            amount: Decimal::new(amount, scale),
+        };
+
+        assert_eq!(book.market(), "ETHCLP");
+        assert_eq!(
+            book.bids(),
+            vec![level(250000, 15, 1), level(249000, 2, 0), level(248000, 3, 0)]
+        );
+        assert_eq!(
+            book.asks(),
+            vec![level(251000, 1, 0), level(252000, 2, 0), level(255000, 4, 0)]
+        );
+        assert_eq!(book.best_bid(), Some(level(250000, 15, 1)));
+        assert_eq!(book.best_ask(), Some(level(251000, 1, 0)));
+        assert_eq!(book.spread(), Some(Decimal::new(1000, 0)));
+        assert_eq!(book.mid_price(), Some(Decimal::new(250500, 0)));
+        assert_eq!(book.amount_at(&OrderType::Buy, Decimal::new(249000, 0)), Decimal::new(2, 0));
+        assert_eq!(book.depth_at(&OrderType::Buy, Decimal::new(249000, 0)), Decimal::new(35, 1));
+        assert_eq!(book.depth_at(&OrderType::Sell, Decimal::new(252000, 0)), Decimal::new(3, 0));
+
+        let fill = book.cumulative_volume(&OrderType::Sell, Decimal::new(2, 0));
+        assert!(fill.complete);
+        assert_eq!(fill.volume, Decimal::new(503000, 0));
+        assert_eq!(fill.average_price, Some(Decimal::new(251500, 0)));
+        assert_eq!(fill.worst_price, Some(Decimal::new(252000, 0)));
+        assert_eq!(fill.levels, 2);
+        let fill = book.cumulative_volume(&OrderType::Buy, Decimal::new(10, 0));
+        assert!(!fill.complete);
+        assert_eq!(fill.amount, Decimal::new(65, 1));
+
+        let changes = market.refresh_order_book(&mut book).unwrap();
+        assert_eq!(
+            changes,
+            vec![
+                LevelChange {
+                    side: OrderType::Buy,
+                    price: Decimal::new(249000, 0),
+                    amount: Decimal::ZERO,
+                },
+                LevelChange {
+                    side: OrderType::Sell,
+                    price: Decimal::new(252000, 0),
+                    amount: Decimal::new(15, 1),
+                },
+            ]
+        );
+        assert_eq!(book.bids(), vec![level(250000, 15, 1), level(248000, 3, 0)]);
+        assert_eq!(transport.requests().len(), 5);
+    }
+
+    #[test]
+    fn order_book_incremental_updates() {
+        use crate::{OrderBook, OrderType};
+
+        let mut book = OrderBook::new("ETHCLP");
+        assert!(book.is_empty());
+        assert_eq!(book.spread(), None);
+        assert_eq!(book.cumulative_volume(&OrderType::Sell, Decimal::ONE).average_price, None);
+
+        book.update(OrderType::Buy, Decimal::new(100, 0), Decimal::new(1, 0));
+        book.update(OrderType::Sell, Decimal::new(110, 0), Decimal::new(1, 0));
+        book.update(OrderType::Sell, Decimal::new(105, 0), Decimal::new(2, 0));
+        assert_eq!(book.best_ask().map(|level| level.price), Some(Decimal::new(105, 0)));
+
+        let entries: Vec<crate::models::Book> = serde_json::from_value(json!([
+            {"price": "105", "amount": "0", "timestamp": "2017-08-31T12:31:58.782060"},
+            {"price": "101", "amount": "0.5", "timestamp": "2017-08-31T12:31:58.782060"}
+        ]))
+        .unwrap();
+        let mut updated = book.clone();
+        updated.apply_entries(OrderType::Sell, &entries[..1]);
+        updated.apply_entries(OrderType::Buy, &entries[1..]);
+        assert_eq!(updated.best_ask().map(|level| level.price), Some(Decimal::new(110, 0)));
+        assert_eq!(updated.best_bid().map(|level| level.price), Some(Decimal::new(101, 0)));
+        assert_eq!(updated.spread(), Some(Decimal::new(9, 0)));
+
+        let changes = book.diff(&updated);
+        assert_eq!(changes.len(), 2);
+        book.apply(&changes);
+        assert_eq!(book, updated);
+
+        // Un nivel de precio cero no entra al libro ni rompe el recorrido por volumen
+        let entries: Vec<crate::models::Book> = serde_json::from_value(json!([
+            {"price": "0", "amount": "3", "timestamp": "2017-08-31T12:31:58.782060"},
+            {"price": "-1", "amount": "1", "timestamp": "2017-08-31T12:31:58.782060"}
+        ]))
+        .unwrap();
+        book.apply_entries(OrderType::Sell, &entries);
+        book.update(OrderType::Sell, Decimal::ZERO, Decimal::ONE);
+        assert_eq!(book, updated);
+        let book = OrderBook::from_entries("ETHCLP", &entries, &entries);
+        assert_eq!(book.best_ask(), None);
+        assert_eq!(book.best_bid(), None);
+        let fill = book.fill_volume(&OrderType::Sell, Decimal::new(100, 0));
+        assert_eq!(fill.levels, 0);
+        assert!(!fill.complete);
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn instant_order_slippage_guard() {
+        use crate::{EstimateSource, OrderType};
+
+        // Compra por 1000 CLP: 9.5 ETH a un precio promedio de 105.26 contra un medio de 100
+        let mut responses = instant_quote("9.5", "1000");
+        // Compra por 1000 CLP: 9.9 ETH, 1.01% sobre el medio
+        responses.extend(instant_quote("9.9", "1000"));
+        responses.extend(instant_quote("9.9", "1000"));
+        responses.push(Ok("{\"status\":\"success\",\"data\":\"\"}".to_string()));
+        let transport = SequenceMock::default().respond_with("", responses);
+        let client =
+            crate::CryptoMktClient::with_transport(API_KEY, SECRET_KEY, Box::new(transport.clone()));
+        let market = client.create_market("ETHCLP");
+        let amount = Decimal::new(1000, 0);
+
+        let error = market
+            .create_order_instant_with_max_slippage(OrderType::Buy, amount, Decimal::new(2, 2))
+            .unwrap_err();
+        assert_eq!(error.kind(), CryptoMktErrorType::SlippageExceeded);
+        assert_eq!(
+            error.message(),
+            Some("estimated slippage 0.052632 is above the maximum 0.02")
+        );
+        assert_eq!(transport.requests().len(), 2);
+
+        let estimate = market.estimate_order_instant(OrderType::Buy, amount).unwrap();
+        assert_eq!(estimate.source, EstimateSource::Quote);
+        assert_eq!(estimate.mid_price, Some(Decimal::new(100, 0)));
+        assert_eq!(estimate.worst_price, None);
+        assert!(estimate.complete);
+        assert_eq!(estimate.slippage().map(|s| s.round_dp(4)), Some(Decimal::new(101, 4)));
+        market
+            .create_order_instant_with_max_slippage(OrderType::Buy, amount, Decimal::new(2, 2))
+            .unwrap();
+        assert_eq!(transport.requests().len(), 7);
+
+        // Un ticker sin datos detiene la orden sin enviarla
+        let empty = Ok("{\"status\":\"success\",\"data\":[]}".to_string());
+        let transport = SequenceMock::default()
+            .respond_with("", vec![instant_quote("9.9", "1000").remove(0), empty]);
+        let client =
+            crate::CryptoMktClient::with_transport(API_KEY, SECRET_KEY, Box::new(transport.clone()));
+        let error = client
+            .create_market("ETHCLP")
+            .create_order_instant_with_max_slippage(OrderType::Buy, amount, Decimal::new(2, 2))
+            .unwrap_err();
+        assert_eq!(error.kind(), CryptoMktErrorType::MalformedResource);
+        assert_eq!(error.message(), Some("empty ticker for market ETHCLP"));
+        assert_eq!(transport.requests().len(), 2);
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn slippage_guard_quotes_the_rounded_amount() {
+        use crate::{MarketRegistry, OrderType};
+
+        let mut responses = instant_quote("9.9", "1000");
+        responses.push(Ok("{\"status\":\"success\",\"data\":\"\"}".to_string()));
+        let transport = SequenceMock::default().respond_with("", responses);
+        let registry = MarketRegistry::from_json(
+            r#"{"markets": [{"name": "ETHCLP", "amount_step": "0.0001", "quote_decimals": 0}]}"#,
+        )
+        .unwrap();
+        let client =
+            crate::CryptoMktClient::with_transport(API_KEY, SECRET_KEY, Box::new(transport.clone()))
+                .with_market_registry(registry);
+        let market = client.create_market("ETHCLP");
+
+        // Se cotiza y se envía la compra por 1000 CLP, no por 1000.7
+        market
+            .create_order_instant_with_max_slippage(
+                OrderType::Buy,
+                Decimal::new(10007, 1),
+                Decimal::new(2, 2),
+            )
+            .unwrap();
+        assert_eq!(transport.amounts(), vec!["1000", "1000"]);
+
+        let amount = Decimal::new(5, 5);
+        let error = market
+            .create_order_instant_with_max_slippage(OrderType::Sell, amount, Decimal::ONE)
+            .unwrap_err();
+        assert_eq!(error.kind(), CryptoMktErrorType::InvalidArgument);
+        assert_eq!(transport.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn slippage_from_order_book_and_async_quote() {
+        use crate::{OrderBook, OrderType, SlippageEstimate};
+
+        let mut book = OrderBook::new("ETHCLP");
+        book.update(OrderType::Buy, Decimal::new(99, 0), Decimal::new(2, 0));
+        book.update(OrderType::Buy, Decimal::new(95, 0), Decimal::new(5, 0));
+        book.update(OrderType::Sell, Decimal::new(101, 0), Decimal::new(1, 0));
+
+        // Venta de 4 ETH: 2 a 99 y 2 a 95
+        let estimate =
+            SlippageEstimate::from_order_book(OrderType::Sell, Decimal::new(4, 0), &book);
+        assert!(estimate.complete);
+        assert_eq!(estimate.obtained, Decimal::new(388, 0));
+        assert_eq!(estimate.average_price, Some(Decimal::new(97, 0)));
+        assert_eq!(estimate.worst_price, Some(Decimal::new(95, 0)));
+        assert_eq!(estimate.slippage(), Some(Decimal::new(3, 2)));
+        assert!(estimate.check(Decimal::new(3, 2)).is_ok());
+        // La compra por 500 CLP no se cubre con 1 ETH a la venta
+        let estimate =
+            SlippageEstimate::from_order_book(OrderType::Buy, Decimal::new(500, 0), &book);
+        assert!(!estimate.complete);
+        assert_eq!(
+            estimate.check(Decimal::ONE).unwrap_err().message(),
+            Some("the market cannot fill the whole amount")
+        );
+
+        // Venta de 2 ETH cotizada en 196 CLP, 2% bajo el medio
+        let transport = SequenceMock::default().respond_with("", instant_quote("196", "2"));
+        let client =
+            crate::AsyncCryptoMktClient::with_transport(API_KEY, SECRET_KEY, Box::new(transport));
+        let market = client.create_market("ETHCLP");
+        let error = market
+            .create_order_instant_with_max_slippage(
+                OrderType::Sell,
+                Decimal::new(2, 0),
+                Decimal::new(1, 2),
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(error.kind(), CryptoMktErrorType::SlippageExceeded);
+        assert_eq!(
+            error.message(),
+            Some("estimated slippage 0.02 is above the maximum 0.01")
+        );
+
+        let empty = Ok("{\"status\":\"success\",\"data\":[]}".to_string());
+        let transport = SequenceMock::default()
+            .respond_with("", vec![instant_quote("196", "2").remove(0), empty]);
+        let client =
+            crate::AsyncCryptoMktClient::with_transport(API_KEY, SECRET_KEY, Box::new(transport));
+        let error = client
+            .create_market("ETHCLP")
+            .create_order_instant_with_max_slippage(
+                OrderType::Sell,
+                Decimal::new(2, 0),
+                Decimal::new(1, 2),
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(error.kind(), CryptoMktErrorType::MalformedResource);
+        assert_eq!(error.message(), Some("empty ticker for market ETHCLP"));
+    }
+
+    #[test]
+    fn split_market_names() {
+        use crate::market::split_market_name;
+        let split = |name: &str| split_market_name(name);
+        assert_eq!(split("ETHCLP"), ("ETH".to_string(), "CLP".to_string()));
+        assert_eq!(split("eosbrl"), ("EOS".to_string(), "BRL".to_string()));
+        assert_eq!(split("ETHBTC"), ("ETH".to_string(), "BTC".to_string()));
+        assert_eq!(split("DOGEXYZ"), ("DOGE".to_string(), "XYZ".to_string()));
+        assert_eq!(split("CLP"), ("".to_string(), "CLP".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_async_market_with_custom_transport() {
+        let mock_transport = MockRequest::new(
+            "{\"status\":\"success\",\"data\":{\"obtained\":\"18047138.226\",\"required\":\"159\"}}",
+            "",
+        );
+        let client = crate::AsyncCryptoMktClient::with_transport(
+            API_KEY,
+            SECRET_KEY,
+            Box::new(mock_transport),
+        );
+
+        let market = client.create_market("ETHCLP");
+        let instant = market
+            .get_order_instant(crate::OrderType::Sell, Decimal::new(159, 0))
+            .await
+            .unwrap();
+        assert_eq!(instant.obtained, Decimal::new(18047138226, 3));
+        assert_eq!(instant.required, Decimal::new(159, 0));
+    }
+
+    ///
+    /// Servidor HTTP local que responde, en orden, una respuesta por conexión.
+    /// Devuelve la URL base y el hilo que retorna la cantidad de peticiones atendidas
+    ///
+    #[cfg(feature = "blocking")]
+    fn serve_responses(responses: Vec<&'static str>) -> (String, thread::JoinHandle<usize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let handle = thread::spawn(move || {
+            let mut served = 0;
+            for resp in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut buf = [0u8; 4096];
+                let _ = stream.read(&mut buf).unwrap();
+                stream.write_all(resp.as_bytes()).unwrap();
+                served += 1;
+            }
+            served
+        });
+        (url, handle)
+    }
+
+    #[test]
+    fn retry_policy_should_retry() {
+        let policy = RetryPolicy::default().retry_post("orders/cancel");
+        let get_url = Url::parse("https://api.cryptomkt.com/v1/book").unwrap();
+        let create_url = Url::parse("https://api.cryptomkt.com/v1/orders/create").unwrap();
+        let cancel_url = Url::parse("https://api.cryptomkt.com/v1/orders/cancel").unwrap();
+        let busy = CryptoMktError::from(CryptoMktErrorType::RequestServiceUnavailable);
+        let unauthorized = CryptoMktError::from(CryptoMktErrorType::RequestUnauthorized);
+
+        assert!(policy.should_retry(&busy, 1, false, &get_url));
+        assert!(policy.should_retry(&busy, 2, false, &get_url));
+        assert!(!policy.should_retry(&busy, 3, false, &get_url));
+        assert!(!policy.should_retry(&unauthorized, 1, false, &get_url));
+        assert!(!policy.should_retry(&busy, 1, true, &create_url));
+        assert!(policy.should_retry(&busy, 1, true, &cancel_url));
+        assert!(RetryPolicy::default()
+            .retry_all_posts(true)
+            .should_retry(&busy, 1, true, &create_url));
+        assert!(!RetryPolicy::none().should_retry(&busy, 1, false, &get_url));
+    }
+
+    #[test]
+    fn retry_policy_delay() {
+        let policy = RetryPolicy::default()
+            .base_delay(Duration::from_millis(100))
+            .max_delay(Duration::from_millis(300))
+            .jitter(false);
+        assert_eq!(policy.delay(1, None), Duration::from_millis(100));
+        assert_eq!(policy.delay(2, None), Duration::from_millis(200));
+        assert_eq!(policy.delay(3, None), Duration::from_millis(300));
+        assert_eq!(
+            policy.delay(1, Some(Duration::from_secs(10))),
+            Duration::from_millis(300)
+        );
+        let delay = RetryPolicy::default()
+            .base_delay(Duration::from_millis(100))
+            .delay(1, None);
+        assert!(delay >= Duration::from_millis(50) && delay <= Duration::from_millis(100));
+    }
+
+    #[test]
+    fn parse_retry_after_header() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(retry_after(&headers), None);
+        headers.insert("Retry-After", "3".parse().unwrap());
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(3)));
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn transport_retries_transient_errors() {
+        let (url, server) = serve_responses(vec![
+            "HTTP/1.1 503 Service Unavailable\r\nRetry-After: 0\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            "HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok",
+        ]);
+        let transport = CryptoMktRequest::with_retry_policy(
+            RetryPolicy::default().base_delay(Duration::from_millis(1)),
+        );
+
+        let resp = transport.get(Url::parse(&url).unwrap(), HeaderMap::new());
+        assert_eq!(resp.unwrap(), "ok");
+        assert_eq!(server.join().unwrap(), 2);
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn transport_does_not_retry_non_idempotent_post() {
+        let (url, server) = serve_responses(vec![
+            "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        ]);
+        let transport = CryptoMktRequest::with_retry_policy(
+            RetryPolicy::default().base_delay(Duration::from_millis(1)),
+        );
+
+        let url = Url::parse(&url).unwrap().join("v1/orders/create").unwrap();
+        let resp = transport.post(url, HeaderMap::new(), HashMap::new());
+        match resp {
+            Err(ref e) if e.kind() == CryptoMktErrorType::RequestServiceUnavailable => {
+                assert_eq!(e.status(), Some(503));
+                assert_eq!(e.method(), Some("POST"));
+            }
+            other => panic!("Unexpected response {:?}", other),
+        }
+        assert_eq!(server.join().unwrap(), 1);
+    }
+
+    ///
+    /// URL de un puerto local sin servidor, las conexiones se rechazan
+    ///
+    fn closed_port_url() -> String {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/v1/", listener.local_addr().unwrap());
+        drop(listener);
+        url
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn transport_retries_connection_errors() {
+        let transport = CryptoMktRequest::with_retry_policy(
+            RetryPolicy::default()
+                .max_attempts(3)
+                .base_delay(Duration::from_millis(50))
+                .jitter(false),
+        );
+        let url = Url::parse(&closed_port_url()).unwrap();
+
+        let start = std::time::Instant::now();
+        let error = transport.get(url.join("book").unwrap(), HeaderMap::new()).unwrap_err();
+        assert_eq!(error.kind(), CryptoMktErrorType::NetworkError);
+        assert!(error.is_retryable());
+        assert_eq!(error.method(), Some("GET"));
+        assert!(error.source().is_some());
+        // Dos esperas entre los tres intentos: 50ms y 100ms
+        assert!(start.elapsed() >= Duration::from_millis(150));
+
+        // Las peticiones POST no idempotentes no se reintentan
+        let start = std::time::Instant::now();
+        let error = transport
+            .post(url.join("orders/create").unwrap(), HeaderMap::new(), HashMap::new())
+            .unwrap_err();
+        assert_eq!(error.kind(), CryptoMktErrorType::NetworkError);
+        assert!(start.elapsed() < Duration::from_millis(50));
+    }
+
+    #[tokio::test]
+    async fn async_transport_retries_connection_errors() {
+        use crate::internal::request::CryptoMktAsyncRequest;
+
+        let transport = CryptoMktAsyncRequest::with_retry_policy(
+            RetryPolicy::default()
+                .max_attempts(2)
+                .base_delay(Duration::from_millis(50))
+                .jitter(false),
+        );
+        let url = Url::parse(&closed_port_url()).unwrap().join("book").unwrap();
+
+        let start = std::time::Instant::now();
+        let error = AsyncHttpRequest::get(&transport, url, HeaderMap::new())
+            .await
+            .unwrap_err();
+        assert_eq!(error.kind(), CryptoMktErrorType::NetworkError);
+        assert!(start.elapsed() >= Duration::from_millis(50));
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn error_keeps_status_endpoint_and_server_message() {
+        let (url, server) = serve_responses(vec![
+            "HTTP/1.1 401 Unauthorized\r\nContent-Length: 42\r\nConnection: close\r\n\r\n{\"status\":\"error\",\"message\":\"invalid key\"}",
+        ]);
+        let api = crate::CryptoMktApi::builder(API_KEY, SECRET_KEY)
+            .domain(&url)
+            .build()
+            .unwrap();
+
+        let resp = api.call::<BalanceResponse>(crate::RequestMethod::Get(false), "balance", HashMap::new());
+        let error = resp.unwrap_err();
+        assert_eq!(error.kind(), CryptoMktErrorType::RequestUnauthorized);
+        assert_eq!(error.status(), Some(401));
+        assert_eq!(error.method(), Some("GET"));
+        assert_eq!(error.endpoint(), Some("balance"));
+        assert_eq!(error.message(), Some("invalid key"));
+        assert!(!error.is_retryable());
+        assert_eq!(
+            error.to_string(),
+            "invalid API key (HTTP 401) on GET balance: invalid key"
+        );
+        assert_eq!(server.join().unwrap(), 1);
+    }
+
+    #[test]
+    fn error_keeps_serde_source() {
+        let api = Api::new(API_KEY, SECRET_KEY, Box::new(MockRequest::new("{\"status\":", "")));
+        let resp: CryptoMktResult<TickerResponse> = api.get_edge("ticker", HashMap::new(), true);
+        let error = resp.unwrap_err();
+        assert_eq!(error.kind(), CryptoMktErrorType::MalformedResource);
+        assert_eq!(error.endpoint(), Some("ticker"));
+        assert!(error.source().is_some());
+        assert_eq!(error.status(), None);
+    }
+
+    #[test]
+    fn status_error_payload_is_an_error() {
+        let api = Api::new(
+            API_KEY,
+            SECRET_KEY,
+            Box::new(MockRequest::new(
+                "{\"status\":\"error\",\"message\":\"Invalid market\"}",
+                "{\"status\":\"error\",\"message\":\"Insufficient funds\"}",
+            )),
+        );
+        let resp: CryptoMktResult<TickerResponse> = api.get_edge("ticker", HashMap::new(), true);
+        let error = resp.unwrap_err();
+        assert_eq!(error.kind(), CryptoMktErrorType::InvalidMarket);
+        assert_eq!(error.message(), Some("Invalid market"));
+        assert_eq!(error.endpoint(), Some("ticker"));
+        assert_eq!(error.method(), Some("GET"));
+
+        let resp: CryptoMktResult<SimpleOrderResponse> = api.post_edge("orders/create", HashMap::new());
+        assert_eq!(resp.unwrap_err().kind(), CryptoMktErrorType::InsufficientFunds);
+    }
+
+    #[tokio::test]
+    async fn status_error_payload_is_an_error_async() {
+        let api = Api::new(
+            API_KEY,
+            SECRET_KEY,
+            Box::new(MockRequest::new(
+                "{\"status\":\"error\"}",
+                "{\"status\":\"error\",\"message\":\"Invalid signature\"}",
+            )),
+        );
+        let resp: CryptoMktResult<BalanceResponse> =
+            api.get_edge_async("balance", HashMap::new(), false).await;
+        let error = resp.unwrap_err();
+        assert_eq!(error.kind(), CryptoMktErrorType::ExchangeError);
+        assert_eq!(error.message(), None);
+
+        let resp: CryptoMktResult<SimpleOrderResponse> =
+            api.post_edge_async("orders/create", HashMap::new()).await;
+        assert_eq!(resp.unwrap_err().kind(), CryptoMktErrorType::InvalidSignature);
+    }
+
+    #[test]
+    fn error_type_from_exchange_message() {
+        let cases = vec![
+            ("not enough balance", CryptoMktErrorType::InsufficientFunds),
+            ("Insufficient funds", CryptoMktErrorType::InsufficientFunds),
+            ("invalid market", CryptoMktErrorType::InvalidMarket),
+            ("Market XYZ does not exist", CryptoMktErrorType::InvalidMarket),
+            ("Amount is below the minimum for ETHCLP market", CryptoMktErrorType::AmountBelowMinimum),
+            ("invalid signature", CryptoMktErrorType::InvalidSignature),
+            ("something else", CryptoMktErrorType::ExchangeError),
+        ];
+        for (message, kind) in cases {
+            assert_eq!(CryptoMktErrorType::from_exchange_message(message), kind, "{}", message);
+        }
+    }
+
+    #[test]
+    fn error_retry_after_and_retryable() {
+        let error = CryptoMktError::from(CryptoMktErrorType::RequestTooManyRequests)
+            .with_status(429)
+            .with_retry_after(Duration::from_secs(3));
+        assert!(error.is_retryable());
+        assert_eq!(error.retry_after(), Some(Duration::from_secs(3)));
+        assert_eq!(error.to_string(), "too many requests (HTTP 429)");
+        assert!(!CryptoMktError::from(CryptoMktErrorType::BadRequest).is_retryable());
+    }
+
+    #[test]
+    fn rate_limiter_fail_fast() {
+        let limiter = RateLimiter::new(
+            Quota::per_minute(2),
+            Quota::per_minute(1),
+            RateLimitMode::FailFast,
+        );
+        assert_eq!(limiter.utilisation(RateLimitScope::Public), 0.0);
+        assert!(limiter.reserve(RateLimitScope::Public).is_ok());
+        assert!(limiter.reserve(RateLimitScope::Public).is_ok());
+        match limiter.reserve(RateLimitScope::Public) {
+            Err(ref e) if e.kind() == CryptoMktErrorType::RateLimitExceeded => {}
+            other => panic!("Unexpected reservation {:?}", other),
+        }
+        assert!(limiter.utilisation(RateLimitScope::Public) > 0.99);
+
+        // El presupuesto privado es independiente
+        assert!(limiter.reserve(RateLimitScope::Private).is_ok());
+        assert!(limiter.reserve(RateLimitScope::Private).is_err());
+    }
+
+    #[test]
+    fn rate_limiter_queue() {
+        let limiter = RateLimiter::new(
+            Quota::per_second(1),
+            Quota::per_second(1),
+            RateLimitMode::Queue,
+        );
+        assert_eq!(
+            limiter.reserve(RateLimitScope::Public).unwrap(),
+            Duration::from_secs(0)
+        );
+        let wait = limiter.reserve(RateLimitScope::Public).unwrap();
+        assert!(wait > Duration::from_millis(900) && wait <= Duration::from_secs(1));
+        let wait = limiter.reserve(RateLimitScope::Public).unwrap();
+        assert!(wait > Duration::from_millis(1900) && wait <= Duration::from_secs(2));
+        assert!(limiter.utilisation(RateLimitScope::Public) > 2.9);
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn rate_limiter_shared_between_clones() {
+        let limiter = Arc::new(RateLimiter::new(
+            Quota::per_minute(1),
+            Quota::per_minute(1),
+            RateLimitMode::FailFast,
+        ));
+        let api = crate::CryptoMktApi::builder(API_KEY, SECRET_KEY)
+            .transport(Box::new(MockRequest::new(
+                "{\"status\":\"success\",\"data\":[{\"high\":\"6888\",\"volume\":\"13.03\",\"low\":\"6303\",\"ask\":\"6887\",\"timestamp\":\"2017-08-2915:44:17.267526\",\"bid\":\"6416\",\"last_price\":\"6610\",\"market\":\"ETHARS\"}]}",
+                "",
+            )))
+            .rate_limiter(limiter.clone())
+            .build()
+            .unwrap();
+        let market_a = crate::Market::new(api.clone(), "ETHCLP");
+        let market_b = crate::Market::new(api.clone(), "ETHARS");
+
+        assert!(market_a.get_current_ticker().is_ok());
+        match market_b.get_current_ticker() {
+            Err(ref e) if e.kind() == CryptoMktErrorType::RateLimitExceeded => {}
+            other => panic!("Unexpected response {:?}", other),
+        }
+        assert!(api.rate_limiter().unwrap().utilisation(RateLimitScope::Public) > 0.99);
+        assert!(limiter.reserve(RateLimitScope::Private).is_ok());
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn rate_limiter_reserves_every_retry() {
+        let (url, server) = serve_responses(vec![
+            "HTTP/1.1 503 Service Unavailable\r\nRetry-After: 0\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            "HTTP/1.1 200 OK\r\nContent-Length: 4\r\nConnection: close\r\n\r\nnull",
+        ]);
+        let limiter = Arc::new(RateLimiter::new(
+            Quota::per_minute(10),
+            Quota::per_minute(10),
+            RateLimitMode::FailFast,
+        ));
+        let api = crate::CryptoMktApi::builder(API_KEY, SECRET_KEY)
+            .domain(&url)
+            .retry_policy(RetryPolicy::default().base_delay(Duration::from_millis(1)))
+            .rate_limiter(limiter.clone())
+            .build()
+            .unwrap();
+
+        let resp = api.call::<serde_json::Value>(crate::RequestMethod::Get(true), "ticker", HashMap::new());
+        assert!(resp.is_ok());
+        assert_eq!(server.join().unwrap(), 2);
+        let public = limiter.utilisation(RateLimitScope::Public);
+        assert!(public > 0.19 && public < 0.21, "public utilisation {}", public);
+        assert!(limiter.utilisation(RateLimitScope::Private) < 0.01);
+    }
 }
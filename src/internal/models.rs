@@ -1,5 +1,240 @@
-use serde::{Deserialize, Serialize};
+use crate::internal::dates::{timestamp, Timestamp};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_json::Value;
+use std::fmt::{self, Display, Formatter};
+
+///
+/// Lenient decimal deserializers, they accept strings and numbers and read
+/// `null` or empty strings as missing values (or zero)
+///
+mod decimal {
+    use rust_decimal::Decimal;
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer};
+    use serde_json::Value;
+    use std::str::FromStr;
+
+    fn parse(value: &str) -> Result<Decimal, rust_decimal::Error> {
+        Decimal::from_str(value).or_else(|_| Decimal::from_scientific(value))
+    }
+
+    pub fn option<'de, D>(deserializer: D) -> Result<Option<Decimal>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match Value::deserialize(deserializer)? {
+            Value::Null => Ok(None),
+            Value::String(value) if value.trim().is_empty() => Ok(None),
+            Value::String(value) => parse(value.trim()).map(Some).map_err(D::Error::custom),
+            Value::Number(value) => parse(&value.to_string()).map(Some).map_err(D::Error::custom),
+            other => Err(D::Error::custom(format!("invalid decimal: {}", other))),
+        }
+    }
+
+    pub fn or_zero<'de, D>(deserializer: D) -> Result<Decimal, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ok(option(deserializer)?.unwrap_or_default())
+    }
+}
+
+///
+/// Order Type, side of an order or a trade
+///
+/// Unknown values sent by the server are kept in `Other`
+///
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[serde(from = "String", into = "String")]
+pub enum OrderType {
+    Buy,
+    Sell,
+    Other(String),
+}
+
+impl From<String> for OrderType {
+    fn from(value: String) -> Self {
+        match value.to_lowercase().as_str() {
+            "buy" => OrderType::Buy,
+            "sell" => OrderType::Sell,
+            _ => OrderType::Other(value),
+        }
+    }
+}
+
+impl From<OrderType> for String {
+    fn from(value: OrderType) -> Self {
+        value.to_string()
+    }
+}
+
+impl Display for OrderType {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            OrderType::Buy => write!(f, "buy"),
+            OrderType::Sell => write!(f, "sell"),
+            OrderType::Other(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+///
+/// Order State
+///
+/// Unknown values sent by the server are kept in `Other`
+///
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[serde(from = "String", into = "String")]
+pub enum OrderState {
+    Active,
+    Executed,
+    Other(String),
+}
+
+impl Default for OrderState {
+    /// Missing state, `Other("")`
+    fn default() -> Self {
+        OrderState::Other(String::new())
+    }
+}
+
+impl From<String> for OrderState {
+    fn from(value: String) -> Self {
+        match value.to_lowercase().as_str() {
+            "active" => OrderState::Active,
+            "executed" => OrderState::Executed,
+            _ => OrderState::Other(value),
+        }
+    }
+}
+
+impl From<OrderState> for String {
+    fn from(value: OrderState) -> Self {
+        value.to_string()
+    }
+}
+
+impl Display for OrderState {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            OrderState::Active => write!(f, "active"),
+            OrderState::Executed => write!(f, "executed"),
+            OrderState::Other(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+///
+/// State of a payment order, sent by the server as a numeric code
+///
+/// Unknown codes are kept in `Other`
+///
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PaymentStatus {
+    /// -4: Multiple payments
+    MultiplePayments,
+    /// -3: The paid amount does not match the required amount
+    AmountMismatch,
+    /// -2: Conversion failed
+    ConversionFailed,
+    /// -1: Payment order expired
+    Expired,
+    /// 0: Waiting for the payment
+    WaitingForPayment,
+    /// 1: Waiting for the block
+    WaitingForBlock,
+    /// 2: Waiting for the processing
+    Processing,
+    /// 3: Successful payment
+    Successful,
+    /// Unknown code
+    Other(String),
+}
+
+impl PaymentStatus {
+    ///
+    /// Numeric code of the status, `None` for unknown codes
+    ///
+    pub fn code(&self) -> Option<i32> {
+        match self {
+            PaymentStatus::MultiplePayments => Some(-4),
+            PaymentStatus::AmountMismatch => Some(-3),
+            PaymentStatus::ConversionFailed => Some(-2),
+            PaymentStatus::Expired => Some(-1),
+            PaymentStatus::WaitingForPayment => Some(0),
+            PaymentStatus::WaitingForBlock => Some(1),
+            PaymentStatus::Processing => Some(2),
+            PaymentStatus::Successful => Some(3),
+            PaymentStatus::Other(_) => None,
+        }
+    }
+
+    ///
+    /// Indicates if the status is final: successful, expired or failed
+    ///
+    pub fn is_final(&self) -> bool {
+        !matches!(
+            self,
+            PaymentStatus::WaitingForPayment
+                | PaymentStatus::WaitingForBlock
+                | PaymentStatus::Processing
+                | PaymentStatus::Other(_)
+        )
+    }
+}
+
+impl From<&str> for PaymentStatus {
+    fn from(value: &str) -> Self {
+        match value.trim().parse::<i32>() {
+            Ok(-4) => PaymentStatus::MultiplePayments,
+            Ok(-3) => PaymentStatus::AmountMismatch,
+            Ok(-2) => PaymentStatus::ConversionFailed,
+            Ok(-1) => PaymentStatus::Expired,
+            Ok(0) => PaymentStatus::WaitingForPayment,
+            Ok(1) => PaymentStatus::WaitingForBlock,
+            Ok(2) => PaymentStatus::Processing,
+            Ok(3) => PaymentStatus::Successful,
+            _ => PaymentStatus::Other(value.to_string()),
+        }
+    }
+}
+
+impl Display for PaymentStatus {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match (self.code(), self) {
+            (_, PaymentStatus::Other(value)) => write!(f, "{}", value),
+            (Some(code), _) => write!(f, "{}", code),
+            (None, _) => Ok(()),
+        }
+    }
+}
+
+impl Serialize for PaymentStatus {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self.code() {
+            Some(code) => serializer.serialize_i32(code),
+            None => serializer.serialize_str(&self.to_string()),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for PaymentStatus {
+    /// Accepts the code as a number or as a string
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ok(match Value::deserialize(deserializer)? {
+            Value::String(value) => PaymentStatus::from(value.as_str()),
+            Value::Number(value) => PaymentStatus::from(value.to_string().as_str()),
+            other => PaymentStatus::Other(other.to_string()),
+        })
+    }
+}
 
 ///
 /// The ticker is a high-level overview of the state of the market. It will show you
@@ -9,20 +244,21 @@ use serde_json::Value;
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Ticker {
     /// Highest price
-    pub high: String,
+    pub high: Decimal,
     /// Lowest price
-    pub low: String,
+    pub low: Decimal,
     /// Purchase price
-    pub ask: String,
+    pub ask: Decimal,
     /// Sale price
-    pub bid: String,
+    pub bid: Decimal,
     /// Last transaction price
-    pub last_price: String,
+    pub last_price: Decimal,
     /// Market volume
-    pub volume: String,
-    /// Market pair
-    pub timestamp: String,
+    pub volume: Decimal,
     /// Date of consultation
+    #[serde(deserialize_with = "timestamp::deserialize")]
+    pub timestamp: Timestamp,
+    /// Market pair
     pub market: String,
 }
 
@@ -33,11 +269,12 @@ pub struct Ticker {
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Book {
     /// Order limit price
-    pub price: String,
-    /// Order quantity
-    pub timestamp: String,
+    pub price: Decimal,
     /// Creation date
-    pub amount: String,
+    #[serde(deserialize_with = "timestamp::deserialize")]
+    pub timestamp: Timestamp,
+    /// Order quantity
+    pub amount: Decimal,
 }
 
 ///
@@ -46,16 +283,17 @@ pub struct Book {
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Trade {
     /// Transaction Type. buy or sell
-    pub market_taker: String,
+    pub market_taker: OrderType,
     /// Price at which the transaction was made
-    pub price: String,
+    pub price: Decimal,
     /// Amount of the transaction
-    pub amount: String,
+    pub amount: Decimal,
     /// ID of the transaction
     #[serde(default)]
     pub tid: String,
     /// Date of the transaction
-    pub timestamp: String,
+    #[serde(deserialize_with = "timestamp::deserialize")]
+    pub timestamp: Timestamp,
     /// Market pair where the transaction was made
     pub market: String,
 }
@@ -66,14 +304,14 @@ pub struct Trade {
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Amount {
     /// Original quantity of the order
-    #[serde(default)]
-    pub original: String,
+    #[serde(default, deserialize_with = "decimal::or_zero")]
+    pub original: Decimal,
     /// Remaining amount of the order. Only in active orders
-    #[serde(default)]
-    pub remaining: String,
+    #[serde(default, deserialize_with = "decimal::option")]
+    pub remaining: Option<Decimal>,
     /// Quantity executed of the order. Only in executed orders
-    #[serde(default)]
-    pub executed: String,
+    #[serde(default, deserialize_with = "decimal::option")]
+    pub executed: Option<Decimal>,
 }
 
 ///
@@ -87,33 +325,33 @@ pub struct Order {
     pub id: String,
     /// Order Status, active o executed
     #[serde(default)]
-    pub status: String,
+    pub status: OrderState,
     /// Order Type. buy o sell
     #[serde(rename = "type")]
-    pub order_type: String,
+    pub order_type: OrderType,
     /// Order limit price
-    #[serde(default)]
-    pub price: String,
+    #[serde(default, deserialize_with = "decimal::or_zero")]
+    pub price: Decimal,
     /// Ammount
     pub amount: Amount,
     /// Execution price
-    #[serde(default)]
-    pub execution_price: Value, // null / String
+    #[serde(default, deserialize_with = "decimal::option")]
+    pub execution_price: Option<Decimal>,
     /// Average weighted execution price. 0 if it is not executed.
-    #[serde(default)]
-    pub avg_execution_price: String,
+    #[serde(default, deserialize_with = "decimal::or_zero")]
+    pub avg_execution_price: Decimal,
     /// Market pair
     #[serde(default)]
     pub market: String,
     /// Creation date
-    #[serde(default)]
-    pub created_at: String,
+    #[serde(default, deserialize_with = "timestamp::option")]
+    pub created_at: Option<Timestamp>,
     /// Update date. Only in active orders
-    #[serde(default)]
-    pub updated_at: String,
+    #[serde(default, deserialize_with = "timestamp::option")]
+    pub updated_at: Option<Timestamp>,
     /// Date of execution Only in executed orders
-    #[serde(default)]
-    pub executed_at: String,
+    #[serde(default, deserialize_with = "timestamp::option")]
+    pub executed_at: Option<Timestamp>,
 }
 
 ///
@@ -125,14 +363,14 @@ pub struct OrdersInstant {
     /// If it is a purchase purchase request, it corresponds to the amount of
     /// cryptocurrency to receive if the purchase was made. If it is a sell sell
     /// request, it corresponds to the amount of local currency to be received if the sale is made.
-    #[serde(default)]
-    pub obtained: String,
+    #[serde(default, deserialize_with = "decimal::or_zero")]
+    pub obtained: Decimal,
     /// If it is a buy type request, it corresponds to the amount of local currency that you want
     /// to use to make the purchase. If type is sell, it corresponds to the amount of cryptocurrency
     /// that you want to use for the sale. Amount less than or equal to the amount requested. Modified
     /// by market liquidity.
-    #[serde(default)]
-    pub required: String,
+    #[serde(default, deserialize_with = "decimal::or_zero")]
+    pub required: Decimal,
 }
 
 ///
@@ -143,9 +381,9 @@ pub struct Balance {
     /// Wallet at CryptoMarket
     pub wallet: String,
     /// Balance available
-    pub available: String,
+    pub available: Decimal,
     /// Countable balance
-    pub balance: String,
+    pub balance: Decimal,
 }
 
 ///
@@ -157,14 +395,15 @@ pub struct Payment {
     pub id: i32,
     /// External ID
     pub external_id: String,
-    /// State of the payment order. See below
-    pub status: String,
+    /// State of the payment order
+    pub status: PaymentStatus,
     /// Amount of the payment order
-    pub to_receive: String,
+    pub to_receive: Decimal,
     /// Type of currency to be received for the payment order
     pub to_receive_currency: String,
     /// Amount waiting for the order to be accepted
-    pub expected_amount: String,
+    #[serde(deserialize_with = "decimal::or_zero")]
+    pub expected_amount: Decimal,
     /// Type of currency waiting for the order to be accepted
     pub expected_currency: String,
     /// Payment order address
@@ -184,7 +423,39 @@ pub struct Payment {
     /// Payment order voucher url
     pub payment_url: String,
     /// Creation date of the payment order
-    pub created_at: String,
+    #[serde(deserialize_with = "timestamp::deserialize")]
+    pub created_at: Timestamp,
     /// Date of update of the payment order
-    pub updated_at: String,
+    #[serde(deserialize_with = "timestamp::deserialize")]
+    pub updated_at: Timestamp,
+}
+
+///
+/// Credentials of the socket connection, valid for one session
+///
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SocketAuth {
+    /// Socket session ID
+    #[serde(deserialize_with = "string_or_number")]
+    pub socid: String,
+    /// User ID
+    #[serde(deserialize_with = "string_or_number")]
+    pub uid: String,
+}
+
+///
+/// Read an identifier sent either as a string or as a number
+///
+fn string_or_number<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Value::deserialize(deserializer)? {
+        Value::String(value) => Ok(value),
+        Value::Number(value) => Ok(value.to_string()),
+        other => Err(serde::de::Error::custom(format!(
+            "invalid identifier: {}",
+            other
+        ))),
+    }
 }
@@ -0,0 +1,343 @@
+//!
+//! Órdenes límite validadas antes de firmar la petición `orders/create`, y el
+//! resultado de las cancelaciones masivas
+//!
+
+use crate::internal::errors::{CryptoMktError, CryptoMktErrorType, CryptoMktResult};
+use crate::internal::models::{Order, OrderState, OrderType};
+use crate::internal::tracker::is_cancelled;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+
+///
+/// Límites de las órdenes de un mercado, los límites desconocidos (`None`) no se validan
+///
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct OrderLimits {
+    /// Variación mínima de la cantidad, la cantidad debe ser un múltiplo del paso
+    pub amount_step: Option<Decimal>,
+    /// Cantidad mínima de una orden, en la moneda base
+    pub min_amount: Option<Decimal>,
+    /// Cantidad máxima de decimales del precio
+    pub price_decimals: Option<u32>,
+    /// Monto mínimo de una orden (precio por cantidad), en la moneda de cotización
+    pub min_notional: Option<Decimal>,
+}
+
+///
+/// Orden límite validada, creada con `OrderRequest::builder` o `Market::order_request`
+///
+/// ```
+/// extern crate cryptomkt;
+/// use cryptomkt::{Decimal, OrderRequest, OrderType};
+///
+/// let order = OrderRequest::builder(OrderType::Buy, Decimal::new(5, 1), Decimal::new(250000, 0))
+///     .min_amount(Decimal::new(1, 3))
+///     .price_decimals(0)
+///     .build()
+///     .unwrap();
+/// assert_eq!(order.amount(), Decimal::new(5, 1));
+///
+/// // El precio tiene más decimales que los permitidos
+/// assert!(OrderRequest::builder(OrderType::Sell, Decimal::new(5, 1), Decimal::new(2500005, 1))
+///     .price_decimals(0)
+///     .build()
+///     .is_err());
+/// ```
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderRequest {
+    order_type: OrderType,
+    amount: Decimal,
+    price: Decimal,
+}
+
+impl OrderRequest {
+    ///
+    /// Crea el builder de la orden
+    ///
+    /// Argumentos
+    ///     order_type: Compra o venta
+    ///     amount: Cantidad, en la moneda base
+    ///     price: Precio límite, en la moneda de cotización
+    ///
+    pub fn builder(order_type: OrderType, amount: Decimal, price: Decimal) -> OrderRequestBuilder {
+        OrderRequestBuilder {
+            order_type,
+            amount,
+            price,
+            market: None,
+            limits: OrderLimits::default(),
+        }
+    }
+
+    /// Compra o venta
+    pub fn order_type(&self) -> OrderType {
+        self.order_type.clone()
+    }
+
+    /// Cantidad, en la moneda base
+    pub fn amount(&self) -> Decimal {
+        self.amount
+    }
+
+    /// Precio límite, en la moneda de cotización
+    pub fn price(&self) -> Decimal {
+        self.price
+    }
+
+    ///
+    /// Parámetros de la petición `orders/create`
+    ///
+    pub(crate) fn to_params(&self, market: &str) -> HashMap<String, String> {
+        let mut params = HashMap::new();
+        params.insert("market".to_string(), market.to_string());
+        params.insert("amount".to_string(), self.amount.normalize().to_string());
+        params.insert("price".to_string(), self.price.normalize().to_string());
+        params.insert("type".to_string(), self.order_type.to_string());
+        params
+    }
+}
+
+///
+/// Builder de `OrderRequest`, la orden se valida en `build`
+///
+#[derive(Debug, Clone)]
+pub struct OrderRequestBuilder {
+    order_type: OrderType,
+    amount: Decimal,
+    price: Decimal,
+    market: Option<String>,
+    limits: OrderLimits,
+}
+
+impl OrderRequestBuilder {
+    ///
+    /// Mercado de la orden, se usa en los mensajes de error
+    ///
+    pub fn market(mut self, market: &str) -> Self {
+        self.market = Some(market.to_string());
+        self
+    }
+
+    ///
+    /// Límites del mercado, reemplaza los límites indicados anteriormente
+    ///
+    pub fn limits(mut self, limits: OrderLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    ///
+    /// Variación mínima de la cantidad de la orden
+    ///
+    pub fn amount_step(mut self, amount_step: Decimal) -> Self {
+        self.limits.amount_step = Some(amount_step);
+        self
+    }
+
+    ///
+    /// Cantidad mínima de la orden
+    ///
+    pub fn min_amount(mut self, min_amount: Decimal) -> Self {
+        self.limits.min_amount = Some(min_amount);
+        self
+    }
+
+    ///
+    /// Monto mínimo de la orden (precio por cantidad)
+    ///
+    pub fn min_notional(mut self, min_notional: Decimal) -> Self {
+        self.limits.min_notional = Some(min_notional);
+        self
+    }
+
+    ///
+    /// Cantidad máxima de decimales del precio
+    ///
+    pub fn price_decimals(mut self, price_decimals: u32) -> Self {
+        self.limits.price_decimals = Some(price_decimals);
+        self
+    }
+
+    ///
+    /// Crea la orden, falla con `InvalidArgument` si la cantidad o el precio no son
+    /// positivos, la cantidad no es múltiplo del paso, la cantidad o el monto son
+    /// menores que el mínimo o el precio tiene más decimales que los permitidos
+    ///
+    pub fn build(self) -> CryptoMktResult<OrderRequest> {
+        if self.amount <= Decimal::ZERO {
+            return Err(self.invalid(format!("amount must be positive, got {}", self.amount)));
+        }
+        if self.price <= Decimal::ZERO {
+            return Err(self.invalid(format!("price must be positive, got {}", self.price)));
+        }
+        if let Some(step) = self.limits.amount_step.filter(|step| *step > Decimal::ZERO) {
+            if !(self.amount % step).is_zero() {
+                return Err(self.invalid(format!(
+                    "amount {} is not a multiple of the step {}",
+                    self.amount, step
+                )));
+            }
+        }
+        if let Some(min_amount) = self.limits.min_amount {
+            if self.amount < min_amount {
+                return Err(self.invalid(format!(
+                    "amount {} is below the minimum {}",
+                    self.amount, min_amount
+                )));
+            }
+        }
+        if let Some(price_decimals) = self.limits.price_decimals {
+            if self.price.normalize().scale() > price_decimals {
+                return Err(self.invalid(format!(
+                    "price {} has more than {} decimals",
+                    self.price, price_decimals
+                )));
+            }
+        }
+        if let Some(min_notional) = self.limits.min_notional {
+            let notional = self.amount * self.price;
+            if notional < min_notional {
+                return Err(self.invalid(format!(
+                    "notional {} is below the minimum {}",
+                    notional.normalize(),
+                    min_notional
+                )));
+            }
+        }
+        Ok(OrderRequest {
+            order_type: self.order_type,
+            amount: self.amount,
+            price: self.price,
+        })
+    }
+
+    fn invalid(&self, message: String) -> CryptoMktError {
+        let message = match &self.market {
+            Some(market) => format!(
+                "invalid {} order on {}: {}",
+                self.order_type, market, message
+            ),
+            None => format!("invalid {} order: {}", self.order_type, message),
+        };
+        CryptoMktError::from(CryptoMktErrorType::InvalidArgument).with_message(&message)
+    }
+}
+
+///
+/// Resultado de la cancelación de una orden en `cancel_all_orders` o `cancel_all`
+///
+#[derive(Debug)]
+pub struct CancelOutcome {
+    /// ID de la orden
+    pub order_id: String,
+    /// Mercado de la orden
+    pub market: String,
+    /// Orden cancelada, o el error de la cancelación
+    pub result: CryptoMktResult<Order>,
+}
+
+impl CancelOutcome {
+    ///
+    /// Indica si la orden se canceló
+    ///
+    pub fn is_cancelled(&self) -> bool {
+        self.result.is_ok()
+    }
+}
+
+///
+/// Resultado de cada paso de `replace_order`: cancelación, confirmación con
+/// `orders/status` y creación de la nueva orden
+///
+#[derive(Debug)]
+pub struct ReplaceOutcome {
+    /// ID de la orden reemplazada
+    pub order_id: String,
+    /// Respuesta de `orders/cancel`
+    pub cancel: CryptoMktResult<Order>,
+    /// Estado de la orden después de la cancelación
+    pub confirmation: CryptoMktResult<Order>,
+    /// Cantidad de la nueva orden, `None` si no se confirmó la cancelación o no se
+    /// conoce la cantidad ejecutada
+    pub amount: Option<Decimal>,
+    /// Respuesta de `orders/create`, o el error si no se conoce la cantidad ejecutada;
+    /// `None` si no se intentó crear la nueva orden
+    pub placement: Option<CryptoMktResult<Vec<Order>>>,
+}
+
+impl ReplaceOutcome {
+    ///
+    /// Indica si se confirmó que la orden ya no está activa: solo cuenta un estado
+    /// ejecutado o cancelado, un estado vacío o desconocido no confirma nada
+    ///
+    pub fn is_cancelled(&self) -> bool {
+        self.confirmation.as_ref().is_ok_and(|order| {
+            order.status == OrderState::Executed || is_cancelled(&order.status)
+        })
+    }
+
+    ///
+    /// Indica si se creó la nueva orden
+    ///
+    pub fn is_replaced(&self) -> bool {
+        self.placement.as_ref().is_some_and(|result| result.is_ok())
+    }
+
+    ///
+    /// Nueva orden, si se creó
+    ///
+    pub fn new_order(&self) -> Option<&Order> {
+        match &self.placement {
+            Some(Ok(orders)) => orders.first(),
+            _ => None,
+        }
+    }
+}
+
+///
+/// Valida los parámetros de `replace_order` antes de cancelar la orden
+///
+pub(crate) fn check_replace(
+    new_price: Decimal,
+    new_amount: Option<Decimal>,
+) -> CryptoMktResult<()> {
+    if new_price <= Decimal::ZERO {
+        return Err(CryptoMktError::from(CryptoMktErrorType::InvalidArgument)
+            .with_message(&format!("price must be positive, got {}", new_price)));
+    }
+    match new_amount {
+        Some(amount) if amount <= Decimal::ZERO => {
+            Err(CryptoMktError::from(CryptoMktErrorType::InvalidArgument)
+                .with_message(&format!("amount must be positive, got {}", amount)))
+        }
+        _ => Ok(()),
+    }
+}
+
+///
+/// Cantidad de la nueva orden: la cantidad total indicada (o la original) menos
+/// la cantidad ya ejecutada por la orden cancelada
+///
+/// La cantidad ejecutada se toma de `executed` y, si no viene, de `original - remaining`.
+/// Falla con `InvalidArgument` si la orden no informa ninguna de las dos o la cantidad
+/// ejecutada queda fuera de `0..=original`
+///
+pub(crate) fn replace_amount(
+    cancelled: &Order,
+    new_amount: Option<Decimal>,
+) -> CryptoMktResult<Decimal> {
+    let amount = &cancelled.amount;
+    let executed = amount
+        .executed
+        .or_else(|| amount.remaining.map(|remaining| amount.original - remaining))
+        .filter(|executed| *executed >= Decimal::ZERO && *executed <= amount.original)
+        .ok_or_else(|| {
+            CryptoMktError::from(CryptoMktErrorType::InvalidArgument).with_message(&format!(
+                "cannot tell the executed amount of order {}",
+                cancelled.id
+            ))
+        })?;
+    Ok((new_amount.unwrap_or(amount.original) - executed).max(Decimal::ZERO))
+}
@@ -0,0 +1,408 @@
+//!
+//! Libro de órdenes completo mantenido localmente
+//!
+//! Las órdenes del libro se agrupan por precio en niveles ordenados: las compras
+//! (`OrderType::Buy`) del mayor al menor precio y las ventas (`OrderType::Sell`)
+//! del menor al mayor, de modo que el primer nivel de cada lado es el mejor
+//!
+
+use crate::internal::models::{Book, OrderType};
+use rust_decimal::Decimal;
+use std::collections::BTreeMap;
+
+///
+/// Cantidad disponible a un precio
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PriceLevel {
+    /// Precio del nivel
+    pub price: Decimal,
+    /// Cantidad total de las órdenes a ese precio
+    pub amount: Decimal,
+}
+
+///
+/// Cambio de un nivel del libro, una cantidad cero elimina el nivel
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct LevelChange {
+    /// Lado del libro
+    pub side: OrderType,
+    /// Precio del nivel
+    pub price: Decimal,
+    /// Nueva cantidad del nivel
+    pub amount: Decimal,
+}
+
+///
+/// Resultado de recorrer un lado del libro hasta completar una cantidad
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct BookFill {
+    /// Cantidad cubierta por el libro, menor a la solicitada si no hay profundidad suficiente
+    pub amount: Decimal,
+    /// Volumen en la moneda de cotización (suma de precio * cantidad)
+    pub volume: Decimal,
+    /// Precio promedio ponderado, `None` si no se cubrió ninguna cantidad
+    pub average_price: Option<Decimal>,
+    /// Precio del último nivel alcanzado
+    pub worst_price: Option<Decimal>,
+    /// Cantidad de niveles alcanzados
+    pub levels: usize,
+    /// Indica si el libro cubre toda la cantidad solicitada
+    pub complete: bool,
+}
+
+///
+/// Libro de órdenes de un mercado con ambos lados ordenados por precio
+///
+/// ```
+/// extern crate cryptomkt;
+/// use cryptomkt::{Decimal, OrderBook, OrderType};
+///
+/// let mut book = OrderBook::new("ETHCLP");
+/// book.update(OrderType::Buy, Decimal::new(250000, 0), Decimal::new(15, 1));
+/// book.update(OrderType::Sell, Decimal::new(251000, 0), Decimal::new(2, 0));
+/// assert_eq!(book.spread(), Some(Decimal::new(1000, 0)));
+/// assert_eq!(book.mid_price(), Some(Decimal::new(250500, 0)));
+/// ```
+///
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OrderBook {
+    market: String,
+    bids: BTreeMap<Decimal, Decimal>,
+    asks: BTreeMap<Decimal, Decimal>,
+}
+
+impl OrderBook {
+    ///
+    /// Crea un libro vacío
+    ///
+    /// Argumentos
+    ///     market: Nombre del mercado (Ej. ETHCLP)
+    ///
+    pub fn new(market: &str) -> Self {
+        OrderBook {
+            market: market.to_string(),
+            bids: BTreeMap::new(),
+            asks: BTreeMap::new(),
+        }
+    }
+
+    ///
+    /// Crea el libro con las órdenes de ambos lados, las órdenes de un mismo precio
+    /// se suman en un nivel
+    ///
+    pub fn from_entries(market: &str, bids: &[Book], asks: &[Book]) -> Self {
+        let mut book = OrderBook::new(market);
+        book.replace_side(OrderType::Buy, bids);
+        book.replace_side(OrderType::Sell, asks);
+        book
+    }
+
+    ///
+    /// Nombre del mercado
+    ///
+    pub fn market(&self) -> &str {
+        &self.market
+    }
+
+    fn levels(&self, side: &OrderType) -> Option<&BTreeMap<Decimal, Decimal>> {
+        match side {
+            OrderType::Buy => Some(&self.bids),
+            OrderType::Sell => Some(&self.asks),
+            OrderType::Other(_) => None,
+        }
+    }
+
+    fn levels_mut(&mut self, side: &OrderType) -> Option<&mut BTreeMap<Decimal, Decimal>> {
+        match side {
+            OrderType::Buy => Some(&mut self.bids),
+            OrderType::Sell => Some(&mut self.asks),
+            OrderType::Other(_) => None,
+        }
+    }
+
+    ///
+    /// Niveles de un lado desde el mejor precio
+    ///
+    fn iter_side<'a>(&'a self, side: &OrderType) -> Box<dyn Iterator<Item = PriceLevel> + 'a> {
+        let level = |(price, amount): (&Decimal, &Decimal)| PriceLevel {
+            price: *price,
+            amount: *amount,
+        };
+        match side {
+            OrderType::Buy => Box::new(self.bids.iter().rev().map(level)),
+            OrderType::Sell => Box::new(self.asks.iter().map(level)),
+            OrderType::Other(_) => Box::new(std::iter::empty()),
+        }
+    }
+
+    ///
+    /// Compras del mayor al menor precio
+    ///
+    pub fn bids(&self) -> Vec<PriceLevel> {
+        self.iter_side(&OrderType::Buy).collect()
+    }
+
+    ///
+    /// Ventas del menor al mayor precio
+    ///
+    pub fn asks(&self) -> Vec<PriceLevel> {
+        self.iter_side(&OrderType::Sell).collect()
+    }
+
+    ///
+    /// Indica si ambos lados están vacíos
+    ///
+    pub fn is_empty(&self) -> bool {
+        self.bids.is_empty() && self.asks.is_empty()
+    }
+
+    ///
+    /// Mejor compra (precio más alto)
+    ///
+    pub fn best_bid(&self) -> Option<PriceLevel> {
+        self.iter_side(&OrderType::Buy).next()
+    }
+
+    ///
+    /// Mejor venta (precio más bajo)
+    ///
+    pub fn best_ask(&self) -> Option<PriceLevel> {
+        self.iter_side(&OrderType::Sell).next()
+    }
+
+    ///
+    /// Diferencia entre la mejor venta y la mejor compra
+    ///
+    pub fn spread(&self) -> Option<Decimal> {
+        Some(self.best_ask()?.price - self.best_bid()?.price)
+    }
+
+    ///
+    /// Precio medio entre la mejor compra y la mejor venta
+    ///
+    pub fn mid_price(&self) -> Option<Decimal> {
+        Some((self.best_ask()?.price + self.best_bid()?.price) / Decimal::new(2, 0))
+    }
+
+    ///
+    /// Cantidad del nivel de un precio, cero si no hay órdenes a ese precio
+    ///
+    pub fn amount_at(&self, side: &OrderType, price: Decimal) -> Decimal {
+        self.levels(side)
+            .and_then(|levels| levels.get(&price).copied())
+            .unwrap_or(Decimal::ZERO)
+    }
+
+    ///
+    /// Cantidad acumulada desde el mejor precio hasta `price` inclusive: las compras
+    /// a ese precio o más y las ventas a ese precio o menos
+    ///
+    pub fn depth_at(&self, side: &OrderType, price: Decimal) -> Decimal {
+        match side {
+            OrderType::Buy => self.bids.range(price..).map(|(_, amount)| *amount).sum(),
+            OrderType::Sell => self.asks.range(..=price).map(|(_, amount)| *amount).sum(),
+            OrderType::Other(_) => Decimal::ZERO,
+        }
+    }
+
+    ///
+    /// Recorre un lado del libro desde el mejor precio hasta completar `amount`
+    ///
+    /// Una orden de compra consume las ventas (`OrderType::Sell`) y una de venta
+    /// las compras (`OrderType::Buy`)
+    ///
+    /// Argumentos
+    ///     side: Lado del libro recorrido
+    ///     amount: Cantidad objetivo
+    ///
+    pub fn cumulative_volume(&self, side: &OrderType, amount: Decimal) -> BookFill {
+        self.walk(side, |filled, _| amount - filled)
+    }
+
+    ///
+    /// Recorre un lado del libro desde el mejor precio hasta gastar `volume` en la
+    /// moneda de cotización, por ejemplo para una compra por un monto en CLP
+    ///
+    /// Argumentos
+    ///     side: Lado del libro recorrido
+    ///     volume: Volumen objetivo en la moneda de cotización
+    ///
+    pub fn fill_volume(&self, side: &OrderType, volume: Decimal) -> BookFill {
+        self.walk(side, |_, level| (volume - level.spent) / level.price)
+    }
+
+    ///
+    /// Recorre los niveles tomando en cada uno la cantidad que indica `remaining`,
+    /// hasta que no quede cantidad por tomar
+    ///
+    fn walk<F>(&self, side: &OrderType, remaining: F) -> BookFill
+    where
+        F: Fn(Decimal, &WalkLevel) -> Decimal,
+    {
+        let mut filled = Decimal::ZERO;
+        let mut volume = Decimal::ZERO;
+        let mut worst_price = None;
+        let mut levels = 0;
+        let mut complete = false;
+        for level in self.iter_side(side) {
+            let wanted = remaining(
+                filled,
+                &WalkLevel {
+                    price: level.price,
+                    spent: volume,
+                },
+            );
+            if wanted <= Decimal::ZERO {
+                complete = true;
+                break;
+            }
+            let take = level.amount.min(wanted);
+            filled += take;
+            volume += take * level.price;
+            worst_price = Some(level.price);
+            levels += 1;
+            complete = take == wanted;
+        }
+        BookFill {
+            amount: filled,
+            volume,
+            average_price: if filled > Decimal::ZERO {
+                Some(volume / filled)
+            } else {
+                None
+            },
+            worst_price,
+            levels,
+            complete,
+        }
+    }
+
+    ///
+    /// Fija la cantidad de un nivel, una cantidad cero o negativa lo elimina. Un precio
+    /// cero o negativo no forma un nivel y se ignora
+    ///
+    pub fn update(&mut self, side: OrderType, price: Decimal, amount: Decimal) {
+        if price <= Decimal::ZERO {
+            return;
+        }
+        if let Some(levels) = self.levels_mut(&side) {
+            if amount > Decimal::ZERO {
+                levels.insert(price, amount);
+            } else {
+                levels.remove(&price);
+            }
+        }
+    }
+
+    ///
+    /// Aplica los cambios de niveles, por ejemplo los obtenidos con `diff`
+    ///
+    pub fn apply(&mut self, changes: &[LevelChange]) {
+        for change in changes {
+            self.update(change.side.clone(), change.price, change.amount);
+        }
+    }
+
+    ///
+    /// Aplica niveles actualizados de un lado: cada entrada fija la cantidad de su
+    /// precio y una cantidad cero elimina el nivel
+    ///
+    pub fn apply_entries(&mut self, side: OrderType, entries: &[Book]) {
+        for (price, amount) in aggregate(entries) {
+            self.update(side.clone(), price, amount);
+        }
+    }
+
+    ///
+    /// Reemplaza todos los niveles de un lado
+    ///
+    pub fn replace_side(&mut self, side: OrderType, entries: &[Book]) {
+        if let Some(levels) = self.levels_mut(&side) {
+            *levels = aggregate(entries)
+                .into_iter()
+                .filter(|(_, amount)| *amount > Decimal::ZERO)
+                .collect();
+        }
+    }
+
+    ///
+    /// Cambios necesarios para pasar de este libro a `newer`, por ejemplo entre dos
+    /// consultas periódicas del libro completo
+    ///
+    pub fn diff(&self, newer: &OrderBook) -> Vec<LevelChange> {
+        let mut changes = Vec::new();
+        for side in [OrderType::Buy, OrderType::Sell].iter() {
+            let (current, next) = match (self.levels(side), newer.levels(side)) {
+                (Some(current), Some(next)) => (current, next),
+                _ => continue,
+            };
+            for (price, amount) in next {
+                if current.get(price) != Some(amount) {
+                    changes.push(LevelChange {
+                        side: side.clone(),
+                        price: *price,
+                        amount: *amount,
+                    });
+                }
+            }
+            for price in current.keys().filter(|price| !next.contains_key(price)) {
+                changes.push(LevelChange {
+                    side: side.clone(),
+                    price: *price,
+                    amount: Decimal::ZERO,
+                });
+            }
+        }
+        changes
+    }
+
+    ///
+    /// Reemplaza el libro por una consulta más reciente y devuelve los niveles que cambiaron
+    ///
+    pub fn refresh_from(&mut self, newer: OrderBook) -> Vec<LevelChange> {
+        let changes = self.diff(&newer);
+        self.bids = newer.bids;
+        self.asks = newer.asks;
+        changes
+    }
+
+    ///
+    /// Aplica un evento `book` del stream de mercados, los demás eventos y los de
+    /// otros mercados se ignoran. Devuelve `true` si el evento se aplicó
+    ///
+    #[cfg(feature = "websocket")]
+    pub fn apply_event(&mut self, event: &crate::internal::streams::MarketEvent) -> bool {
+        match event {
+            crate::internal::streams::MarketEvent::Book {
+                market,
+                side,
+                entries,
+            } if market.eq_ignore_ascii_case(&self.market) => {
+                self.apply_entries(side.clone(), entries);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Nivel visitado al recorrer el libro, con el volumen gastado en los niveles anteriores
+struct WalkLevel {
+    price: Decimal,
+    spent: Decimal,
+}
+
+///
+/// Suma las cantidades de las órdenes de un mismo precio, descartando las de precio
+/// cero o negativo
+///
+fn aggregate(entries: &[Book]) -> BTreeMap<Decimal, Decimal> {
+    let mut levels = BTreeMap::new();
+    for entry in entries.iter().filter(|entry| entry.price > Decimal::ZERO) {
+        *levels.entry(entry.price).or_insert(Decimal::ZERO) += entry.amount;
+    }
+    levels
+}
@@ -0,0 +1,273 @@
+//!
+//! Recorrido automático de los endpoints paginados, sigue `pagination.next`
+//! hasta que no quedan páginas o se alcanza el límite configurado
+//!
+//! Los elementos se entregan uno a uno y solo se mantiene en memoria la página actual
+//!
+
+use crate::internal::errors::CryptoMktResult;
+use crate::internal::response::CryptoMktResponse;
+use futures::stream::{self, Stream};
+use std::collections::VecDeque;
+use std::future::Future;
+
+///
+/// Opciones del recorrido de un endpoint paginado
+///
+/// ```
+/// extern crate cryptomkt;
+/// use cryptomkt::PageOptions;
+///
+/// // Páginas de 50 elementos, como máximo 10 páginas o 200 elementos
+/// let options = PageOptions::default().page_size(50).max_pages(10).max_items(200);
+/// ```
+///
+#[derive(Debug, Clone, Copy)]
+pub struct PageOptions {
+    page_size: u32,
+    start_page: u32,
+    max_pages: Option<u32>,
+    max_items: Option<usize>,
+}
+
+impl Default for PageOptions {
+    ///
+    /// Páginas de 100 elementos desde la página 0, sin límites
+    ///
+    fn default() -> Self {
+        PageOptions {
+            page_size: 100,
+            start_page: 0,
+            max_pages: None,
+            max_items: None,
+        }
+    }
+}
+
+impl PageOptions {
+    ///
+    /// Cantidad de elementos solicitados por página (`limit`)
+    ///
+    pub fn page_size(mut self, page_size: u32) -> Self {
+        self.page_size = page_size.max(1);
+        self
+    }
+
+    ///
+    /// Primera página solicitada
+    ///
+    pub fn start_page(mut self, start_page: u32) -> Self {
+        self.start_page = start_page;
+        self
+    }
+
+    ///
+    /// Cantidad máxima de páginas solicitadas
+    ///
+    pub fn max_pages(mut self, max_pages: u32) -> Self {
+        self.max_pages = Some(max_pages);
+        self
+    }
+
+    ///
+    /// Cantidad máxima de elementos entregados
+    ///
+    pub fn max_items(mut self, max_items: usize) -> Self {
+        self.max_items = Some(max_items);
+        self
+    }
+}
+
+///
+/// Una página de un endpoint paginado, con los elementos y la información de
+/// paginación enviada por el servidor
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct Page<T> {
+    /// Elementos de la página
+    pub items: Vec<T>,
+    /// Número de la página
+    pub page: u32,
+    /// Cantidad de elementos solicitados por página
+    pub limit: u32,
+    /// Página anterior, `None` en la primera página
+    pub previous: Option<u32>,
+    /// Página siguiente, `None` en la última página
+    pub next: Option<u32>,
+}
+
+impl<T> Page<T> {
+    ///
+    /// Indica si el servidor informa una página siguiente
+    ///
+    pub fn has_next(&self) -> bool {
+        self.next.is_some()
+    }
+}
+
+impl<T> From<CryptoMktResponse<Vec<T>>> for Page<T> {
+    fn from(response: CryptoMktResponse<Vec<T>>) -> Self {
+        let pagination = response.pagination;
+        Page {
+            items: response.data,
+            page: pagination.page,
+            limit: pagination.limit,
+            previous: pagination.previous,
+            next: pagination.next,
+        }
+    }
+}
+
+impl<T> IntoIterator for Page<T> {
+    type Item = T;
+    type IntoIter = std::vec::IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.into_iter()
+    }
+}
+
+///
+/// Estado del recorrido, compartido por el iterador y el stream
+///
+struct PageState<T> {
+    options: PageOptions,
+    buffer: VecDeque<T>,
+    next_page: Option<u32>,
+    pages: u32,
+    items: usize,
+}
+
+impl<T> PageState<T> {
+    fn new(options: PageOptions) -> Self {
+        PageState {
+            options,
+            buffer: VecDeque::new(),
+            next_page: Some(options.start_page),
+            pages: 0,
+            items: 0,
+        }
+    }
+
+    fn items_exhausted(&self) -> bool {
+        self.options.max_items.is_some_and(|max| self.items >= max)
+    }
+
+    /// Siguiente elemento de la página actual
+    fn pop(&mut self) -> Option<T> {
+        if self.items_exhausted() {
+            return None;
+        }
+        let item = self.buffer.pop_front()?;
+        self.items += 1;
+        Some(item)
+    }
+
+    /// Página que se debe solicitar, `None` al terminar el recorrido
+    fn next_request(&self) -> Option<u32> {
+        if self.items_exhausted() || self.options.max_pages.is_some_and(|max| self.pages >= max) {
+            return None;
+        }
+        self.next_page
+    }
+
+    fn push_page(&mut self, page: u32, items: Vec<T>, next: Option<u32>) {
+        self.pages += 1;
+        // Una página vacía o que no avanza termina el recorrido
+        self.next_page = match next {
+            Some(next) if next > page && !items.is_empty() => Some(next),
+            _ => None,
+        };
+        self.buffer.extend(items);
+    }
+
+    fn finish(&mut self) {
+        self.next_page = None;
+        self.buffer.clear();
+    }
+}
+
+///
+/// Función que obtiene una página: recibe la página y el límite, devuelve los
+/// elementos y la página siguiente
+///
+#[cfg(feature = "blocking")]
+type FetchPage<T> = Box<dyn FnMut(u32, u32) -> CryptoMktResult<(Vec<T>, Option<u32>)> + Send>;
+
+///
+/// Iterador bloqueante sobre todos los elementos de un endpoint paginado
+///
+/// Las páginas se solicitan a medida que se consumen los elementos. Si una
+/// petición falla se entrega el error y el recorrido termina
+///
+#[cfg(feature = "blocking")]
+pub struct Paginator<T> {
+    state: PageState<T>,
+    fetch: FetchPage<T>,
+}
+
+#[cfg(feature = "blocking")]
+impl<T> Paginator<T> {
+    pub(crate) fn new<F>(options: PageOptions, fetch: F) -> Self
+    where
+        F: FnMut(u32, u32) -> CryptoMktResult<(Vec<T>, Option<u32>)> + Send + 'static,
+    {
+        Paginator {
+            state: PageState::new(options),
+            fetch: Box::new(fetch),
+        }
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl<T> Iterator for Paginator<T> {
+    type Item = CryptoMktResult<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.state.pop() {
+                return Some(Ok(item));
+            }
+            let page = self.state.next_request()?;
+            match (self.fetch)(page, self.state.options.page_size) {
+                Ok((items, next)) => self.state.push_page(page, items, next),
+                Err(e) => {
+                    self.state.finish();
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
+
+///
+/// Stream asíncrono sobre todos los elementos de un endpoint paginado, con el
+/// mismo comportamiento que `Paginator`
+///
+pub(crate) fn page_stream<T, F, Fut>(
+    options: PageOptions,
+    fetch: F,
+) -> impl Stream<Item = CryptoMktResult<T>>
+where
+    F: FnMut(u32, u32) -> Fut,
+    Fut: Future<Output = CryptoMktResult<(Vec<T>, Option<u32>)>>,
+{
+    stream::unfold(
+        (PageState::new(options), fetch),
+        |(mut state, mut fetch)| async move {
+            loop {
+                if let Some(item) = state.pop() {
+                    return Some((Ok(item), (state, fetch)));
+                }
+                let page = state.next_request()?;
+                match fetch(page, state.options.page_size).await {
+                    Ok((items, next)) => state.push_page(page, items, next),
+                    Err(e) => {
+                        state.finish();
+                        return Some((Err(e), (state, fetch)));
+                    }
+                }
+            }
+        },
+    )
+}
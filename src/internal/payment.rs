@@ -0,0 +1,260 @@
+//!
+//! Parámetros de las órdenes de pago, validados antes de enviar la petición
+//!
+
+use crate::internal::errors::{CryptoMktError, CryptoMktErrorType, CryptoMktResult};
+use reqwest::Url;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+
+///
+/// Orden de pago a crear con `CryptoMktClient::create_payment_order`
+///
+/// ```
+/// extern crate cryptomkt;
+/// use cryptomkt::{Decimal, PaymentOrderRequest};
+///
+/// let request = PaymentOrderRequest::builder(Decimal::new(15000, 0), "CLP", "shop@example.com")
+///     .external_id("ORDER-123")
+///     .callback_url("https://example.com/callback")
+///     .refund_email("buyer@example.com")
+///     .build()
+///     .unwrap();
+/// assert_eq!(request.to_receive(), Decimal::new(15000, 0));
+/// ```
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaymentOrderRequest {
+    to_receive: Decimal,
+    to_receive_currency: String,
+    payment_receiver: String,
+    external_id: Option<String>,
+    callback_url: Option<String>,
+    error_url: Option<String>,
+    success_url: Option<String>,
+    refund_email: Option<String>,
+}
+
+impl PaymentOrderRequest {
+    ///
+    /// Crea el builder de la orden de pago
+    ///
+    /// Argumentos
+    ///     to_receive: Monto a recibir
+    ///     to_receive_currency: Moneda del monto (Ej. CLP)
+    ///     payment_receiver: Email de la cuenta que recibe el pago
+    ///
+    pub fn builder(
+        to_receive: Decimal,
+        to_receive_currency: &str,
+        payment_receiver: &str,
+    ) -> PaymentOrderRequestBuilder {
+        PaymentOrderRequestBuilder {
+            request: PaymentOrderRequest {
+                to_receive,
+                to_receive_currency: to_receive_currency.trim().to_string(),
+                payment_receiver: payment_receiver.trim().to_string(),
+                external_id: None,
+                callback_url: None,
+                error_url: None,
+                success_url: None,
+                refund_email: None,
+            },
+        }
+    }
+
+    /// Monto a recibir
+    pub fn to_receive(&self) -> Decimal {
+        self.to_receive
+    }
+
+    /// Moneda del monto a recibir
+    pub fn to_receive_currency(&self) -> &str {
+        &self.to_receive_currency
+    }
+
+    /// Email de la cuenta que recibe el pago
+    pub fn payment_receiver(&self) -> &str {
+        &self.payment_receiver
+    }
+
+    /// Identificador externo de la orden
+    pub fn external_id(&self) -> Option<&str> {
+        self.external_id.as_deref()
+    }
+
+    /// URL notificada con los cambios de estado
+    pub fn callback_url(&self) -> Option<&str> {
+        self.callback_url.as_deref()
+    }
+
+    /// URL de redirección cuando el pago falla
+    pub fn error_url(&self) -> Option<&str> {
+        self.error_url.as_deref()
+    }
+
+    /// URL de redirección cuando el pago es exitoso
+    pub fn success_url(&self) -> Option<&str> {
+        self.success_url.as_deref()
+    }
+
+    /// Email para las devoluciones
+    pub fn refund_email(&self) -> Option<&str> {
+        self.refund_email.as_deref()
+    }
+
+    ///
+    /// Parámetros de la petición `payment/new_order`
+    ///
+    pub(crate) fn to_params(&self) -> HashMap<String, String> {
+        let mut params = HashMap::new();
+        params.insert("to_receive".to_string(), self.to_receive.to_string());
+        params.insert(
+            "to_receive_currency".to_string(),
+            self.to_receive_currency.clone(),
+        );
+        params.insert(
+            "payment_receiver".to_string(),
+            self.payment_receiver.clone(),
+        );
+
+        let optional = [
+            ("external_id", &self.external_id),
+            ("callback_url", &self.callback_url),
+            ("error_url", &self.error_url),
+            ("success_url", &self.success_url),
+            ("refund_email", &self.refund_email),
+        ];
+        for (name, value) in optional.iter() {
+            if let Some(value) = value {
+                params.insert(name.to_string(), value.clone());
+            }
+        }
+        params
+    }
+}
+
+///
+/// Builder de `PaymentOrderRequest`, los parámetros se validan en `build`
+///
+#[derive(Debug, Clone)]
+pub struct PaymentOrderRequestBuilder {
+    request: PaymentOrderRequest,
+}
+
+impl PaymentOrderRequestBuilder {
+    ///
+    /// Identificador externo de la orden (Ej. el id de la orden de la tienda)
+    ///
+    pub fn external_id(mut self, external_id: &str) -> Self {
+        self.request.external_id = Some(external_id.to_string());
+        self
+    }
+
+    ///
+    /// URL notificada con los cambios de estado del pago
+    ///
+    pub fn callback_url(mut self, callback_url: &str) -> Self {
+        self.request.callback_url = Some(callback_url.trim().to_string());
+        self
+    }
+
+    ///
+    /// URL de redirección cuando el pago falla
+    ///
+    pub fn error_url(mut self, error_url: &str) -> Self {
+        self.request.error_url = Some(error_url.trim().to_string());
+        self
+    }
+
+    ///
+    /// URL de redirección cuando el pago es exitoso
+    ///
+    pub fn success_url(mut self, success_url: &str) -> Self {
+        self.request.success_url = Some(success_url.trim().to_string());
+        self
+    }
+
+    ///
+    /// Email al que se notifica la devolución de un pago fallido
+    ///
+    pub fn refund_email(mut self, refund_email: &str) -> Self {
+        self.request.refund_email = Some(refund_email.trim().to_string());
+        self
+    }
+
+    ///
+    /// Crea la orden de pago, falla con `InvalidArgument` si el monto no es
+    /// positivo, la moneda está vacía o algún email o URL no es válido
+    ///
+    pub fn build(self) -> CryptoMktResult<PaymentOrderRequest> {
+        let request = self.request;
+        if request.to_receive <= Decimal::ZERO {
+            return Err(invalid_argument(
+                "to_receive",
+                &request.to_receive.to_string(),
+            ));
+        }
+        if request.to_receive_currency.is_empty()
+            || !request
+                .to_receive_currency
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric())
+        {
+            return Err(invalid_argument(
+                "to_receive_currency",
+                &request.to_receive_currency,
+            ));
+        }
+        check_email("payment_receiver", Some(&request.payment_receiver))?;
+        check_email("refund_email", request.refund_email.as_ref())?;
+        check_url("callback_url", request.callback_url.as_ref())?;
+        check_url("error_url", request.error_url.as_ref())?;
+        check_url("success_url", request.success_url.as_ref())?;
+        Ok(request)
+    }
+}
+
+fn invalid_argument(name: &str, value: &str) -> CryptoMktError {
+    CryptoMktError::from(CryptoMktErrorType::InvalidArgument)
+        .with_message(&format!("invalid {}: {:?}", name, value))
+}
+
+///
+/// Indica si la cadena tiene la forma `usuario@dominio.tld`
+///
+fn is_email(value: &str) -> bool {
+    let mut parts = value.splitn(2, '@');
+    let (local, domain) = match (parts.next(), parts.next()) {
+        (Some(local), Some(domain)) => (local, domain),
+        _ => return false,
+    };
+    !local.is_empty()
+        && !value.chars().any(|c| c.is_whitespace() || c.is_control())
+        && !domain.contains('@')
+        && domain.contains('.')
+        && domain.split('.').all(|label| {
+            !label.is_empty()
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_alphanumeric() || c == '-')
+        })
+}
+
+fn check_email(name: &str, value: Option<&String>) -> CryptoMktResult<()> {
+    match value {
+        Some(value) if !is_email(value) => Err(invalid_argument(name, value)),
+        _ => Ok(()),
+    }
+}
+
+fn check_url(name: &str, value: Option<&String>) -> CryptoMktResult<()> {
+    let value = match value {
+        Some(value) => value,
+        None => return Ok(()),
+    };
+    match Url::parse(value) {
+        Ok(url) if (url.scheme() == "http" || url.scheme() == "https") && url.has_host() => Ok(()),
+        _ => Err(invalid_argument(name, value)),
+    }
+}
@@ -0,0 +1,216 @@
+//!
+//! Limitador de peticiones del lado del cliente (token bucket), con presupuestos
+//! separados para los endpoints públicos y privados
+//!
+
+use crate::internal::errors::{CryptoMktErrorType, CryptoMktResult};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+///
+/// Tipo de endpoint al que se descuenta la petición
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RateLimitScope {
+    /// Endpoints públicos, `RequestMethod::Get(true)`
+    Public,
+    /// Endpoints privados, firmados con la API key
+    Private,
+}
+
+///
+/// Comportamiento cuando no quedan peticiones disponibles
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RateLimitMode {
+    /// Espera hasta que haya una petición disponible
+    Queue,
+    /// Falla inmediatamente con `CryptoMktErrorType::RateLimitExceeded`
+    FailFast,
+}
+
+///
+/// Cuota de peticiones: `requests` peticiones cada `period`, que también es
+/// la ráfaga máxima permitida
+///
+#[derive(Debug, Clone, Copy)]
+pub struct Quota {
+    requests: u32,
+    period: Duration,
+}
+
+impl Quota {
+    ///
+    /// Crea una cuota de `requests` peticiones cada `period`
+    ///
+    pub fn new(requests: u32, period: Duration) -> Self {
+        Quota {
+            requests: requests.max(1),
+            period,
+        }
+    }
+
+    ///
+    /// Cuota de `requests` peticiones por segundo
+    ///
+    pub fn per_second(requests: u32) -> Self {
+        Quota::new(requests, Duration::from_secs(1))
+    }
+
+    ///
+    /// Cuota de `requests` peticiones por minuto
+    ///
+    pub fn per_minute(requests: u32) -> Self {
+        Quota::new(requests, Duration::from_secs(60))
+    }
+}
+
+///
+/// Token bucket, los tokens negativos corresponden a peticiones en cola
+///
+#[derive(Debug)]
+struct TokenBucket {
+    capacity: f64,
+    tokens: f64,
+    refill_per_sec: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new(quota: Quota) -> Self {
+        let capacity = f64::from(quota.requests);
+        let period = quota.period.as_secs_f64();
+        TokenBucket {
+            capacity,
+            tokens: capacity,
+            refill_per_sec: if period > 0.0 {
+                capacity / period
+            } else {
+                f64::INFINITY
+            },
+            last_refill: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+        self.last_refill = now;
+    }
+
+    ///
+    /// Reserva un token y devuelve la espera necesaria, `None` si no hay tokens
+    /// disponibles y no se permite esperar
+    ///
+    fn reserve(&mut self, queue: bool) -> Option<Duration> {
+        self.refill();
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            return Some(Duration::from_secs(0));
+        }
+        if !queue {
+            return None;
+        }
+        self.tokens -= 1.0;
+        Some(Duration::from_secs_f64(-self.tokens / self.refill_per_sec))
+    }
+
+    fn utilisation(&mut self) -> f64 {
+        self.refill();
+        1.0 - self.tokens / self.capacity
+    }
+}
+
+///
+/// Limitador de peticiones compartido por todos los clones de `CryptoMktApi`
+/// y `AsyncCryptoMktApi`
+///
+/// ```
+/// extern crate cryptomkt;
+/// use cryptomkt::{AsyncCryptoMktApi, Quota, RateLimitMode, RateLimitScope, RateLimiter};
+/// use std::sync::Arc;
+///
+/// let limiter = Arc::new(RateLimiter::new(
+///     Quota::per_minute(100),
+///     Quota::per_minute(60),
+///     RateLimitMode::Queue,
+/// ));
+/// let api = AsyncCryptoMktApi::builder("<API Key>", "<Secret Key>")
+///     .rate_limiter(limiter.clone())
+///     .build()
+///     .unwrap();
+/// println!("{}", limiter.utilisation(RateLimitScope::Public));
+/// ```
+///
+#[derive(Debug)]
+pub struct RateLimiter {
+    public: Mutex<TokenBucket>,
+    private: Mutex<TokenBucket>,
+    mode: RateLimitMode,
+}
+
+impl RateLimiter {
+    ///
+    /// Crea el limitador
+    ///
+    /// Argumentos
+    ///     public: Cuota de los endpoints públicos
+    ///     private: Cuota de los endpoints privados
+    ///     mode: Define si se espera o se falla cuando se agota la cuota
+    ///
+    pub fn new(public: Quota, private: Quota, mode: RateLimitMode) -> Self {
+        RateLimiter {
+            public: Mutex::new(TokenBucket::new(public)),
+            private: Mutex::new(TokenBucket::new(private)),
+            mode,
+        }
+    }
+
+    ///
+    /// Devuelve el comportamiento cuando se agota la cuota
+    ///
+    pub fn mode(&self) -> RateLimitMode {
+        self.mode
+    }
+
+    fn bucket(&self, scope: RateLimitScope) -> &Mutex<TokenBucket> {
+        match scope {
+            RateLimitScope::Public => &self.public,
+            RateLimitScope::Private => &self.private,
+        }
+    }
+
+    ///
+    /// Reserva una petición y devuelve el tiempo que se debe esperar antes de realizarla
+    ///
+    /// Argumentos
+    ///     scope: Tipo de endpoint
+    ///
+    pub fn reserve(&self, scope: RateLimitScope) -> CryptoMktResult<Duration> {
+        let mut bucket = self.bucket(scope).lock().unwrap();
+        match bucket.reserve(self.mode == RateLimitMode::Queue) {
+            Some(wait) => {
+                if wait > Duration::from_secs(0) {
+                    debug!(target: "cryptomkt", "{:?} rate limit reached, waiting {:?}", scope, wait);
+                }
+                Ok(wait)
+            }
+            None => {
+                warn!(target: "cryptomkt", "{:?} rate limit exceeded", scope);
+                Err(CryptoMktErrorType::RateLimitExceeded.into())
+            }
+        }
+    }
+
+    ///
+    /// Fracción de la cuota consumida, entre 0 y 1. Los valores mayores que 1
+    /// indican peticiones esperando en cola
+    ///
+    /// Argumentos
+    ///     scope: Tipo de endpoint
+    ///
+    pub fn utilisation(&self, scope: RateLimitScope) -> f64 {
+        self.bucket(scope).lock().unwrap().utilisation()
+    }
+}
@@ -1,11 +1,32 @@
-use reqwest::{header::HeaderMap, blocking::Client, StatusCode, Url};
+use async_trait::async_trait;
+#[cfg(feature = "blocking")]
+use reqwest::blocking::{Client, RequestBuilder};
+pub use reqwest::{header::HeaderMap, Url};
+use reqwest::StatusCode;
 use std::collections::HashMap;
+use std::sync::Arc;
+#[cfg(feature = "blocking")]
+use std::thread;
 
-use crate::internal::errors::{CryptoMktErrorType, CryptoMktResult};
+use crate::internal::api::X_MKT_APIKEY;
+use crate::internal::errors::{CryptoMktError, CryptoMktErrorType, CryptoMktResult};
+use crate::internal::rate_limit::{RateLimitScope, RateLimiter};
+use crate::internal::retry::{retry_after, RetryPolicy};
+
+/// Transporte bloqueante compartido entre los clones de `CryptoMktApi`
+#[cfg(feature = "blocking")]
+pub(crate) type DynHttpRequest = dyn HttpRequest<Result = CryptoMktResult<String>> + Send + Sync;
+
+/// Transporte asíncrono compartido entre los clones de `AsyncCryptoMktApi`
+pub(crate) type DynAsyncHttpRequest = dyn AsyncHttpRequest + Send + Sync;
 
 ///
 /// Definición que deben cumplir para poder extaer datos mediante HTTP
 ///
+/// Implementando este trait se puede sustituir el transporte por defecto (`CryptoMktRequest`)
+/// mediante `CryptoMktApi::with_transport`, por ejemplo para usar otro cliente HTTP, un proxy
+/// que grabe las peticiones o un doble de pruebas
+///
 pub trait HttpRequest {
     ///
     /// Result
@@ -31,83 +52,247 @@ pub trait HttpRequest {
     ) -> Self::Result;
 }
 
+///
+/// Versión asíncrona de `HttpRequest`, las peticiones no bloquean el hilo
+/// que las realiza. Se usa con `AsyncCryptoMktApi::with_transport`
+///
+#[async_trait]
+pub trait AsyncHttpRequest {
+    ///
+    ///  Argumentos:
+    ///     url: Url
+    ///     headers: HeaderMap
+    ///
+    async fn get(&self, url: Url, headers: HeaderMap) -> CryptoMktResult<String>;
+    ///
+    ///  Argumentos:
+    ///     url: Url
+    ///     headers: Headers
+    ///     payload: Datos a enviar a la URL especificada
+    ///
+    async fn post(
+        &self,
+        url: Url,
+        headers: HeaderMap,
+        payload: HashMap<String, String>,
+    ) -> CryptoMktResult<String>;
+}
+
+///
+/// Traspasa los errores del StatusCode para CryptoMktErrorType
+///
+/// Argumentos:
+///     prefix: Cadena de texto adiciona al log de errores
+///     status: Estado de la petición
+///
+pub fn translate_errors(prefix: &str, status: StatusCode) -> CryptoMktErrorType {
+    match status {
+        StatusCode::UNAUTHORIZED => {
+            error!(target: "cryptomkt", "{}: StatusCode: {:?}", prefix, StatusCode::UNAUTHORIZED);
+            CryptoMktErrorType::RequestUnauthorized
+        }
+
+        StatusCode::FORBIDDEN => {
+            error!(target: "cryptomkt", "{}: StatusCode: {:?}", prefix, StatusCode::FORBIDDEN);
+            CryptoMktErrorType::RequestForbidden
+        }
+        StatusCode::NOT_FOUND => {
+            error!(target: "cryptomkt", "{}: StatusCode: {:?}", prefix, StatusCode::NOT_FOUND);
+            CryptoMktErrorType::RequestNotFound
+        }
+        StatusCode::METHOD_NOT_ALLOWED => {
+            error!(target: "cryptomkt", "{}: StatusCode: {:?}", prefix, StatusCode::METHOD_NOT_ALLOWED);
+            CryptoMktErrorType::RequestMethodNotAllowed
+        }
+        StatusCode::NOT_ACCEPTABLE => {
+            error!(target: "cryptomkt", "{}: StatusCode: {:?}", prefix, StatusCode::NOT_ACCEPTABLE);
+            CryptoMktErrorType::RequestNotAcceptable
+        }
+        StatusCode::GONE => {
+            error!(target: "cryptomkt", "{}: StatusCode: {:?}", prefix, StatusCode::GONE);
+            CryptoMktErrorType::RequestGone
+        }
+        StatusCode::TOO_MANY_REQUESTS => {
+            error!(target: "cryptomkt", "{}: StatusCode: {:?}", prefix, StatusCode::TOO_MANY_REQUESTS);
+            CryptoMktErrorType::RequestTooManyRequests
+        }
+        StatusCode::INTERNAL_SERVER_ERROR => {
+            error!(target: "cryptomkt", "{}: StatusCode: {:?}", prefix, StatusCode::INTERNAL_SERVER_ERROR);
+            CryptoMktErrorType::RequestInternalServerError
+        }
+        StatusCode::SERVICE_UNAVAILABLE => {
+            error!(target: "cryptomkt", "{}: StatusCode: {:?}", prefix, StatusCode::SERVICE_UNAVAILABLE);
+            CryptoMktErrorType::RequestServiceUnavailable
+        }
+        status => {
+            error!(target: "cryptomkt", "{}: StatusCode: {:?} Code({:?})", prefix, status, status.as_u16());
+            if status.as_u16() == 418 {
+                CryptoMktErrorType::RequestTeapot
+            } else {
+                CryptoMktErrorType::BadRequest
+            }
+        }
+    }
+}
+
+///
+/// Construye el error de una respuesta con estado distinto de 200, conservando el
+/// código HTTP, el mensaje enviado por el exchange y la cabecera `Retry-After`
+///
+/// Argumentos:
+///     prefix: Método de la petición
+///     status: Estado de la petición
+///     headers: Cabeceras de la respuesta
+///     body: Cuerpo de la respuesta
+///
+pub fn response_error(
+    prefix: &str,
+    status: StatusCode,
+    headers: &HeaderMap,
+    body: &str,
+) -> CryptoMktError {
+    let mut error = CryptoMktError::from(translate_errors(prefix, status))
+        .with_status(status.as_u16())
+        .with_method(prefix);
+    if let Some(message) = server_message(body) {
+        error = error.with_message(&message);
+    }
+    if let Some(wait) = retry_after(headers) {
+        error = error.with_retry_after(wait);
+    }
+    error
+}
+
+///
+/// Extrae el campo `message` del JSON de error enviado por el exchange
+///
+pub fn server_message(body: &str) -> Option<String> {
+    serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .and_then(|value| value.get("message").cloned())
+        .and_then(|message| match message {
+            serde_json::Value::String(message) => Some(message),
+            serde_json::Value::Null => None,
+            other => Some(other.to_string()),
+        })
+}
+
 ///
 /// CryptoMktRequest
 ///
+#[cfg(feature = "blocking")]
 #[derive(Debug, Clone)]
 pub struct CryptoMktRequest {
     client: Box<Client>,
+    retry_policy: RetryPolicy,
+    rate_limiter: Option<Arc<RateLimiter>>,
 }
 
+#[cfg(feature = "blocking")]
 impl CryptoMktRequest {
     ///
-    /// Devuelve una nueva instancia
+    /// Devuelve una nueva instancia, sin reintentos
     ///
     pub fn new() -> Self {
+        Self::with_retry_policy(RetryPolicy::none())
+    }
+    ///
+    /// Devuelve una nueva instancia que reintenta los errores transitorios
+    /// según la política indicada
+    ///
+    pub fn with_retry_policy(retry_policy: RetryPolicy) -> Self {
         CryptoMktRequest {
             client: Box::new(Client::new()),
+            retry_policy,
+            rate_limiter: None,
         }
     }
     ///
+    /// Reserva en el limitador una petición por cada reintento, el primer intento lo
+    /// reserva `Api` antes de llamar al transporte
+    ///
+    pub fn with_rate_limiter(mut self, rate_limiter: Arc<RateLimiter>) -> Self {
+        self.rate_limiter = Some(rate_limiter);
+        self
+    }
+    ///
     /// Traspasa los errores del StatusCode para CryptoMktErrorType
     ///
     /// Argumentos:
     ///     prefix: Cadena de texto adiciona al log de errores
     ///     status: Estado de la petición
     ///
-    pub fn translate_errors<'c>(&self, prefix: &'c str, status: StatusCode) -> CryptoMktErrorType {
-        match status {
-            StatusCode::UNAUTHORIZED => {
-                error!(target: "cryptomkt", "{}: StatusCode: {:?}", prefix, StatusCode::UNAUTHORIZED);
-                CryptoMktErrorType::RequestUnauthorized
-            }
+    pub fn translate_errors(&self, prefix: &str, status: StatusCode) -> CryptoMktErrorType {
+        translate_errors(prefix, status)
+    }
 
-            StatusCode::FORBIDDEN => {
-                error!(target: "cryptomkt", "{}: StatusCode: {:?}", prefix, StatusCode::FORBIDDEN);
-                CryptoMktErrorType::RequestForbidden
-            }
-            StatusCode::NOT_FOUND => {
-                error!(target: "cryptomkt", "{}: StatusCode: {:?}", prefix, StatusCode::NOT_FOUND);
-                CryptoMktErrorType::RequestNotFound
-            }
-            StatusCode::METHOD_NOT_ALLOWED => {
-                error!(target: "cryptomkt", "{}: StatusCode: {:?}", prefix, StatusCode::METHOD_NOT_ALLOWED);
-                CryptoMktErrorType::RequestMethodNotAllowed
-            }
-            StatusCode::NOT_ACCEPTABLE => {
-                error!(target: "cryptomkt", "{}: StatusCode: {:?}", prefix, StatusCode::NOT_ACCEPTABLE);
-                CryptoMktErrorType::RequestNotAcceptable
-            }
-            StatusCode::GONE => {
-                error!(target: "cryptomkt", "{}: StatusCode: {:?}", prefix, StatusCode::GONE);
-                CryptoMktErrorType::RequestGone
-            }
-            StatusCode::TOO_MANY_REQUESTS => {
-                error!(target: "cryptomkt", "{}: StatusCode: {:?}", prefix, StatusCode::TOO_MANY_REQUESTS);
-                CryptoMktErrorType::RequestTooManyRequests
-            }
-            StatusCode::INTERNAL_SERVER_ERROR => {
-                error!(target: "cryptomkt", "{}: StatusCode: {:?}", prefix, StatusCode::INTERNAL_SERVER_ERROR);
-                CryptoMktErrorType::RequestInternalServerError
-            }
-            StatusCode::SERVICE_UNAVAILABLE => {
-                error!(target: "cryptomkt", "{}: StatusCode: {:?}", prefix, StatusCode::SERVICE_UNAVAILABLE);
-                CryptoMktErrorType::RequestServiceUnavailable
-            }
-            status => {
-                error!(target: "cryptomkt", "{}: StatusCode: {:?} Code({:?})", prefix, status, status.as_u16());
-                if status.as_u16() == 418 {
-                    CryptoMktErrorType::RequestTeapot
-                } else {
-                    CryptoMktErrorType::BadRequest
-                }
+    ///
+    /// Realiza la petición, repitiéndola mientras la política de reintentos lo permita
+    ///
+    /// Argumentos:
+    ///     prefix: Método de la petición, usado en el log
+    ///     url: Url
+    ///     scope: Cuota del limitador que consumen los reintentos
+    ///     request: Construye la petición de cada intento
+    ///
+    fn send_with_retry<F>(
+        &self,
+        prefix: &str,
+        url: &Url,
+        scope: RateLimitScope,
+        request: F,
+    ) -> CryptoMktResult<String>
+    where
+        F: Fn() -> RequestBuilder,
+    {
+        let mut attempt = 1;
+        loop {
+            let error = match request().send() {
+                Ok(resp) => match resp.status() {
+                    StatusCode::OK => {
+                        return match resp.text() {
+                            Ok(txt) => Ok(txt),
+                            Err(e) => {
+                                error!(target: "cryptomkt", "{}: Response Details: {:?}", prefix, e);
+                                Err(text_error(prefix).with_source(e))
+                            }
+                        };
+                    }
+                    status => {
+                        let headers = resp.headers().clone();
+                        let body = resp.text().unwrap_or_default();
+                        response_error(prefix, status, &headers, &body)
+                    }
+                },
+                Err(e) => send_error(prefix, e),
+            };
+            if !self
+                .retry_policy
+                .should_retry(&error, attempt, prefix == "POST", url)
+            {
+                return Err(error);
             }
+            let delay = self.retry_policy.delay(attempt, error.retry_after());
+            warn!(
+                target: "cryptomkt",
+                "{} {}: attempt {}/{} failed with {:?}, retrying in {:?}",
+                prefix, url, attempt, self.retry_policy.get_max_attempts(), error, delay
+            );
+            thread::sleep(delay + reserve_retry(&self.rate_limiter, scope)?);
+            attempt += 1;
         }
     }
 }
 
-impl HttpRequest for CryptoMktRequest {
+#[cfg(feature = "blocking")]
+impl Default for CryptoMktRequest {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
+#[cfg(feature = "blocking")]
+impl HttpRequest for CryptoMktRequest {
     type Result = CryptoMktResult<String>;
 
     ///
@@ -116,23 +301,9 @@ impl HttpRequest for CryptoMktRequest {
     ///     headers: HeaderMap
     ///
     fn get(&self, url: Url, headers: HeaderMap) -> Self::Result {
-        let result = self.client.get(url).headers(headers).send();
-        match result {
-            Ok(resp) => match resp.status() {
-                StatusCode::OK => match resp.text() {
-                    Ok(txt) => Ok(txt),
-                    Err(e) => {
-                        error!(target: "cryptomkt", "GET: Request Text details: {:?}", e);
-                        Err(CryptoMktErrorType::MalformedResource)
-                    }
-                },
-                status => Err(self.translate_errors("GET", status)),
-            },
-            Err(e) => {
-                error!(target: "cryptomkt", "GET {:?}", e);
-                Err(CryptoMktErrorType::BadRequest)
-            }
-        }
+        self.send_with_retry("GET", &url, rate_limit_scope(&headers), || {
+            self.client.get(url.clone()).headers(headers.clone())
+        })
     }
     ///
     ///  Argumentos:
@@ -146,23 +317,198 @@ impl HttpRequest for CryptoMktRequest {
         headers: HeaderMap,
         payload: HashMap<String, String>,
     ) -> Self::Result {
-        let result = self.client.post(url).headers(headers).form(&payload).send();
-
-        match result {
-            Ok(resp) => match resp.status() {
-                StatusCode::OK => match resp.text() {
-                    Ok(txt) => Ok(txt),
-                    Err(e) => {
-                        error!(target: "cryptomkt", "POST: Response Details: {:?}", e);
-                        Err(CryptoMktErrorType::BadRequest)
+        self.send_with_retry("POST", &url, rate_limit_scope(&headers), || {
+            self.client
+                .post(url.clone())
+                .headers(headers.clone())
+                .form(&payload)
+        })
+    }
+}
+
+///
+/// CryptoMktAsyncRequest, transporte asíncrono basado en `reqwest::Client`
+///
+#[derive(Debug, Clone)]
+pub struct CryptoMktAsyncRequest {
+    client: Box<reqwest::Client>,
+    retry_policy: RetryPolicy,
+    rate_limiter: Option<Arc<RateLimiter>>,
+}
+
+impl CryptoMktAsyncRequest {
+    ///
+    /// Devuelve una nueva instancia, sin reintentos
+    ///
+    pub fn new() -> Self {
+        Self::with_retry_policy(RetryPolicy::none())
+    }
+    ///
+    /// Devuelve una nueva instancia que reintenta los errores transitorios
+    /// según la política indicada
+    ///
+    pub fn with_retry_policy(retry_policy: RetryPolicy) -> Self {
+        CryptoMktAsyncRequest {
+            client: Box::new(reqwest::Client::new()),
+            retry_policy,
+            rate_limiter: None,
+        }
+    }
+    ///
+    /// Reserva en el limitador una petición por cada reintento, el primer intento lo
+    /// reserva `Api` antes de llamar al transporte
+    ///
+    pub fn with_rate_limiter(mut self, rate_limiter: Arc<RateLimiter>) -> Self {
+        self.rate_limiter = Some(rate_limiter);
+        self
+    }
+
+    ///
+    /// Realiza la petición, repitiéndola mientras la política de reintentos lo permita
+    ///
+    /// Argumentos:
+    ///     prefix: Método de la petición, usado en el log
+    ///     url: Url
+    ///     scope: Cuota del limitador que consumen los reintentos
+    ///     request: Construye la petición de cada intento
+    ///
+    async fn send_with_retry<F>(
+        &self,
+        prefix: &str,
+        url: &Url,
+        scope: RateLimitScope,
+        request: F,
+    ) -> CryptoMktResult<String>
+    where
+        F: Fn() -> reqwest::RequestBuilder,
+    {
+        let mut attempt = 1;
+        loop {
+            let error = match request().send().await {
+                Ok(resp) => match resp.status() {
+                    StatusCode::OK => {
+                        return match resp.text().await {
+                            Ok(txt) => Ok(txt),
+                            Err(e) => {
+                                error!(target: "cryptomkt", "{}: Response Details: {:?}", prefix, e);
+                                Err(text_error(prefix).with_source(e))
+                            }
+                        };
+                    }
+                    status => {
+                        let headers = resp.headers().clone();
+                        let body = resp.text().await.unwrap_or_default();
+                        response_error(prefix, status, &headers, &body)
                     }
                 },
-                status => Err(self.translate_errors("POST", status)),
-            },
-            Err(e) => {
-                error!(target: "cryptomkt", "POST {:?}", e);
-                Err(CryptoMktErrorType::BadRequest)
+                Err(e) => send_error(prefix, e),
+            };
+            if !self
+                .retry_policy
+                .should_retry(&error, attempt, prefix == "POST", url)
+            {
+                return Err(error);
             }
+            let delay = self.retry_policy.delay(attempt, error.retry_after());
+            warn!(
+                target: "cryptomkt",
+                "{} {}: attempt {}/{} failed with {:?}, retrying in {:?}",
+                prefix, url, attempt, self.retry_policy.get_max_attempts(), error, delay
+            );
+            tokio::time::delay_for(delay + reserve_retry(&self.rate_limiter, scope)?).await;
+            attempt += 1;
         }
     }
 }
+
+impl Default for CryptoMktAsyncRequest {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl AsyncHttpRequest for CryptoMktAsyncRequest {
+    ///
+    ///  Argumentos:
+    ///     url: Url
+    ///     headers: HeaderMap
+    ///
+    async fn get(&self, url: Url, headers: HeaderMap) -> CryptoMktResult<String> {
+        self.send_with_retry("GET", &url, rate_limit_scope(&headers), || {
+            self.client.get(url.clone()).headers(headers.clone())
+        })
+        .await
+    }
+    ///
+    ///  Argumentos:
+    ///     url: Url
+    ///     headers: HeaderMap
+    ///     payload: Datos a enviar a la URL especificada
+    ///
+    async fn post(
+        &self,
+        url: Url,
+        headers: HeaderMap,
+        payload: HashMap<String, String>,
+    ) -> CryptoMktResult<String> {
+        self.send_with_retry("POST", &url, rate_limit_scope(&headers), || {
+            self.client
+                .post(url.clone())
+                .headers(headers.clone())
+                .form(&payload)
+        })
+        .await
+    }
+}
+
+///
+/// Cuota de una petición: las peticiones firmadas con el API key son privadas
+///
+fn rate_limit_scope(headers: &HeaderMap) -> RateLimitScope {
+    if headers.contains_key(X_MKT_APIKEY) {
+        RateLimitScope::Private
+    } else {
+        RateLimitScope::Public
+    }
+}
+
+///
+/// Reserva la petición de un reintento y devuelve la espera que exige el limitador
+///
+fn reserve_retry(
+    rate_limiter: &Option<Arc<RateLimiter>>,
+    scope: RateLimitScope,
+) -> CryptoMktResult<std::time::Duration> {
+    match rate_limiter {
+        Some(rate_limiter) => rate_limiter.reserve(scope),
+        None => Ok(std::time::Duration::from_secs(0)),
+    }
+}
+
+///
+/// Error de una petición que no obtuvo respuesta: los fallos de conexión, DNS y tiempo
+/// de espera son `NetworkError` y se pueden reintentar, una petición mal construida
+/// es `BadRequest`
+///
+fn send_error(prefix: &str, e: reqwest::Error) -> CryptoMktError {
+    error!(target: "cryptomkt", "{} {:?}", prefix, e);
+    let kind = if e.is_builder() || e.is_redirect() {
+        CryptoMktErrorType::BadRequest
+    } else {
+        CryptoMktErrorType::NetworkError
+    };
+    CryptoMktError::from(kind).with_method(prefix).with_source(e)
+}
+
+///
+/// Error devuelto cuando no se puede leer el cuerpo de la respuesta
+///
+fn text_error(prefix: &str) -> CryptoMktError {
+    let kind = if prefix == "GET" {
+        CryptoMktErrorType::MalformedResource
+    } else {
+        CryptoMktErrorType::BadRequest
+    };
+    CryptoMktError::from(kind).with_method(prefix)
+}
@@ -3,30 +3,39 @@
 //! servidor para los diferentes endpoints
 //!
 
-use crate::internal::models::{Balance, Book, Order, OrdersInstant, Payment, Ticker, Trade};
-use serde::Deserialize;
+use crate::internal::errors::{CryptoMktError, CryptoMktErrorType};
+use crate::internal::models::{
+    Balance, Book, Order, OrdersInstant, Payment, SocketAuth, Ticker, Trade,
+};
+use crate::internal::request::server_message;
+use serde::{Deserialize, Deserializer};
 use serde_json::Value;
 
 /// Información sobre la paginación
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
 pub struct Pagination {
-    pub limit: i32,
-    #[serde(default)]
-    pub page: i32,
+    pub limit: u32,
     #[serde(default)]
-    pub previous: Value, // String / i32
-    pub next: Value, // String / i32
+    pub page: u32,
+    #[serde(default, deserialize_with = "page_number")]
+    pub previous: Option<u32>,
+    #[serde(default, deserialize_with = "page_number")]
+    pub next: Option<u32>,
 }
-/// Timeout in seconds.
-impl Default for Pagination {
-    fn default() -> Self {
-        Pagination {
-            limit: 0,
-            page: 0,
-            previous: Value::default(),
-            next: Value::default(),
-        }
-    }
+
+///
+/// Lee el número de una página, enviado como número, como cadena o como
+/// `null` / `"null"` cuando no existe
+///
+fn page_number<'de, D>(deserializer: D) -> Result<Option<u32>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(match Value::deserialize(deserializer)? {
+        Value::Number(page) => page.as_u64().map(|page| page as u32),
+        Value::String(page) => page.trim().parse().ok(),
+        _ => None,
+    })
 }
 
 /// Conforma la respuesta enviada por el Exchange cryptomkt
@@ -38,6 +47,35 @@ pub struct CryptoMktResponse<T> {
     pub pagination: Pagination,
 }
 
+/// Estado de la respuesta, se lee antes de deserializar `data`
+#[derive(Deserialize, Debug)]
+struct ResponseStatus {
+    #[serde(default)]
+    status: String,
+}
+
+///
+/// Devuelve el error de una respuesta `{"status": "error", "message": ...}`, con el
+/// mensaje traducido al tipo de error correspondiente
+///
+/// Argumentos
+///     body: Cuerpo de la respuesta
+///
+pub fn response_status_error(body: &str) -> Option<CryptoMktError> {
+    let status = serde_json::from_str::<ResponseStatus>(body).ok()?;
+    if status.status != "error" {
+        return None;
+    }
+    let error = match server_message(body) {
+        Some(message) => {
+            CryptoMktError::from(CryptoMktErrorType::from_exchange_message(&message))
+                .with_message(&message)
+        }
+        None => CryptoMktError::from(CryptoMktErrorType::ExchangeError),
+    };
+    Some(error)
+}
+
 // ============ Market ==============
 
 /// Estructura de la respuesta asociada a los mercados, existentes
@@ -69,3 +107,6 @@ pub type BalanceResponse = CryptoMktResponse<Vec<Balance>>;
 /// Pagos:
 pub type PaymentResponse = CryptoMktResponse<Payment>;
 pub type PaymentListResponse = CryptoMktResponse<Vec<Payment>>;
+
+/// Credenciales de la conexión al socket:
+pub type SocketAuthResponse = CryptoMktResponse<SocketAuth>;
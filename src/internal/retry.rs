@@ -0,0 +1,195 @@
+//!
+//! Política de reintentos con espera exponencial para los errores transitorios
+//! del servidor (429, 500 y 503) y los fallos de red (conexión, DNS y tiempo de espera)
+//!
+
+use crate::internal::errors::CryptoMktError;
+use rand::Rng;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::Url;
+use std::time::Duration;
+
+///
+/// Política de reintentos del transporte
+///
+/// Las peticiones GET se reintentan siempre que el error sea transitorio, las peticiones
+/// POST no son idempotentes (`orders/create`, `payment/new_order`) y solo se reintentan
+/// si se autoriza explícitamente el endpoint con `retry_post` o todas con `retry_all_posts`.
+///
+/// ```
+/// extern crate cryptomkt;
+/// use cryptomkt::RetryPolicy;
+/// use std::time::Duration;
+///
+/// let policy = RetryPolicy::default()
+///     .max_attempts(5)
+///     .base_delay(Duration::from_millis(200))
+///     .retry_post("orders/cancel");
+/// ```
+///
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+    jitter: bool,
+    respect_retry_after: bool,
+    retry_all_posts: bool,
+    post_endpoints: Vec<String>,
+}
+
+impl Default for RetryPolicy {
+    ///
+    /// 3 intentos, espera base de 500ms, máximo 30s, con jitter y respetando `Retry-After`
+    ///
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            jitter: true,
+            respect_retry_after: true,
+            retry_all_posts: false,
+            post_endpoints: Vec::new(),
+        }
+    }
+}
+
+impl RetryPolicy {
+    ///
+    /// Política sin reintentos, cada petición se realiza una sola vez
+    ///
+    pub fn none() -> Self {
+        RetryPolicy::default().max_attempts(1)
+    }
+
+    ///
+    /// Cantidad máxima de intentos, incluyendo el primero
+    ///
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    ///
+    /// Espera del primer reintento, se duplica en cada intento
+    ///
+    pub fn base_delay(mut self, base_delay: Duration) -> Self {
+        self.base_delay = base_delay;
+        self
+    }
+
+    ///
+    /// Espera máxima entre dos intentos
+    ///
+    pub fn max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    ///
+    /// Aplica un factor aleatorio (entre 50% y 100%) a cada espera para evitar
+    /// que varios clientes reintenten al mismo tiempo
+    ///
+    pub fn jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    ///
+    /// Usa la cabecera `Retry-After` enviada por el servidor como espera, si existe
+    ///
+    pub fn respect_retry_after(mut self, respect: bool) -> Self {
+        self.respect_retry_after = respect;
+        self
+    }
+
+    ///
+    /// Autoriza reintentar las peticiones POST al endpoint indicado (Ej. orders/cancel)
+    ///
+    pub fn retry_post(mut self, endpoint: &str) -> Self {
+        self.post_endpoints.push(endpoint.trim_matches('/').to_string());
+        self
+    }
+
+    ///
+    /// Autoriza reintentar todas las peticiones POST, incluidas las no idempotentes
+    ///
+    pub fn retry_all_posts(mut self, retry: bool) -> Self {
+        self.retry_all_posts = retry;
+        self
+    }
+
+    ///
+    /// Devuelve la cantidad máxima de intentos
+    ///
+    pub fn get_max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    ///
+    /// Indica si se debe realizar un nuevo intento
+    ///
+    /// Argumentos
+    ///     error: Error del intento actual
+    ///     attempt: Número del intento actual, comenzando en 1
+    ///     is_post: Define si la petición es POST
+    ///     url: URL de la petición
+    ///
+    pub fn should_retry(
+        &self,
+        error: &CryptoMktError,
+        attempt: u32,
+        is_post: bool,
+        url: &Url,
+    ) -> bool {
+        if attempt >= self.max_attempts || !error.is_retryable() {
+            return false;
+        }
+        if is_post && !self.retry_all_posts {
+            let path = url.path().trim_end_matches('/');
+            return self
+                .post_endpoints
+                .iter()
+                .any(|endpoint| path.ends_with(&format!("/{}", endpoint)));
+        }
+        true
+    }
+
+    ///
+    /// Calcula la espera antes del siguiente intento
+    ///
+    /// Argumentos
+    ///     attempt: Número del intento que falló, comenzando en 1
+    ///     retry_after: Espera solicitada por el servidor
+    ///
+    pub fn delay(&self, attempt: u32, retry_after: Option<Duration>) -> Duration {
+        if let Some(retry_after) = retry_after {
+            if self.respect_retry_after {
+                return retry_after.min(self.max_delay);
+            }
+        }
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        let delay = self
+            .base_delay
+            .checked_mul(factor)
+            .unwrap_or(self.max_delay)
+            .min(self.max_delay);
+        if self.jitter {
+            delay.mul_f64(rand::thread_rng().gen_range(0.5, 1.0))
+        } else {
+            delay
+        }
+    }
+}
+
+///
+/// Extrae la espera en segundos de la cabecera `Retry-After`
+///
+pub fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    headers
+        .get(RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .map(Duration::from_secs)
+}
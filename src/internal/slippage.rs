@@ -0,0 +1,186 @@
+//!
+//! Estimación del precio de ejecución y del deslizamiento de las órdenes instantáneas
+//!
+//! Como en el Instant Exchange, `amount` es el monto en la moneda de cotización para
+//! las compras (Ej. CLP) y la cantidad de la moneda base para las ventas (Ej. ETH).
+//! El deslizamiento es la diferencia relativa entre el precio promedio de ejecución y
+//! el precio medio del mercado, positiva cuando el precio es peor que el medio
+//!
+
+use crate::internal::errors::{CryptoMktError, CryptoMktErrorType, CryptoMktResult};
+use crate::internal::models::{OrderType, OrdersInstant, Ticker};
+use crate::internal::order_book::OrderBook;
+use rust_decimal::Decimal;
+
+///
+/// Origen de la estimación
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EstimateSource {
+    /// Cotización del Instant Exchange (`orders/instant/get`)
+    Quote,
+    /// Recorrido del libro de órdenes
+    OrderBook,
+}
+
+///
+/// Estimación de la ejecución de una orden instantánea
+///
+/// ```
+/// extern crate cryptomkt;
+/// use cryptomkt::{Decimal, OrderBook, OrderType, SlippageEstimate};
+///
+/// let mut book = OrderBook::new("ETHCLP");
+/// book.update(OrderType::Buy, Decimal::new(99, 0), Decimal::new(1, 0));
+/// book.update(OrderType::Sell, Decimal::new(101, 0), Decimal::new(1, 0));
+/// book.update(OrderType::Sell, Decimal::new(111, 0), Decimal::new(1, 0));
+///
+/// // Compra por 212 CLP: 1 ETH a 101 y 1 ETH a 111
+/// let estimate = SlippageEstimate::from_order_book(OrderType::Buy, Decimal::new(212, 0), &book);
+/// assert_eq!(estimate.average_price, Some(Decimal::new(106, 0)));
+/// assert_eq!(estimate.slippage(), Some(Decimal::new(6, 2)));
+/// assert!(estimate.check(Decimal::new(5, 2)).is_err());
+/// ```
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct SlippageEstimate {
+    /// Origen de la estimación
+    pub source: EstimateSource,
+    /// Tipo de la orden
+    pub order_type: OrderType,
+    /// Monto solicitado
+    pub amount: Decimal,
+    /// Lo que se recibe: moneda base en las compras y de cotización en las ventas
+    pub obtained: Decimal,
+    /// Lo que se entrega, menor a `amount` si no hay liquidez suficiente
+    pub required: Decimal,
+    /// Precio promedio de ejecución
+    pub average_price: Option<Decimal>,
+    /// Precio del peor nivel alcanzado, solo se conoce al recorrer el libro
+    pub worst_price: Option<Decimal>,
+    /// Precio medio del mercado usado como referencia
+    pub mid_price: Option<Decimal>,
+    /// Indica si el mercado cubre todo el monto solicitado
+    pub complete: bool,
+}
+
+impl SlippageEstimate {
+    ///
+    /// Estimación a partir de la cotización del Instant Exchange
+    ///
+    /// Argumentos
+    ///     order_type: Tipo de la orden
+    ///     amount: Monto cotizado
+    ///     quote: Respuesta de `get_order_instant`
+    ///     mid_price: Precio medio de referencia, ver `mid_price`
+    ///
+    pub fn from_quote(
+        order_type: OrderType,
+        amount: Decimal,
+        quote: &OrdersInstant,
+        mid_price: Option<Decimal>,
+    ) -> Self {
+        let (base, counter) = match order_type {
+            OrderType::Sell => (quote.required, quote.obtained),
+            _ => (quote.obtained, quote.required),
+        };
+        SlippageEstimate {
+            source: EstimateSource::Quote,
+            average_price: if base > Decimal::ZERO {
+                Some(counter / base)
+            } else {
+                None
+            },
+            worst_price: None,
+            mid_price,
+            complete: quote.required >= amount && quote.obtained > Decimal::ZERO,
+            obtained: quote.obtained,
+            required: quote.required,
+            order_type,
+            amount,
+        }
+    }
+
+    ///
+    /// Estimación recorriendo el libro de órdenes: las compras consumen las ventas
+    /// del libro y las ventas consumen las compras
+    ///
+    /// Argumentos
+    ///     order_type: Tipo de la orden
+    ///     amount: Monto de la orden
+    ///     book: Libro de órdenes del mercado
+    ///
+    pub fn from_order_book(order_type: OrderType, amount: Decimal, book: &OrderBook) -> Self {
+        let (fill, obtained, required) = match order_type {
+            OrderType::Buy => {
+                let fill = book.fill_volume(&OrderType::Sell, amount);
+                (fill.clone(), fill.amount, fill.volume)
+            }
+            OrderType::Sell => {
+                let fill = book.cumulative_volume(&OrderType::Buy, amount);
+                (fill.clone(), fill.volume, fill.amount)
+            }
+            OrderType::Other(_) => {
+                let fill = book.cumulative_volume(&order_type, amount);
+                (fill, Decimal::ZERO, Decimal::ZERO)
+            }
+        };
+        SlippageEstimate {
+            source: EstimateSource::OrderBook,
+            order_type,
+            amount,
+            obtained,
+            required,
+            average_price: fill.average_price,
+            worst_price: fill.worst_price,
+            mid_price: book.mid_price(),
+            complete: fill.complete,
+        }
+    }
+
+    ///
+    /// Deslizamiento respecto del precio medio (Ej. 0.01 = 1%), `None` si no se
+    /// conoce el precio promedio o el precio medio
+    ///
+    pub fn slippage(&self) -> Option<Decimal> {
+        let average = self.average_price?;
+        let mid = self.mid_price.filter(|mid| *mid > Decimal::ZERO)?;
+        match self.order_type {
+            OrderType::Buy => Some((average - mid) / mid),
+            OrderType::Sell => Some((mid - average) / mid),
+            OrderType::Other(_) => None,
+        }
+    }
+
+    ///
+    /// Verifica que la orden se pueda ejecutar completa con un deslizamiento menor
+    /// o igual a `max_slippage`, en otro caso devuelve `SlippageExceeded`
+    ///
+    pub fn check(&self, max_slippage: Decimal) -> CryptoMktResult<()> {
+        let message = if !self.complete {
+            "the market cannot fill the whole amount".to_string()
+        } else {
+            match self.slippage() {
+                Some(slippage) if slippage <= max_slippage => return Ok(()),
+                Some(slippage) => format!(
+                    "estimated slippage {} is above the maximum {}",
+                    slippage.round_dp(6).normalize(),
+                    max_slippage.normalize()
+                ),
+                None => "the slippage cannot be estimated".to_string(),
+            }
+        };
+        Err(CryptoMktError::from(CryptoMktErrorType::SlippageExceeded).with_message(&message))
+    }
+}
+
+///
+/// Precio medio entre la mejor compra y la mejor venta del ticker
+///
+pub(crate) fn mid_price(ticker: &Ticker) -> Option<Decimal> {
+    if ticker.bid > Decimal::ZERO && ticker.ask > Decimal::ZERO {
+        Some((ticker.bid + ticker.ask) / Decimal::new(2, 0))
+    } else {
+        None
+    }
+}
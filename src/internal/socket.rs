@@ -0,0 +1,480 @@
+//!
+//! Conexión Socket.IO con el exchange, usada por los streams en tiempo real
+//!
+//! El socket usa el protocolo Engine.IO v3 sobre websocket: el servidor envía el
+//! paquete de apertura (`0{...}`) con el intervalo de ping, el cliente envía un
+//! ping (`2`) en cada intervalo y los eventos se intercambian como `42["evento", datos]`.
+//!
+//! Cada conexión se autentica con las credenciales de `socket/auth`, firmadas con
+//! la llave del API, y vuelve a enviar las suscripciones después de reconectar
+//!
+
+use crate::async_client::AsyncCryptoMktClient;
+use crate::internal::errors::{CryptoMktError, CryptoMktErrorType, CryptoMktResult};
+use futures::sink::SinkExt;
+use futures::stream::StreamExt;
+use reqwest::Url;
+use serde_json::{json, Value};
+use std::time::{Duration, Instant};
+use tokio::net::TcpStream;
+use tokio_tungstenite::stream::Stream as MaybeTlsStream;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::WebSocketStream;
+
+/// Dirección del socket del exchange
+pub const SOCKET_URL: &str = "wss://socket.cryptomkt.com";
+
+/// Intervalo de ping usado si el servidor no lo informa
+const DEFAULT_PING_INTERVAL: Duration = Duration::from_secs(25);
+
+/// Espera de la respuesta al ping usada si el servidor no la informa
+const DEFAULT_PING_TIMEOUT: Duration = Duration::from_secs(60);
+
+///
+/// Opciones de la conexión al socket
+///
+/// ```
+/// extern crate cryptomkt;
+/// use cryptomkt::SocketOptions;
+/// use std::time::Duration;
+///
+/// let options = SocketOptions::default()
+///     .reconnect_delay(Duration::from_millis(500))
+///     .max_reconnect_delay(Duration::from_secs(10))
+///     .max_reconnects(20);
+/// ```
+///
+#[derive(Debug, Clone)]
+pub struct SocketOptions {
+    url: String,
+    reconnect_delay: Duration,
+    max_reconnect_delay: Duration,
+    max_reconnects: Option<u32>,
+}
+
+impl Default for SocketOptions {
+    ///
+    /// Socket del exchange, primera espera de reconexión de 1s duplicada hasta 30s,
+    /// sin límite de reconexiones
+    ///
+    fn default() -> Self {
+        SocketOptions {
+            url: SOCKET_URL.to_string(),
+            reconnect_delay: Duration::from_secs(1),
+            max_reconnect_delay: Duration::from_secs(30),
+            max_reconnects: None,
+        }
+    }
+}
+
+impl SocketOptions {
+    ///
+    /// Dirección del socket (`wss://` o `ws://`)
+    ///
+    pub fn url(mut self, url: &str) -> Self {
+        self.url = url.trim_end_matches('/').to_string();
+        self
+    }
+
+    ///
+    /// Espera antes del primer intento de reconexión, se duplica en cada intento fallido
+    ///
+    pub fn reconnect_delay(mut self, delay: Duration) -> Self {
+        self.reconnect_delay = delay;
+        self
+    }
+
+    ///
+    /// Espera máxima entre dos intentos de reconexión
+    ///
+    pub fn max_reconnect_delay(mut self, delay: Duration) -> Self {
+        self.max_reconnect_delay = delay;
+        self
+    }
+
+    ///
+    /// Cantidad de intentos de conexión fallidos consecutivos tras la cual el stream
+    /// termina (mínimo 1)
+    ///
+    pub fn max_reconnects(mut self, max_reconnects: u32) -> Self {
+        self.max_reconnects = Some(max_reconnects);
+        self
+    }
+
+    ///
+    /// Espera antes del intento de conexión indicado, comenzando en 1
+    ///
+    fn delay(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.reconnect_delay
+            .checked_mul(factor)
+            .unwrap_or(self.max_reconnect_delay)
+            .min(self.max_reconnect_delay)
+    }
+}
+
+///
+/// Paquete Engine.IO / Socket.IO recibido
+///
+#[derive(Debug, PartialEq)]
+enum Packet {
+    /// Apertura de la sesión, con el intervalo y la espera del ping
+    Open(Duration, Duration),
+    /// Ping del servidor
+    Ping,
+    /// Evento `42["evento", datos]`
+    Event(String, Value),
+    /// El servidor cierra la sesión
+    Close,
+    /// Paquetes sin efecto para el cliente (pong, conexión al namespace, ...)
+    Ignored,
+}
+
+///
+/// Lee un paquete de texto recibido por el websocket
+///
+fn parse_packet(text: &str) -> Packet {
+    let (kind, payload) = text.split_at(text.len().min(1));
+    match kind {
+        "0" => {
+            let open: Value = serde_json::from_str(payload).unwrap_or(Value::Null);
+            let millis = |name: &str, default: Duration| {
+                open.get(name)
+                    .and_then(Value::as_u64)
+                    .map(Duration::from_millis)
+                    .unwrap_or(default)
+            };
+            Packet::Open(
+                millis("pingInterval", DEFAULT_PING_INTERVAL),
+                millis("pingTimeout", DEFAULT_PING_TIMEOUT),
+            )
+        }
+        "1" => Packet::Close,
+        "2" => Packet::Ping,
+        "4" => parse_message(payload),
+        _ => Packet::Ignored,
+    }
+}
+
+///
+/// Lee un mensaje Socket.IO (`2[...]` evento, `1` desconexión)
+///
+fn parse_message(payload: &str) -> Packet {
+    let (kind, body) = payload.split_at(payload.len().min(1));
+    match kind {
+        "1" => Packet::Close,
+        "2" => {
+            // Namespace opcional: `/namespace,[...]`
+            let body = match body.find('[') {
+                Some(start) => &body[start..],
+                None => return Packet::Ignored,
+            };
+            match serde_json::from_str::<Vec<Value>>(body) {
+                Ok(mut values) if !values.is_empty() => {
+                    let data = if values.len() > 1 {
+                        values.remove(1)
+                    } else {
+                        Value::Null
+                    };
+                    match values.remove(0) {
+                        Value::String(event) => Packet::Event(event, data),
+                        _ => Packet::Ignored,
+                    }
+                }
+                _ => Packet::Ignored,
+            }
+        }
+        _ => Packet::Ignored,
+    }
+}
+
+///
+/// Paquete de un evento enviado al servidor
+///
+fn event_packet(event: &str, data: &Value) -> String {
+    format!("42{}", json!([event, data]))
+}
+
+fn closed(message: &str) -> CryptoMktError {
+    CryptoMktError::from(CryptoMktErrorType::ConnectionClosed).with_message(message)
+}
+
+fn closed_by<E>(message: &str, error: E) -> CryptoMktError
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    closed(message).with_source(error)
+}
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream, tokio_tls::TlsStream<TcpStream>>>;
+
+///
+/// Conexión abierta con el servidor
+///
+struct Connection {
+    socket: Socket,
+    ping_interval: Duration,
+    ping_timeout: Duration,
+    next_ping: Instant,
+    last_received: Instant,
+}
+
+impl Connection {
+    ///
+    /// Abre el websocket y espera el paquete de apertura de la sesión
+    ///
+    async fn open(url: &str) -> CryptoMktResult<Self> {
+        let url =
+            Url::parse(&format!("{}/socket.io/?EIO=3&transport=websocket", url)).map_err(|e| {
+                CryptoMktError::from(CryptoMktErrorType::InvalidConfiguration)
+                    .with_message("invalid socket url")
+                    .with_source(e)
+            })?;
+        let host = url.host_str().unwrap_or_default().to_string();
+        let port = url.port_or_known_default().unwrap_or(443);
+        let tcp = TcpStream::connect((host.as_str(), port))
+            .await
+            .map_err(|e| closed_by("cannot connect to the socket", e))?;
+        let stream = if url.scheme() == "wss" {
+            let connector = native_tls::TlsConnector::new()
+                .map_err(|e| closed_by("cannot create the TLS connector", e))?;
+            let tls = tokio_tls::TlsConnector::from(connector)
+                .connect(&host, tcp)
+                .await
+                .map_err(|e| closed_by("TLS handshake failed", e))?;
+            MaybeTlsStream::Tls(tls)
+        } else {
+            MaybeTlsStream::Plain(tcp)
+        };
+        let (socket, _) = tokio_tungstenite::client_async(url.as_str(), stream)
+            .await
+            .map_err(|e| closed_by("websocket handshake failed", e))?;
+
+        let mut connection = Connection {
+            socket,
+            ping_interval: DEFAULT_PING_INTERVAL,
+            ping_timeout: DEFAULT_PING_TIMEOUT,
+            next_ping: Instant::now(),
+            last_received: Instant::now(),
+        };
+        loop {
+            if let Packet::Open(interval, timeout) = connection.receive().await? {
+                connection.ping_interval = interval;
+                connection.ping_timeout = timeout;
+                connection.next_ping = Instant::now() + interval;
+                return Ok(connection);
+            }
+        }
+    }
+
+    async fn send(&mut self, text: String) -> CryptoMktResult<()> {
+        self.socket
+            .send(Message::Text(text))
+            .await
+            .map_err(|e| closed_by("cannot write to the socket", e))
+    }
+
+    async fn emit(&mut self, event: &str, data: &Value) -> CryptoMktResult<()> {
+        self.send(event_packet(event, data)).await
+    }
+
+    ///
+    /// Espera el siguiente paquete de texto
+    ///
+    async fn receive(&mut self) -> CryptoMktResult<Packet> {
+        loop {
+            match self.socket.next().await {
+                Some(Ok(Message::Text(text))) => {
+                    self.last_received = Instant::now();
+                    return Ok(parse_packet(&text));
+                }
+                Some(Ok(Message::Close(_))) | None => {
+                    return Err(closed("socket closed by the server"))
+                }
+                Some(Ok(_)) => self.last_received = Instant::now(),
+                Some(Err(e)) => return Err(closed_by("socket error", e)),
+            }
+        }
+    }
+
+    ///
+    /// Espera el siguiente evento, enviando los pings y respondiendo los del servidor
+    ///
+    async fn next_event(&mut self) -> CryptoMktResult<(String, Value)> {
+        loop {
+            let now = Instant::now();
+            if now.duration_since(self.last_received) > self.ping_interval + self.ping_timeout {
+                return Err(closed("socket ping timeout"));
+            }
+            if now >= self.next_ping {
+                self.send("2".to_string()).await?;
+                self.next_ping = now + self.ping_interval;
+            }
+            let wait = self.next_ping.saturating_duration_since(now);
+            let packet = match tokio::time::timeout(wait, self.receive()).await {
+                Ok(packet) => packet?,
+                Err(_) => continue,
+            };
+            match packet {
+                Packet::Event(event, data) => return Ok((event, data)),
+                Packet::Ping => self.send("3".to_string()).await?,
+                Packet::Close => return Err(closed("socket closed by the server")),
+                Packet::Open(..) | Packet::Ignored => {}
+            }
+        }
+    }
+}
+
+///
+/// Evento de la sesión del socket
+///
+pub(crate) enum SessionEvent {
+    /// Conexión autenticada y suscripciones enviadas
+    Connected { reconnected: bool },
+    /// Evento enviado por el servidor
+    Message(String, Value),
+    /// Se perdió la conexión o falló un intento de conexión
+    Disconnected {
+        error: CryptoMktError,
+        terminal: bool,
+    },
+}
+
+///
+/// Sesión del socket: conecta, autentica, suscribe y reconecta
+///
+pub(crate) struct SocketSession {
+    client: AsyncCryptoMktClient,
+    options: SocketOptions,
+    subscriptions: Vec<String>,
+    channel: Option<String>,
+    connection: Option<Connection>,
+    connected_once: bool,
+    failures: u32,
+    stopped: bool,
+}
+
+impl SocketSession {
+    pub(crate) fn new(client: AsyncCryptoMktClient, options: SocketOptions) -> Self {
+        SocketSession {
+            client,
+            options,
+            subscriptions: Vec::new(),
+            channel: None,
+            connection: None,
+            connected_once: false,
+            failures: 0,
+            stopped: false,
+        }
+    }
+
+    ///
+    /// Autentica además el canal privado con la firma del API key en cada conexión
+    ///
+    pub(crate) fn private(mut self, channel: &str) -> Self {
+        self.channel = Some(channel.to_string());
+        self
+    }
+
+    pub(crate) fn subscriptions(&self) -> Vec<String> {
+        self.subscriptions.clone()
+    }
+
+    ///
+    /// Agrega la suscripción, se envía en la conexión actual y en cada reconexión
+    ///
+    pub(crate) async fn subscribe(&mut self, name: &str) -> CryptoMktResult<()> {
+        let name = name.trim().to_uppercase();
+        if name.is_empty() || self.subscriptions.contains(&name) {
+            return Ok(());
+        }
+        self.subscriptions.push(name.clone());
+        match self.connection.as_mut() {
+            Some(connection) => connection.emit("subscribe", &json!(name)).await,
+            None => Ok(()),
+        }
+    }
+
+    ///
+    /// Quita la suscripción
+    ///
+    pub(crate) async fn unsubscribe(&mut self, name: &str) -> CryptoMktResult<()> {
+        let name = name.trim().to_uppercase();
+        if !self.subscriptions.contains(&name) {
+            return Ok(());
+        }
+        self.subscriptions
+            .retain(|subscription| *subscription != name);
+        match self.connection.as_mut() {
+            Some(connection) => connection.emit("unsubscribe", &json!(name)).await,
+            None => Ok(()),
+        }
+    }
+
+    ///
+    /// Espera el siguiente evento, `None` cuando se alcanzó `max_reconnects`
+    ///
+    pub(crate) async fn next(&mut self) -> Option<SessionEvent> {
+        if self.stopped {
+            return None;
+        }
+        let connection = match self.connection.as_mut() {
+            Some(connection) => connection,
+            None => return Some(self.connect().await),
+        };
+        match connection.next_event().await {
+            Ok((event, data)) => Some(SessionEvent::Message(event, data)),
+            Err(error) => {
+                warn!(target: "cryptomkt", "Socket disconnected: {}", error);
+                self.connection = None;
+                Some(SessionEvent::Disconnected {
+                    error,
+                    terminal: false,
+                })
+            }
+        }
+    }
+
+    async fn connect(&mut self) -> SessionEvent {
+        if self.connected_once || self.failures > 0 {
+            tokio::time::delay_for(self.options.delay(self.failures + 1)).await;
+        }
+        match self.open().await {
+            Ok(connection) => {
+                let reconnected = self.connected_once;
+                self.connection = Some(connection);
+                self.connected_once = true;
+                self.failures = 0;
+                SessionEvent::Connected { reconnected }
+            }
+            Err(error) => {
+                warn!(target: "cryptomkt", "Cannot connect to the socket: {}", error);
+                self.failures += 1;
+                let terminal = self
+                    .options
+                    .max_reconnects
+                    .is_some_and(|max| self.failures >= max.max(1));
+                self.stopped = terminal;
+                SessionEvent::Disconnected { error, terminal }
+            }
+        }
+    }
+
+    async fn open(&self) -> CryptoMktResult<Connection> {
+        let auth = self.client.get_socket_auth().await?;
+        let mut connection = Connection::open(&self.options.url).await?;
+        connection
+            .emit("user-auth", &json!({"uid": auth.uid, "socid": auth.socid}))
+            .await?;
+        if let Some(channel) = &self.channel {
+            let credentials = self.client.api().sign_channel(channel);
+            connection
+                .emit("private-subscribe", &json!(credentials))
+                .await?;
+        }
+        for subscription in &self.subscriptions {
+            connection.emit("subscribe", &json!(subscription)).await?;
+        }
+        Ok(connection)
+    }
+}
@@ -0,0 +1,500 @@
+//!
+//! Streams en tiempo real sobre el socket del exchange
+//!
+//! Los eventos de mercado se reciben con la misma forma que las respuestas REST:
+//!
+//! - `ticker`: un `Ticker` o una lista de `Ticker`
+//! - `book`: `{"market": "ETHCLP", "type": "buy", "data": [Book, ...]}`
+//! - `trades`: `{"market": "ETHCLP", "data": [Trade, ...]}`
+//!
+//! Los demás eventos se entregan sin procesar como `MarketEvent::Other`
+//!
+//! El canal privado de la cuenta envía el estado completo de los saldos y órdenes
+//! que cambiaron, no la diferencia con el estado anterior:
+//!
+//! - `balance`: un `Balance` o una lista de `Balance`
+//! - `order`: una `Order` o una lista de `Order`
+//!
+
+use crate::async_client::AsyncCryptoMktClient;
+use crate::internal::dates::Timestamp;
+use crate::internal::errors::{CryptoMktError, CryptoMktResult};
+use crate::internal::models::{Balance, Book, Order, OrderType, Ticker, Trade};
+use crate::internal::socket::{SessionEvent, SocketOptions, SocketSession};
+use futures::stream::{self, Stream};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+
+///
+/// Evento del stream de mercados
+///
+#[derive(Debug)]
+pub enum MarketEvent {
+    /// Conexión establecida y suscripciones enviadas, también después de reconectar
+    Connected {
+        /// Indica si es una reconexión
+        reconnected: bool,
+    },
+    /// Ticker de un mercado
+    Ticker(Ticker),
+    /// Órdenes de un lado del libro
+    Book {
+        /// Mercado
+        market: String,
+        /// Lado del libro, compras o ventas
+        side: OrderType,
+        /// Órdenes del libro
+        entries: Vec<Book>,
+    },
+    /// Transacciones de un mercado
+    Trades {
+        /// Mercado
+        market: String,
+        /// Transacciones realizadas
+        trades: Vec<Trade>,
+    },
+    /// Evento no reconocido
+    Other {
+        /// Nombre del evento
+        event: String,
+        /// Datos del evento
+        data: Value,
+    },
+    /// Se perdió la conexión o falló un intento de conexión, es final si se
+    /// alcanzó `max_reconnects`
+    Disconnected {
+        /// Error de la conexión
+        error: CryptoMktError,
+        /// Indica si el stream termina
+        terminal: bool,
+    },
+}
+
+/// Datos del evento `book`
+#[derive(Deserialize)]
+struct BookPayload {
+    market: String,
+    #[serde(rename = "type")]
+    side: OrderType,
+    data: Vec<Book>,
+}
+
+/// Datos del evento `trades`
+#[derive(Deserialize)]
+struct TradesPayload {
+    market: String,
+    data: Vec<Trade>,
+}
+
+/// Datos de un evento que envía uno o varios elementos
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OneOrMany<T> {
+    One(T),
+    Many(Vec<T>),
+}
+
+impl<T> OneOrMany<T> {
+    fn into_vec(self) -> Vec<T> {
+        match self {
+            OneOrMany::One(item) => vec![item],
+            OneOrMany::Many(items) => items,
+        }
+    }
+}
+
+///
+/// Convierte un evento del socket en los eventos de mercado correspondientes
+///
+fn market_events(event: String, data: Value) -> Vec<MarketEvent> {
+    let parsed = match event.as_str() {
+        "ticker" => serde_json::from_value(data.clone()).map(|payload: OneOrMany<Ticker>| {
+            payload
+                .into_vec()
+                .into_iter()
+                .map(MarketEvent::Ticker)
+                .collect()
+        }),
+        "book" => serde_json::from_value(data.clone()).map(|payload: BookPayload| {
+            vec![MarketEvent::Book {
+                market: payload.market,
+                side: payload.side,
+                entries: payload.data,
+            }]
+        }),
+        "trades" => serde_json::from_value(data.clone()).map(|payload: TradesPayload| {
+            vec![MarketEvent::Trades {
+                market: payload.market,
+                trades: payload.data,
+            }]
+        }),
+        _ => return vec![MarketEvent::Other { event, data }],
+    };
+    match parsed {
+        Ok(events) => events,
+        Err(e) => {
+            warn!(target: "cryptomkt", "Invalid {} event: {}", event, e);
+            vec![MarketEvent::Other { event, data }]
+        }
+    }
+}
+
+///
+/// Stream de tickers, libros de órdenes y transacciones de los mercados suscritos
+///
+/// La conexión se abre con el primer evento solicitado, se autentica con las
+/// credenciales del cliente y, si se pierde, se reconecta con una espera creciente
+/// volviendo a suscribir los mercados
+///
+/// ```no_run
+/// extern crate cryptomkt;
+/// use cryptomkt::{AsyncCryptoMktClient, MarketEvent, MarketStream, SocketOptions};
+///
+/// # async fn run() {
+/// let client = AsyncCryptoMktClient::new("<API_KEY>", "<API SECRET>");
+/// let mut stream = MarketStream::new(client, SocketOptions::default());
+/// stream.subscribe("ETHCLP").await.unwrap();
+/// while let Some(event) = stream.next_event().await {
+///     match event {
+///         MarketEvent::Ticker(ticker) => println!("{} {}", ticker.market, ticker.last_price),
+///         MarketEvent::Trades { market, trades } => println!("{} {:?}", market, trades),
+///         other => println!("{:?}", other),
+///     }
+/// }
+/// # }
+/// ```
+///
+pub struct MarketStream {
+    session: SocketSession,
+    pending: VecDeque<MarketEvent>,
+}
+
+impl MarketStream {
+    ///
+    /// Crea el stream sin suscripciones, la conexión se abre con `next_event`
+    ///
+    pub fn new(client: AsyncCryptoMktClient, options: SocketOptions) -> Self {
+        MarketStream {
+            session: SocketSession::new(client, options),
+            pending: VecDeque::new(),
+        }
+    }
+
+    ///
+    /// Suscribe un mercado, la suscripción se mantiene en las reconexiones
+    ///
+    /// Argumentos
+    ///     market: Nombre del mercado (Ej. ETHCLP)
+    ///
+    pub async fn subscribe(&mut self, market: &str) -> CryptoMktResult<&mut Self> {
+        self.session.subscribe(market).await?;
+        Ok(self)
+    }
+
+    ///
+    /// Cancela la suscripción de un mercado
+    ///
+    pub async fn unsubscribe(&mut self, market: &str) -> CryptoMktResult<&mut Self> {
+        self.session.unsubscribe(market).await?;
+        Ok(self)
+    }
+
+    ///
+    /// Mercados suscritos
+    ///
+    pub fn subscriptions(&self) -> Vec<String> {
+        self.session.subscriptions()
+    }
+
+    ///
+    /// Espera el siguiente evento, `None` cuando se alcanzó `max_reconnects`
+    ///
+    pub async fn next_event(&mut self) -> Option<MarketEvent> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Some(event);
+            }
+            match self.session.next().await? {
+                SessionEvent::Connected { reconnected } => {
+                    return Some(MarketEvent::Connected { reconnected })
+                }
+                SessionEvent::Disconnected { error, terminal } => {
+                    return Some(MarketEvent::Disconnected { error, terminal })
+                }
+                SessionEvent::Message(event, data) => {
+                    self.pending.extend(market_events(event, data));
+                }
+            }
+        }
+    }
+
+    ///
+    /// Stream de los eventos, termina cuando se alcanzó `max_reconnects`
+    ///
+    pub fn into_stream(self) -> impl Stream<Item = MarketEvent> {
+        stream::unfold(self, |mut market_stream| async move {
+            let event = market_stream.next_event().await?;
+            Some((event, market_stream))
+        })
+    }
+}
+
+/// Canal privado de la cuenta
+const ACCOUNT_CHANNEL: &str = "account";
+
+///
+/// Saldos y órdenes activas obtenidos del API REST
+///
+#[derive(Debug, Clone)]
+pub struct AccountSnapshot {
+    /// Saldos de todas las billeteras
+    pub balances: Vec<Balance>,
+    /// Órdenes activas de los mercados seguidos
+    pub orders: Vec<Order>,
+}
+
+///
+/// Evento del stream de la cuenta
+///
+#[derive(Debug)]
+pub enum AccountEvent {
+    /// Conexión establecida y canal privado autenticado, también después de reconectar
+    Connected {
+        /// Indica si es una reconexión
+        reconnected: bool,
+    },
+    /// Estado completo de una billetera después de un cambio, no la diferencia
+    /// con el saldo anterior
+    BalanceUpdated(Balance),
+    /// Estado completo de una orden después de un cambio, no la diferencia con el
+    /// estado anterior
+    OrderUpdated(Order),
+    /// Estado completo obtenido del API REST después de reconectar, reemplaza los
+    /// saldos y órdenes conocidos porque los cambios durante la desconexión se perdieron
+    Snapshot(AccountSnapshot),
+    /// No se pudo obtener el estado completo después de reconectar
+    SnapshotFailed(CryptoMktError),
+    /// Evento no reconocido
+    Other {
+        /// Nombre del evento
+        event: String,
+        /// Datos del evento
+        data: Value,
+    },
+    /// Se perdió la conexión o falló un intento de conexión, es final si se
+    /// alcanzó `max_reconnects`
+    Disconnected {
+        /// Error de la conexión
+        error: CryptoMktError,
+        /// Indica si el stream termina
+        terminal: bool,
+    },
+}
+
+///
+/// Convierte un evento del canal privado en los eventos de la cuenta correspondientes
+///
+fn account_events(event: String, data: Value) -> Vec<AccountEvent> {
+    let parsed = match event.as_str() {
+        "balance" => serde_json::from_value(data.clone()).map(|payload: OneOrMany<Balance>| {
+            payload
+                .into_vec()
+                .into_iter()
+                .map(AccountEvent::BalanceUpdated)
+                .collect()
+        }),
+        "order" => serde_json::from_value(data.clone()).map(|payload: OneOrMany<Order>| {
+            payload
+                .into_vec()
+                .into_iter()
+                .map(AccountEvent::OrderUpdated)
+                .collect()
+        }),
+        _ => return vec![AccountEvent::Other { event, data }],
+    };
+    match parsed {
+        Ok(events) => events,
+        Err(e) => {
+            warn!(target: "cryptomkt", "Invalid {} event: {}", event, e);
+            vec![AccountEvent::Other { event, data }]
+        }
+    }
+}
+
+///
+/// Stream de los cambios de saldos y órdenes de la cuenta
+///
+/// Cada conexión autentica el canal privado con la firma del API key. Los cambios
+/// que ocurren mientras el socket está desconectado se pierden, por eso después de
+/// reconectar se entrega un `AccountEvent::Snapshot` con los saldos y las órdenes
+/// activas de los mercados seguidos, obtenidos del API REST
+///
+/// Las órdenes con un `updated_at` anterior al de la versión ya conocida (del último
+/// estado completo o de un evento previo) llegaron fuera de orden y se descartan.
+/// Los saldos no tienen fecha y se entregan en el orden en que llegan
+///
+/// ```no_run
+/// extern crate cryptomkt;
+/// use cryptomkt::{AccountEvent, AccountStream, AsyncCryptoMktClient, SocketOptions};
+///
+/// # async fn run() {
+/// let client = AsyncCryptoMktClient::new("<API_KEY>", "<API SECRET>");
+/// let mut stream = AccountStream::new(client, SocketOptions::default()).track_market("ETHCLP");
+/// let snapshot = stream.snapshot().await.unwrap();
+/// println!("{:?}", snapshot.balances);
+/// while let Some(event) = stream.next_event().await {
+///     match event {
+///         AccountEvent::BalanceUpdated(balance) => {
+///             println!("{} {}", balance.wallet, balance.available)
+///         }
+///         AccountEvent::OrderUpdated(order) => println!("{} {:?}", order.id, order.status),
+///         other => println!("{:?}", other),
+///     }
+/// }
+/// # }
+/// ```
+///
+pub struct AccountStream {
+    client: AsyncCryptoMktClient,
+    session: SocketSession,
+    markets: Vec<String>,
+    resync: bool,
+    pending: VecDeque<AccountEvent>,
+    /// Último `updated_at` conocido de cada orden
+    order_versions: HashMap<String, Timestamp>,
+}
+
+impl AccountStream {
+    ///
+    /// Crea el stream de la cuenta, la conexión se abre con `next_event`
+    ///
+    pub fn new(client: AsyncCryptoMktClient, options: SocketOptions) -> Self {
+        AccountStream {
+            session: SocketSession::new(client.clone(), options).private(ACCOUNT_CHANNEL),
+            client,
+            markets: Vec::new(),
+            resync: true,
+            pending: VecDeque::new(),
+            order_versions: HashMap::new(),
+        }
+    }
+
+    ///
+    /// Agrega un mercado cuyas órdenes activas se incluyen en el estado completo
+    ///
+    /// Argumentos
+    ///     market: Nombre del mercado (Ej. ETHCLP)
+    ///
+    pub fn track_market(mut self, market: &str) -> Self {
+        let market = market.trim().to_uppercase();
+        if !market.is_empty() && !self.markets.contains(&market) {
+            self.markets.push(market);
+        }
+        self
+    }
+
+    ///
+    /// Define si después de reconectar se obtiene el estado completo, activo por defecto
+    ///
+    pub fn resync_on_reconnect(mut self, resync: bool) -> Self {
+        self.resync = resync;
+        self
+    }
+
+    ///
+    /// Mercados seguidos
+    ///
+    pub fn markets(&self) -> Vec<String> {
+        self.markets.clone()
+    }
+
+    ///
+    /// Obtiene del API REST los saldos y las órdenes activas de los mercados seguidos,
+    /// los eventos de órdenes anteriores a este estado se descartan
+    ///
+    pub async fn snapshot(&mut self) -> CryptoMktResult<AccountSnapshot> {
+        let balances = self.client.get_balance().await?;
+        let mut orders = Vec::new();
+        for market in &self.markets {
+            orders.extend(
+                self.client
+                    .create_market(market)
+                    .active_orders(None)
+                    .await?,
+            );
+        }
+        for order in &orders {
+            self.record_order(order);
+        }
+        Ok(AccountSnapshot { balances, orders })
+    }
+
+    ///
+    /// Guarda la versión más reciente de la orden
+    ///
+    fn record_order(&mut self, order: &Order) {
+        if self.is_stale(order) {
+            return;
+        }
+        if let Some(updated_at) = order.updated_at.as_ref().cloned() {
+            self.order_versions.insert(order.id.clone(), updated_at);
+        }
+    }
+
+    ///
+    /// Indica si la orden es anterior a la versión conocida
+    ///
+    fn is_stale(&self, order: &Order) -> bool {
+        match (&order.updated_at, self.order_versions.get(&order.id)) {
+            (Some(updated_at), Some(known)) => updated_at < known,
+            _ => false,
+        }
+    }
+
+    ///
+    /// Espera el siguiente evento, `None` cuando se alcanzó `max_reconnects`
+    ///
+    pub async fn next_event(&mut self) -> Option<AccountEvent> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                if let AccountEvent::OrderUpdated(order) = &event {
+                    if self.is_stale(order) {
+                        debug!(target: "cryptomkt", "Dropped stale order event {}", order.id);
+                        continue;
+                    }
+                    self.record_order(order);
+                }
+                return Some(event);
+            }
+            match self.session.next().await? {
+                SessionEvent::Connected { reconnected } => {
+                    if reconnected && self.resync {
+                        let event = match self.snapshot().await {
+                            Ok(snapshot) => AccountEvent::Snapshot(snapshot),
+                            Err(e) => AccountEvent::SnapshotFailed(e),
+                        };
+                        self.pending.push_back(event);
+                    }
+                    return Some(AccountEvent::Connected { reconnected });
+                }
+                SessionEvent::Disconnected { error, terminal } => {
+                    return Some(AccountEvent::Disconnected { error, terminal })
+                }
+                SessionEvent::Message(event, data) => {
+                    self.pending.extend(account_events(event, data));
+                }
+            }
+        }
+    }
+
+    ///
+    /// Stream de los eventos, termina cuando se alcanzó `max_reconnects`
+    ///
+    pub fn into_stream(self) -> impl Stream<Item = AccountEvent> {
+        stream::unfold(self, |mut account_stream| async move {
+            let event = account_stream.next_event().await?;
+            Some((event, account_stream))
+        })
+    }
+}
@@ -0,0 +1,630 @@
+//!
+//! Seguimiento del ciclo de vida de las órdenes límite
+//!
+//! Compara las versiones consecutivas de cada orden para calcular la cantidad
+//! ejecutada desde la versión anterior (`Fill`) y detectar la ejecución completa o
+//! la cancelación. Las versiones se obtienen consultando `orders/executed` y
+//! `orders/status`, o se entregan con `update` desde otra fuente (Ej. un websocket)
+//!
+
+use crate::async_market::AsyncMarket;
+use crate::internal::errors::{CryptoMktError, CryptoMktErrorType, CryptoMktResult};
+use crate::internal::models::{Order, OrderState};
+use crate::internal::watcher::WatchOptions;
+#[cfg(feature = "blocking")]
+use crate::market::Market;
+use futures::stream::{self, Stream};
+use rust_decimal::Decimal;
+use std::collections::VecDeque;
+#[cfg(feature = "blocking")]
+use std::sync::mpsc::{channel, Receiver};
+#[cfg(feature = "blocking")]
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Cantidad de órdenes ejecutadas consultadas en cada ciclo
+const EXECUTED_PAGE_LIMIT: u32 = 100;
+
+///
+/// Cantidad ejecutada de una orden desde la versión anterior
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fill {
+    /// ID de la orden
+    pub order_id: String,
+    /// Cantidad ejecutada desde la versión anterior, en la moneda base
+    pub amount: Decimal,
+    /// Precio promedio de la cantidad ejecutada, si el exchange lo informa
+    pub price: Option<Decimal>,
+    /// Cantidad ejecutada en total
+    pub executed: Decimal,
+    /// Cantidad pendiente
+    pub remaining: Decimal,
+}
+
+///
+/// Cambio de una orden límite
+///
+#[derive(Debug)]
+pub enum OrderEvent {
+    /// Se ejecutó parte de la orden, la ejecución que completa la orden se
+    /// entrega antes de `Executed`
+    Fill {
+        /// Última versión de la orden
+        order: Order,
+        /// Cantidad ejecutada desde la versión anterior
+        fill: Fill,
+    },
+    /// La orden se ejecutó por completo, estado final
+    Executed(Order),
+    /// La orden se canceló, estado final
+    Cancelled(Order),
+    /// Falló la consulta de la orden, es final si se alcanzó `max_errors`
+    Error {
+        /// ID de la orden
+        id: String,
+        /// Error de la consulta
+        error: CryptoMktError,
+        /// Indica si se dejó de seguir la orden
+        terminal: bool,
+    },
+}
+
+impl OrderEvent {
+    ///
+    /// ID de la orden
+    ///
+    pub fn id(&self) -> String {
+        match self {
+            OrderEvent::Error { id, .. } => id.clone(),
+            _ => self
+                .order()
+                .map(|order| order.id.clone())
+                .unwrap_or_default(),
+        }
+    }
+
+    ///
+    /// Última versión de la orden, `None` para los errores
+    ///
+    pub fn order(&self) -> Option<&Order> {
+        match self {
+            OrderEvent::Fill { order, .. }
+            | OrderEvent::Executed(order)
+            | OrderEvent::Cancelled(order) => Some(order),
+            OrderEvent::Error { .. } => None,
+        }
+    }
+
+    ///
+    /// Indica si es el último evento de la orden
+    ///
+    pub fn is_terminal(&self) -> bool {
+        match self {
+            OrderEvent::Fill { .. } => false,
+            OrderEvent::Error { terminal, .. } => *terminal,
+            _ => true,
+        }
+    }
+}
+
+///
+/// Indica si el estado corresponde a una orden cancelada
+///
+pub(crate) fn is_cancelled(state: &OrderState) -> bool {
+    match state {
+        OrderState::Other(state) => state.to_lowercase().starts_with("cancel"),
+        _ => false,
+    }
+}
+
+///
+/// Cantidad ejecutada según la versión de la orden, las órdenes activas solo
+/// informan la cantidad pendiente y las ejecutadas solo la ejecutada
+///
+fn executed_amount(order: &Order, previous: Decimal) -> Decimal {
+    let amount = &order.amount;
+    let executed = amount.executed.filter(|executed| *executed > Decimal::ZERO);
+    let from_remaining = amount.remaining.map(|remaining| amount.original - remaining);
+    let executed = match order.status {
+        OrderState::Active => from_remaining,
+        OrderState::Executed => executed.or(Some(amount.original)),
+        _ => executed.or(from_remaining),
+    };
+    executed.unwrap_or(previous).max(previous)
+}
+
+///
+/// Orden seguida
+///
+struct Tracked {
+    id: String,
+    executed: Decimal,
+    avg_price: Decimal,
+    errors: u32,
+}
+
+///
+/// Estado del seguimiento, compartido por la versión bloqueante y la asíncrona
+///
+struct TrackState {
+    options: WatchOptions,
+    orders: Vec<Tracked>,
+    pending: VecDeque<OrderEvent>,
+    interval: Duration,
+    next_poll: Instant,
+    retry_after: Option<Duration>,
+}
+
+impl TrackState {
+    fn new(options: WatchOptions) -> Self {
+        TrackState {
+            options,
+            orders: Vec::new(),
+            pending: VecDeque::new(),
+            interval: options.initial_interval,
+            next_poll: Instant::now(),
+            retry_after: None,
+        }
+    }
+
+    fn track(&mut self, id: &str) {
+        let id = id.trim();
+        if !id.is_empty() && self.orders.iter().all(|order| order.id != id) {
+            self.orders.push(Tracked {
+                id: id.to_string(),
+                executed: Decimal::ZERO,
+                avg_price: Decimal::ZERO,
+                errors: 0,
+            });
+            self.next_poll = Instant::now();
+        }
+    }
+
+    fn track_order(&mut self, order: &Order) {
+        self.track(&order.id);
+        if let Some(tracked) = self
+            .orders
+            .iter_mut()
+            .find(|tracked| tracked.id == order.id)
+        {
+            tracked.executed = executed_amount(order, tracked.executed);
+            tracked.avg_price = order.avg_execution_price;
+        }
+    }
+
+    fn untrack(&mut self, id: &str) {
+        self.orders.retain(|order| order.id != id.trim());
+    }
+
+    fn tracked(&self) -> Vec<String> {
+        self.orders.iter().map(|order| order.id.clone()).collect()
+    }
+
+    ///
+    /// Compara la orden con su versión anterior, las órdenes no seguidas se ignoran
+    ///
+    fn apply(&mut self, order: Order) -> Vec<OrderEvent> {
+        let position = match self
+            .orders
+            .iter()
+            .position(|tracked| tracked.id == order.id)
+        {
+            Some(position) => position,
+            None => return Vec::new(),
+        };
+        let tracked = &mut self.orders[position];
+        tracked.errors = 0;
+
+        let mut events = Vec::new();
+        let executed = executed_amount(&order, tracked.executed);
+        let amount = executed - tracked.executed;
+        if amount > Decimal::ZERO {
+            let fill = Fill {
+                order_id: order.id.clone(),
+                amount,
+                price: fill_price(tracked, &order, executed, amount),
+                executed,
+                remaining: (order.amount.original - executed).max(Decimal::ZERO),
+            };
+            events.push(OrderEvent::Fill {
+                order: order.clone(),
+                fill,
+            });
+        }
+        tracked.executed = executed;
+        if order.avg_execution_price > Decimal::ZERO {
+            tracked.avg_price = order.avg_execution_price;
+        }
+
+        let filled = order.amount.original > Decimal::ZERO && executed >= order.amount.original;
+        if order.status == OrderState::Executed || (filled && order.status != OrderState::Active) {
+            events.push(OrderEvent::Executed(order));
+        } else if is_cancelled(&order.status) {
+            events.push(OrderEvent::Cancelled(order));
+        }
+        if events.last().is_some_and(OrderEvent::is_terminal) {
+            self.orders.remove(position);
+        }
+        events
+    }
+
+    ///
+    /// Registra el resultado de la consulta de una orden
+    ///
+    fn record(&mut self, id: &str, result: CryptoMktResult<Order>) -> Vec<OrderEvent> {
+        let error = match result {
+            Ok(order) => return self.apply(order),
+            Err(error) => error,
+        };
+        // Cuota local agotada: se reintenta en el siguiente ciclo sin contar el error
+        if error.kind() == CryptoMktErrorType::RateLimitExceeded {
+            self.delay(error.retry_after().unwrap_or(self.interval));
+            return Vec::new();
+        }
+        if let Some(retry_after) = error.retry_after() {
+            self.delay(retry_after);
+        }
+        let max_errors = self.options.max_errors;
+        let position = match self.orders.iter().position(|tracked| tracked.id == id) {
+            Some(position) => position,
+            None => return Vec::new(),
+        };
+        let tracked = &mut self.orders[position];
+        tracked.errors += 1;
+        let terminal = max_errors.is_some_and(|max| tracked.errors >= max);
+        if terminal {
+            self.orders.remove(position);
+        }
+        vec![OrderEvent::Error {
+            id: id.to_string(),
+            error,
+            terminal,
+        }]
+    }
+
+    fn delay(&mut self, wait: Duration) {
+        self.retry_after = Some(self.retry_after.map_or(wait, |current| current.max(wait)));
+    }
+
+    ///
+    /// Termina un ciclo de consultas: la espera vuelve al mínimo si hubo cambios
+    /// y crece en caso contrario
+    ///
+    fn finish_cycle(&mut self, events: Vec<OrderEvent>) {
+        self.interval = if events.iter().any(|event| event.order().is_some()) {
+            self.options.initial_interval
+        } else {
+            self.options.next_interval(self.interval)
+        };
+        let wait = self
+            .retry_after
+            .take()
+            .map_or(self.interval, |retry_after| self.interval.max(retry_after));
+        self.next_poll = Instant::now() + wait;
+        self.pending.extend(events);
+    }
+
+    ///
+    /// Evento pendiente o la espera hasta el siguiente ciclo, `None` cuando no
+    /// quedan eventos ni órdenes
+    ///
+    fn next(&mut self) -> Option<Result<OrderEvent, Duration>> {
+        if let Some(event) = self.pending.pop_front() {
+            return Some(Ok(event));
+        }
+        if self.orders.is_empty() {
+            return None;
+        }
+        Some(Err(self
+            .next_poll
+            .saturating_duration_since(Instant::now())))
+    }
+}
+
+///
+/// Precio promedio de la cantidad ejecutada, obtenido del precio promedio de la
+/// orden antes y después de la ejecución
+///
+fn fill_price(
+    tracked: &Tracked,
+    order: &Order,
+    executed: Decimal,
+    amount: Decimal,
+) -> Option<Decimal> {
+    let avg_price = order.avg_execution_price;
+    if avg_price > Decimal::ZERO
+        && (tracked.executed == Decimal::ZERO || tracked.avg_price > Decimal::ZERO)
+    {
+        let price = (avg_price * executed - tracked.avg_price * tracked.executed) / amount;
+        return Some(price.normalize());
+    }
+    order.execution_price.filter(|price| *price > Decimal::ZERO)
+}
+
+///
+/// Seguimiento bloqueante de las órdenes límite de un mercado
+///
+/// ```no_run
+/// extern crate cryptomkt;
+/// use cryptomkt::{CryptoMktClient, Decimal, OrderEvent, OrderTracker, OrderType, WatchOptions};
+///
+/// let client = CryptoMktClient::new("<API_KEY>", "<API SECRET>");
+/// let market = client.create_market("ETHCLP");
+/// let orders = market
+///     .create_order(OrderType::Buy, Decimal::new(5, 1), Decimal::new(250000, 0))
+///     .unwrap();
+///
+/// let mut tracker = OrderTracker::new(market, WatchOptions::default());
+/// tracker.track_order(&orders[0]);
+/// tracker.run(|event| match event {
+///     OrderEvent::Fill { fill, .. } => println!("filled {} at {:?}", fill.amount, fill.price),
+///     OrderEvent::Executed(order) => println!("executed {}", order.id),
+///     OrderEvent::Cancelled(order) => println!("cancelled {}", order.id),
+///     other => println!("{:?}", other),
+/// });
+/// ```
+///
+#[cfg(feature = "blocking")]
+pub struct OrderTracker {
+    market: Market,
+    state: TrackState,
+}
+
+#[cfg(feature = "blocking")]
+impl OrderTracker {
+    ///
+    /// Crea el seguimiento, las consultas usan el API del mercado y por lo tanto
+    /// respetan su `RateLimiter` y su política de reintentos
+    ///
+    pub fn new(market: Market, options: WatchOptions) -> Self {
+        OrderTracker {
+            market,
+            state: TrackState::new(options),
+        }
+    }
+
+    ///
+    /// Agrega una orden al seguimiento, las ejecuciones anteriores se informan con
+    /// la primera consulta
+    ///
+    pub fn track(&mut self, id: &str) -> &mut Self {
+        self.state.track(id);
+        self
+    }
+
+    ///
+    /// Agrega una orden al seguimiento tomando su versión actual (Ej. la respuesta de
+    /// `create_order`) como punto de partida
+    ///
+    pub fn track_order(&mut self, order: &Order) -> &mut Self {
+        self.state.track_order(order);
+        self
+    }
+
+    ///
+    /// Quita una orden del seguimiento
+    ///
+    pub fn untrack(&mut self, id: &str) -> &mut Self {
+        self.state.untrack(id);
+        self
+    }
+
+    ///
+    /// IDs de las órdenes que aún no llegan a un estado final
+    ///
+    pub fn tracked(&self) -> Vec<String> {
+        self.state.tracked()
+    }
+
+    ///
+    /// Aplica una versión de la orden recibida por otra fuente, devuelve los
+    /// eventos que genera
+    ///
+    pub fn update(&mut self, order: Order) -> Vec<OrderEvent> {
+        self.state.apply(order)
+    }
+
+    ///
+    /// Consulta las órdenes hasta que todas llegan a un estado final, llamando a
+    /// la función con cada evento
+    ///
+    pub fn run<F>(&mut self, mut on_event: F)
+    where
+        F: FnMut(OrderEvent),
+    {
+        while let Some(event) = self.next_event() {
+            on_event(event);
+        }
+    }
+
+    ///
+    /// Realiza el seguimiento en otro hilo y entrega los eventos por un canal, el hilo
+    /// termina cuando todas las órdenes llegan a un estado final o se descarta el canal
+    ///
+    pub fn spawn(mut self) -> (Receiver<OrderEvent>, JoinHandle<()>) {
+        let (sender, receiver) = channel();
+        let handle = thread::spawn(move || {
+            while let Some(event) = self.next_event() {
+                if sender.send(event).is_err() {
+                    break;
+                }
+            }
+        });
+        (receiver, handle)
+    }
+
+    ///
+    /// Espera el siguiente evento, `None` cuando no quedan órdenes
+    ///
+    pub fn next_event(&mut self) -> Option<OrderEvent> {
+        loop {
+            let wait = match self.state.next()? {
+                Ok(event) => return Some(event),
+                Err(wait) => wait,
+            };
+            if wait > Duration::from_millis(0) {
+                thread::sleep(wait);
+            }
+            self.poll();
+        }
+    }
+
+    fn poll(&mut self) {
+        let mut events = Vec::new();
+        match self
+            .market
+            .get_user_orders_by_state(OrderState::Executed, 0, EXECUTED_PAGE_LIMIT)
+        {
+            Ok(orders) => {
+                for order in orders {
+                    events.extend(self.state.apply(order));
+                }
+            }
+            Err(e) => warn!(target: "cryptomkt", "Cannot list executed orders: {}", e),
+        }
+        for id in self.state.tracked() {
+            let result = self.market.get_order_status(&id);
+            events.extend(self.state.record(&id, result));
+        }
+        self.state.finish_cycle(events);
+    }
+}
+
+///
+/// Seguimiento asíncrono de las órdenes límite de un mercado, ver `OrderTracker`
+///
+/// ```no_run
+/// extern crate cryptomkt;
+/// use cryptomkt::{AsyncCryptoMktClient, AsyncOrderTracker, WatchOptions};
+/// use futures::stream::StreamExt;
+///
+/// # async fn run() {
+/// let client = AsyncCryptoMktClient::new("<API_KEY>", "<API SECRET>");
+/// let mut tracker = AsyncOrderTracker::new(client.create_market("ETHCLP"), WatchOptions::default());
+/// tracker.track("O000001");
+/// let mut events = Box::pin(tracker.into_stream());
+/// while let Some(event) = events.next().await {
+///     println!("{:?}", event);
+/// }
+/// # }
+/// ```
+///
+pub struct AsyncOrderTracker {
+    market: AsyncMarket,
+    state: TrackState,
+}
+
+impl AsyncOrderTracker {
+    ///
+    /// Crea el seguimiento, las consultas usan el API del mercado y por lo tanto
+    /// respetan su `RateLimiter` y su política de reintentos
+    ///
+    pub fn new(market: AsyncMarket, options: WatchOptions) -> Self {
+        AsyncOrderTracker {
+            market,
+            state: TrackState::new(options),
+        }
+    }
+
+    ///
+    /// Agrega una orden al seguimiento, las ejecuciones anteriores se informan con
+    /// la primera consulta
+    ///
+    pub fn track(&mut self, id: &str) -> &mut Self {
+        self.state.track(id);
+        self
+    }
+
+    ///
+    /// Agrega una orden al seguimiento tomando su versión actual (Ej. la respuesta de
+    /// `create_order`) como punto de partida
+    ///
+    pub fn track_order(&mut self, order: &Order) -> &mut Self {
+        self.state.track_order(order);
+        self
+    }
+
+    ///
+    /// Quita una orden del seguimiento
+    ///
+    pub fn untrack(&mut self, id: &str) -> &mut Self {
+        self.state.untrack(id);
+        self
+    }
+
+    ///
+    /// IDs de las órdenes que aún no llegan a un estado final
+    ///
+    pub fn tracked(&self) -> Vec<String> {
+        self.state.tracked()
+    }
+
+    ///
+    /// Aplica una versión de la orden recibida por otra fuente, devuelve los
+    /// eventos que genera
+    ///
+    pub fn update(&mut self, order: Order) -> Vec<OrderEvent> {
+        self.state.apply(order)
+    }
+
+    ///
+    /// Consulta las órdenes hasta que todas llegan a un estado final, llamando a
+    /// la función con cada evento
+    ///
+    pub async fn run<F>(&mut self, mut on_event: F)
+    where
+        F: FnMut(OrderEvent),
+    {
+        while let Some(event) = self.next_event().await {
+            on_event(event);
+        }
+    }
+
+    ///
+    /// Stream de los eventos, termina cuando no quedan órdenes
+    ///
+    pub fn into_stream(self) -> impl Stream<Item = OrderEvent> {
+        stream::unfold(self, |mut tracker| async move {
+            let event = tracker.next_event().await?;
+            Some((event, tracker))
+        })
+    }
+
+    ///
+    /// Espera el siguiente evento, `None` cuando no quedan órdenes
+    ///
+    pub async fn next_event(&mut self) -> Option<OrderEvent> {
+        loop {
+            let wait = match self.state.next()? {
+                Ok(event) => return Some(event),
+                Err(wait) => wait,
+            };
+            if wait > Duration::from_millis(0) {
+                tokio::time::delay_for(wait).await;
+            }
+            self.poll().await;
+        }
+    }
+
+    async fn poll(&mut self) {
+        let mut events = Vec::new();
+        match self
+            .market
+            .get_user_orders_by_state(OrderState::Executed, 0, EXECUTED_PAGE_LIMIT)
+            .await
+        {
+            Ok(orders) => {
+                for order in orders {
+                    events.extend(self.state.apply(order));
+                }
+            }
+            Err(e) => warn!(target: "cryptomkt", "Cannot list executed orders: {}", e),
+        }
+        for id in self.state.tracked() {
+            let result = self.market.get_order_status(&id).await;
+            events.extend(self.state.record(&id, result));
+        }
+        self.state.finish_cycle(events);
+    }
+}
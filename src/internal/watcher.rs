@@ -0,0 +1,502 @@
+//!
+//! Seguimiento del ciclo de vida de las órdenes de pago
+//!
+//! Consulta `payment/status` de cada orden con una espera creciente mientras el
+//! estado no cambia, entrega un `PaymentEvent` en cada cambio de estado y deja de
+//! consultar la orden cuando llega a un estado final
+//!
+
+use crate::async_client::AsyncCryptoMktClient;
+#[cfg(feature = "blocking")]
+use crate::client::CryptoMktClient;
+use crate::internal::errors::{CryptoMktError, CryptoMktErrorType, CryptoMktResult};
+use crate::internal::models::{Payment, PaymentStatus};
+use futures::stream::{self, Stream};
+#[cfg(feature = "blocking")]
+use std::sync::mpsc::{channel, Receiver};
+#[cfg(feature = "blocking")]
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+///
+/// Opciones de las consultas de seguimiento
+///
+/// ```
+/// extern crate cryptomkt;
+/// use cryptomkt::WatchOptions;
+/// use std::time::Duration;
+///
+/// let options = WatchOptions::default()
+///     .initial_interval(Duration::from_secs(5))
+///     .max_interval(Duration::from_secs(120))
+///     .max_errors(10);
+/// ```
+///
+#[derive(Debug, Clone, Copy)]
+pub struct WatchOptions {
+    pub(crate) initial_interval: Duration,
+    max_interval: Duration,
+    backoff_factor: f64,
+    pub(crate) max_errors: Option<u32>,
+}
+
+impl Default for WatchOptions {
+    ///
+    /// Primera espera de 2s, multiplicada por 1.5 hasta un máximo de 60s, deja
+    /// de seguir una orden después de 5 errores consecutivos
+    ///
+    fn default() -> Self {
+        WatchOptions {
+            initial_interval: Duration::from_secs(2),
+            max_interval: Duration::from_secs(60),
+            backoff_factor: 1.5,
+            max_errors: Some(5),
+        }
+    }
+}
+
+impl WatchOptions {
+    ///
+    /// Espera entre consultas después de un cambio de estado
+    ///
+    pub fn initial_interval(mut self, interval: Duration) -> Self {
+        self.initial_interval = interval;
+        self
+    }
+
+    ///
+    /// Espera máxima entre dos consultas de la misma orden
+    ///
+    pub fn max_interval(mut self, interval: Duration) -> Self {
+        self.max_interval = interval;
+        self
+    }
+
+    ///
+    /// Factor aplicado a la espera cuando el estado no cambia o la consulta falla
+    /// (mínimo 1, espera constante)
+    ///
+    pub fn backoff_factor(mut self, factor: f64) -> Self {
+        self.backoff_factor = if factor.is_finite() {
+            factor.max(1.0)
+        } else {
+            1.0
+        };
+        self
+    }
+
+    ///
+    /// Cantidad de errores consecutivos tras la cual se deja de seguir una orden
+    ///
+    pub fn max_errors(mut self, max_errors: u32) -> Self {
+        self.max_errors = Some(max_errors.max(1));
+        self
+    }
+
+    ///
+    /// Sigue las órdenes sin importar la cantidad de errores
+    ///
+    pub fn unlimited_errors(mut self) -> Self {
+        self.max_errors = None;
+        self
+    }
+
+    pub(crate) fn next_interval(&self, interval: Duration) -> Duration {
+        interval
+            .mul_f64(self.backoff_factor)
+            .min(self.max_interval)
+            .max(self.initial_interval.min(self.max_interval))
+    }
+}
+
+///
+/// Cambio de estado de una orden de pago
+///
+#[derive(Debug)]
+pub enum PaymentEvent {
+    /// Esperando el pago, el bloque o el procesamiento (0, 1, 2)
+    Waiting(Payment),
+    /// Se pagó un monto distinto al de la orden (-3), estado final
+    Partial(Payment),
+    /// Pago exitoso (3), estado final
+    Paid(Payment),
+    /// La orden expiró sin recibir el pago (-1), estado final
+    Expired(Payment),
+    /// El pago se devuelve al `refund_email`: pagos múltiples (-4) o falló la
+    /// conversión (-2), estado final
+    Refunded(Payment),
+    /// Falló la consulta de la orden, es final si se alcanzó `max_errors`
+    Error {
+        /// ID de la orden de pago
+        id: String,
+        /// Error de la consulta
+        error: CryptoMktError,
+        /// Indica si se dejó de seguir la orden
+        terminal: bool,
+    },
+}
+
+impl PaymentEvent {
+    fn from_payment(payment: Payment) -> Self {
+        match payment.status {
+            PaymentStatus::Successful => PaymentEvent::Paid(payment),
+            PaymentStatus::Expired => PaymentEvent::Expired(payment),
+            PaymentStatus::AmountMismatch => PaymentEvent::Partial(payment),
+            PaymentStatus::MultiplePayments | PaymentStatus::ConversionFailed => {
+                PaymentEvent::Refunded(payment)
+            }
+            _ => PaymentEvent::Waiting(payment),
+        }
+    }
+
+    ///
+    /// ID de la orden de pago
+    ///
+    pub fn id(&self) -> String {
+        match self {
+            PaymentEvent::Error { id, .. } => id.clone(),
+            _ => self.payment().map(|p| p.id.to_string()).unwrap_or_default(),
+        }
+    }
+
+    ///
+    /// Orden de pago consultada, `None` para los errores
+    ///
+    pub fn payment(&self) -> Option<&Payment> {
+        match self {
+            PaymentEvent::Waiting(payment)
+            | PaymentEvent::Partial(payment)
+            | PaymentEvent::Paid(payment)
+            | PaymentEvent::Expired(payment)
+            | PaymentEvent::Refunded(payment) => Some(payment),
+            PaymentEvent::Error { .. } => None,
+        }
+    }
+
+    ///
+    /// Indica si es el último evento de la orden
+    ///
+    pub fn is_terminal(&self) -> bool {
+        match self {
+            PaymentEvent::Waiting(_) => false,
+            PaymentEvent::Error { terminal, .. } => *terminal,
+            _ => true,
+        }
+    }
+}
+
+///
+/// Orden seguida
+///
+struct Watched {
+    id: String,
+    status: Option<PaymentStatus>,
+    interval: Duration,
+    next_poll: Instant,
+    errors: u32,
+}
+
+///
+/// Estado del seguimiento, compartido por la versión bloqueante y la asíncrona
+///
+struct WatchState {
+    options: WatchOptions,
+    orders: Vec<Watched>,
+}
+
+impl WatchState {
+    fn new(options: WatchOptions) -> Self {
+        WatchState {
+            options,
+            orders: Vec::new(),
+        }
+    }
+
+    fn watch(&mut self, id: &str) {
+        let id = id.trim();
+        if !id.is_empty() && self.orders.iter().all(|order| order.id != id) {
+            self.orders.push(Watched {
+                id: id.to_string(),
+                status: None,
+                interval: self.options.initial_interval,
+                next_poll: Instant::now(),
+                errors: 0,
+            });
+        }
+    }
+
+    fn unwatch(&mut self, id: &str) {
+        self.orders.retain(|order| order.id != id.trim());
+    }
+
+    fn watched(&self) -> Vec<String> {
+        self.orders.iter().map(|order| order.id.clone()).collect()
+    }
+
+    ///
+    /// Orden que se debe consultar a continuación y la espera hasta su consulta
+    ///
+    fn next_due(&self) -> Option<(String, Duration)> {
+        let now = Instant::now();
+        self.orders
+            .iter()
+            .min_by_key(|order| order.next_poll)
+            .map(|order| {
+                (
+                    order.id.clone(),
+                    order.next_poll.saturating_duration_since(now),
+                )
+            })
+    }
+
+    ///
+    /// Registra el resultado de una consulta, devuelve el evento si cambió el estado
+    ///
+    fn record(&mut self, id: &str, result: CryptoMktResult<Payment>) -> Option<PaymentEvent> {
+        let options = self.options;
+        let position = self.orders.iter().position(|order| order.id == id)?;
+        let order = &mut self.orders[position];
+        let now = Instant::now();
+
+        let event = match result {
+            Ok(payment) => {
+                order.errors = 0;
+                if order.status.as_ref() == Some(&payment.status) {
+                    order.interval = options.next_interval(order.interval);
+                    order.next_poll = now + order.interval;
+                    return None;
+                }
+                order.status = Some(payment.status.clone());
+                order.interval = options.initial_interval;
+                PaymentEvent::from_payment(payment)
+            }
+            // Cuota local agotada: se reintenta sin contar el error
+            Err(ref e) if e.kind() == CryptoMktErrorType::RateLimitExceeded => {
+                order.next_poll = now + e.retry_after().unwrap_or(order.interval);
+                return None;
+            }
+            Err(error) => {
+                order.errors += 1;
+                order.interval = options.next_interval(order.interval);
+                let terminal = options.max_errors.is_some_and(|max| order.errors >= max);
+                if let Some(retry_after) = error.retry_after() {
+                    order.interval = order.interval.max(retry_after);
+                }
+                PaymentEvent::Error {
+                    id: id.to_string(),
+                    error,
+                    terminal,
+                }
+            }
+        };
+        order.next_poll = now + order.interval;
+        if event.is_terminal() {
+            self.orders.remove(position);
+        }
+        Some(event)
+    }
+}
+
+///
+/// Seguimiento bloqueante de un conjunto de órdenes de pago
+///
+/// ```no_run
+/// extern crate cryptomkt;
+/// use cryptomkt::{CryptoMktClient, PaymentEvent, PaymentWatcher, WatchOptions};
+///
+/// let client = CryptoMktClient::new("<API_KEY>", "<API SECRET>");
+/// let mut watcher = PaymentWatcher::new(client, WatchOptions::default());
+/// watcher.watch("P13565").watch("P13566");
+/// watcher.run(|event| match event {
+///     PaymentEvent::Paid(payment) => println!("paid {}", payment.external_id),
+///     PaymentEvent::Expired(payment) => println!("expired {}", payment.external_id),
+///     other => println!("{:?}", other),
+/// });
+/// ```
+///
+#[cfg(feature = "blocking")]
+pub struct PaymentWatcher {
+    client: CryptoMktClient,
+    state: WatchState,
+}
+
+#[cfg(feature = "blocking")]
+impl PaymentWatcher {
+    ///
+    /// Crea el seguimiento, las consultas usan el API del cliente y por lo tanto
+    /// respetan su `RateLimiter` y su política de reintentos
+    ///
+    pub fn new(client: CryptoMktClient, options: WatchOptions) -> Self {
+        PaymentWatcher {
+            client,
+            state: WatchState::new(options),
+        }
+    }
+
+    ///
+    /// Agrega una orden de pago al seguimiento
+    ///
+    pub fn watch(&mut self, id: &str) -> &mut Self {
+        self.state.watch(id);
+        self
+    }
+
+    ///
+    /// Quita una orden de pago del seguimiento
+    ///
+    pub fn unwatch(&mut self, id: &str) -> &mut Self {
+        self.state.unwatch(id);
+        self
+    }
+
+    ///
+    /// IDs de las órdenes que aún no llegan a un estado final
+    ///
+    pub fn watched(&self) -> Vec<String> {
+        self.state.watched()
+    }
+
+    ///
+    /// Consulta las órdenes hasta que todas llegan a un estado final, llamando
+    /// a la función con cada cambio de estado
+    ///
+    pub fn run<F>(&mut self, mut on_event: F)
+    where
+        F: FnMut(PaymentEvent),
+    {
+        while let Some(event) = self.next_event() {
+            on_event(event);
+        }
+    }
+
+    ///
+    /// Realiza el seguimiento en otro hilo y entrega los eventos por un canal, el hilo
+    /// termina cuando todas las órdenes llegan a un estado final o se descarta el canal
+    ///
+    pub fn spawn(mut self) -> (Receiver<PaymentEvent>, JoinHandle<()>) {
+        let (sender, receiver) = channel();
+        let handle = thread::spawn(move || {
+            while let Some(event) = self.next_event() {
+                if sender.send(event).is_err() {
+                    break;
+                }
+            }
+        });
+        (receiver, handle)
+    }
+
+    ///
+    /// Espera el siguiente cambio de estado, `None` cuando no quedan órdenes
+    ///
+    pub fn next_event(&mut self) -> Option<PaymentEvent> {
+        loop {
+            let (id, wait) = self.state.next_due()?;
+            if wait > Duration::from_millis(0) {
+                thread::sleep(wait);
+            }
+            let result = self.client.payment_order_status(&id);
+            if let Some(event) = self.state.record(&id, result) {
+                return Some(event);
+            }
+        }
+    }
+}
+
+///
+/// Seguimiento asíncrono de un conjunto de órdenes de pago, ver `PaymentWatcher`
+///
+/// ```no_run
+/// extern crate cryptomkt;
+/// use cryptomkt::{AsyncCryptoMktClient, AsyncPaymentWatcher, WatchOptions};
+/// use futures::stream::StreamExt;
+///
+/// # async fn run() {
+/// let client = AsyncCryptoMktClient::new("<API_KEY>", "<API SECRET>");
+/// let mut watcher = AsyncPaymentWatcher::new(client, WatchOptions::default());
+/// watcher.watch("P13565");
+/// let mut events = Box::pin(watcher.into_stream());
+/// while let Some(event) = events.next().await {
+///     println!("{:?}", event);
+/// }
+/// # }
+/// ```
+///
+pub struct AsyncPaymentWatcher {
+    client: AsyncCryptoMktClient,
+    state: WatchState,
+}
+
+impl AsyncPaymentWatcher {
+    ///
+    /// Crea el seguimiento, las consultas usan el API del cliente y por lo tanto
+    /// respetan su `RateLimiter` y su política de reintentos
+    ///
+    pub fn new(client: AsyncCryptoMktClient, options: WatchOptions) -> Self {
+        AsyncPaymentWatcher {
+            client,
+            state: WatchState::new(options),
+        }
+    }
+
+    ///
+    /// Agrega una orden de pago al seguimiento
+    ///
+    pub fn watch(&mut self, id: &str) -> &mut Self {
+        self.state.watch(id);
+        self
+    }
+
+    ///
+    /// Quita una orden de pago del seguimiento
+    ///
+    pub fn unwatch(&mut self, id: &str) -> &mut Self {
+        self.state.unwatch(id);
+        self
+    }
+
+    ///
+    /// IDs de las órdenes que aún no llegan a un estado final
+    ///
+    pub fn watched(&self) -> Vec<String> {
+        self.state.watched()
+    }
+
+    ///
+    /// Consulta las órdenes hasta que todas llegan a un estado final, llamando
+    /// a la función con cada cambio de estado
+    ///
+    pub async fn run<F>(&mut self, mut on_event: F)
+    where
+        F: FnMut(PaymentEvent),
+    {
+        while let Some(event) = self.next_event().await {
+            on_event(event);
+        }
+    }
+
+    ///
+    /// Stream de los cambios de estado, termina cuando no quedan órdenes
+    ///
+    pub fn into_stream(self) -> impl Stream<Item = PaymentEvent> {
+        stream::unfold(self, |mut watcher| async move {
+            let event = watcher.next_event().await?;
+            Some((event, watcher))
+        })
+    }
+
+    ///
+    /// Espera el siguiente cambio de estado, `None` cuando no quedan órdenes
+    ///
+    pub async fn next_event(&mut self) -> Option<PaymentEvent> {
+        loop {
+            let (id, wait) = self.state.next_due()?;
+            if wait > Duration::from_millis(0) {
+                tokio::time::delay_for(wait).await;
+            }
+            let result = self.client.payment_order_status(&id).await;
+            if let Some(event) = self.state.record(&id, result) {
+                return Some(event);
+            }
+        }
+    }
+}
@@ -0,0 +1,406 @@
+//!
+//! Notificaciones de las órdenes de pago enviadas por CryptoMarket al `callback_url`
+//!
+//! Cada cambio de estado de una orden se envía como un POST con los datos de la
+//! orden y el campo `signature`, que corresponde al HMAC-SHA384 en hexadecimal de
+//! `id + status` firmado con la llave secreta de la cuenta
+//!
+
+use crate::internal::api::{sign_message, verify_message};
+use crate::internal::dates::{timestamp, Timestamp};
+use crate::internal::errors::{CryptoMktError, CryptoMktErrorType, CryptoMktResult};
+use crate::internal::models::PaymentStatus;
+use rust_decimal::Decimal;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+///
+/// Notificación del cambio de estado de una orden de pago
+///
+/// ```
+/// extern crate cryptomkt;
+/// use cryptomkt::{PaymentNotification, PaymentStatus};
+///
+/// let body = "id=P13565&status=3&external_id=ORDER-123&to_receive=15000&to_receive_currency=CLP\
+///             &signature=00ff";
+/// let notification = PaymentNotification::parse(body).unwrap();
+/// assert_eq!(notification.status, PaymentStatus::Successful);
+///
+/// // La firma no corresponde a la llave secreta
+/// assert!(notification.verify("<API SECRET>").is_err());
+/// ```
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaymentNotification {
+    /// ID interno de la orden de pago
+    pub id: String,
+    /// ID externo de la orden
+    pub external_id: Option<String>,
+    /// Estado de la orden de pago
+    pub status: PaymentStatus,
+    /// Monto de la orden de pago
+    pub to_receive: Option<Decimal>,
+    /// Moneda del monto de la orden
+    pub to_receive_currency: Option<String>,
+    /// Monto esperado en la moneda de pago
+    pub expected_amount: Option<Decimal>,
+    /// Moneda de pago
+    pub expected_currency: Option<String>,
+    /// Dirección de depósito
+    pub deposit_address: Option<String>,
+    /// Email para coordinar devoluciones
+    pub refund_email: Option<String>,
+    /// URL del comprobante de pago
+    pub payment_url: Option<String>,
+    /// Fecha de creación de la orden
+    pub created_at: Option<Timestamp>,
+    /// Fecha de actualización de la orden
+    pub updated_at: Option<Timestamp>,
+    /// Firma de la notificación
+    pub signature: String,
+    /// Estado tal como se recibió, forma parte del mensaje firmado
+    raw_status: String,
+}
+
+impl PaymentNotification {
+    ///
+    /// Lee una notificación enviada como formulario (`application/x-www-form-urlencoded`)
+    /// o como JSON. No verifica la firma, ver `verify`
+    ///
+    /// Argumentos
+    ///     body: Cuerpo de la petición
+    ///
+    pub fn parse(body: &str) -> CryptoMktResult<Self> {
+        let fields = if body.trim_start().starts_with('{') {
+            let fields: HashMap<String, Value> = serde_json::from_str(body).map_err(malformed)?;
+            fields
+                .into_iter()
+                .filter_map(|(name, value)| match value {
+                    Value::String(value) => Some((name, value)),
+                    Value::Number(value) => Some((name, value.to_string())),
+                    _ => None,
+                })
+                .collect()
+        } else {
+            serde_urlencoded::from_str::<HashMap<String, String>>(body.trim()).map_err(malformed)?
+        };
+        Self::from_fields(fields)
+    }
+
+    ///
+    /// Lee una notificación y verifica su firma
+    ///
+    /// Argumentos
+    ///     body: Cuerpo de la petición
+    ///     secret_key: Llave secreta de la cuenta
+    ///
+    pub fn parse_verified(body: &str, secret_key: &str) -> CryptoMktResult<Self> {
+        let notification = Self::parse(body)?;
+        notification.verify(secret_key)?;
+        Ok(notification)
+    }
+
+    ///
+    /// Verifica la firma de la notificación, falla con `InvalidSignature`
+    ///
+    /// Argumentos
+    ///     secret_key: Llave secreta de la cuenta
+    ///
+    pub fn verify(&self, secret_key: &str) -> CryptoMktResult<()> {
+        if verify_message(secret_key, &self.signed_message(), &self.signature) {
+            Ok(())
+        } else {
+            Err(CryptoMktError::from(CryptoMktErrorType::InvalidSignature)
+                .with_message(&format!("invalid signature of payment order {}", self.id)))
+        }
+    }
+
+    ///
+    /// Calcula la firma esperada de la notificación
+    ///
+    /// Argumentos
+    ///     secret_key: Llave secreta de la cuenta
+    ///
+    pub fn expected_signature(&self, secret_key: &str) -> String {
+        sign_message(secret_key, &self.signed_message())
+    }
+
+    ///
+    /// Indica si la orden fue pagada
+    ///
+    pub fn is_paid(&self) -> bool {
+        self.status == PaymentStatus::Successful
+    }
+
+    ///
+    /// Indica si la orden expiró sin recibir el pago
+    ///
+    pub fn is_expired(&self) -> bool {
+        self.status == PaymentStatus::Expired
+    }
+
+    fn signed_message(&self) -> String {
+        format!("{}{}", self.id, self.raw_status)
+    }
+
+    fn from_fields(mut fields: HashMap<String, String>) -> CryptoMktResult<Self> {
+        let mut take = |name: &str| {
+            fields
+                .remove(name)
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+        let id = take("id").ok_or_else(|| missing("id"))?;
+        let raw_status = take("status").ok_or_else(|| missing("status"))?;
+        let signature = take("signature").ok_or_else(|| missing("signature"))?;
+        let to_receive = take("to_receive");
+        let expected_amount = take("expected_amount");
+        let created_at = take("created_at");
+        let updated_at = take("updated_at");
+
+        Ok(PaymentNotification {
+            status: PaymentStatus::from(raw_status.as_str()),
+            external_id: take("external_id"),
+            to_receive: parse_decimal("to_receive", to_receive)?,
+            to_receive_currency: take("to_receive_currency"),
+            expected_amount: parse_decimal("expected_amount", expected_amount)?,
+            expected_currency: take("expected_currency"),
+            deposit_address: take("deposit_address"),
+            refund_email: take("refund_email"),
+            payment_url: take("payment_url"),
+            created_at: parse_timestamp("created_at", created_at)?,
+            updated_at: parse_timestamp("updated_at", updated_at)?,
+            id,
+            signature,
+            raw_status,
+        })
+    }
+}
+
+fn malformed<E>(error: E) -> CryptoMktError
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    CryptoMktError::from(CryptoMktErrorType::MalformedResource)
+        .with_message("invalid payment notification")
+        .with_source(error)
+}
+
+fn missing(name: &str) -> CryptoMktError {
+    CryptoMktError::from(CryptoMktErrorType::MalformedResource)
+        .with_message(&format!("payment notification without {}", name))
+}
+
+fn invalid_field(name: &str, value: &str) -> CryptoMktError {
+    CryptoMktError::from(CryptoMktErrorType::MalformedResource).with_message(&format!(
+        "invalid {} in payment notification: {}",
+        name, value
+    ))
+}
+
+fn parse_decimal(name: &str, value: Option<String>) -> CryptoMktResult<Option<Decimal>> {
+    match value {
+        Some(value) => Decimal::from_str(&value)
+            .or_else(|_| Decimal::from_scientific(&value))
+            .map(Some)
+            .map_err(|_| invalid_field(name, &value)),
+        None => Ok(None),
+    }
+}
+
+fn parse_timestamp(name: &str, value: Option<String>) -> CryptoMktResult<Option<Timestamp>> {
+    match value {
+        Some(value) => {
+            timestamp::option(Value::String(value.clone())).map_err(|_| invalid_field(name, &value))
+        }
+        None => Ok(None),
+    }
+}
+
+#[cfg(feature = "webhook-server")]
+pub use self::server::PaymentWebhook;
+
+#[cfg(feature = "webhook-server")]
+mod server {
+    use super::PaymentNotification;
+    use crate::internal::errors::{CryptoMktError, CryptoMktErrorType, CryptoMktResult};
+    use hyper::body::HttpBody;
+    use hyper::header::CONTENT_LENGTH;
+    use hyper::service::{make_service_fn, service_fn};
+    use hyper::{Body, Method, Request, Response, Server, StatusCode};
+    use std::convert::Infallible;
+    use std::net::TcpListener;
+    use std::sync::Arc;
+
+    ///
+    /// Servidor HTTP embebido que recibe las notificaciones de pago
+    ///
+    /// Las notificaciones con una firma válida se entregan a la función indicada y se
+    /// responden con 200, las firmas inválidas con 401, los cuerpos inválidos con 400 y
+    /// los cuerpos que superan `max_body_size` con 413
+    ///
+    /// ```no_run
+    /// extern crate cryptomkt;
+    /// use cryptomkt::PaymentWebhook;
+    /// use std::net::TcpListener;
+    ///
+    /// # async fn run() {
+    /// let listener = TcpListener::bind("0.0.0.0:8080").unwrap();
+    /// PaymentWebhook::new("<API SECRET>")
+    ///     .serve(listener, |notification| {
+    ///         if notification.is_paid() {
+    ///             println!("paid: {:?}", notification.external_id);
+    ///         } else if notification.is_expired() {
+    ///             println!("expired: {:?}", notification.external_id);
+    ///         }
+    ///     })
+    ///     .await
+    ///     .unwrap();
+    /// # }
+    /// ```
+    ///
+    #[derive(Debug, Clone)]
+    pub struct PaymentWebhook {
+        secret_key: String,
+        max_body_size: usize,
+    }
+
+    impl PaymentWebhook {
+        ///
+        /// Tamaño máximo por defecto del cuerpo de una notificación, 64 KiB
+        ///
+        pub const DEFAULT_MAX_BODY_SIZE: usize = 64 * 1024;
+
+        ///
+        /// Crea el receptor de notificaciones
+        ///
+        /// Argumentos
+        ///     secret_key: Llave secreta de la cuenta, usada para verificar las firmas
+        ///
+        pub fn new(secret_key: &str) -> Self {
+            PaymentWebhook {
+                secret_key: secret_key.to_string(),
+                max_body_size: Self::DEFAULT_MAX_BODY_SIZE,
+            }
+        }
+
+        ///
+        /// Cambia el tamaño máximo en bytes del cuerpo de las notificaciones
+        /// (Por defecto: `DEFAULT_MAX_BODY_SIZE`)
+        ///
+        pub fn max_body_size(mut self, max_body_size: usize) -> Self {
+            self.max_body_size = max_body_size;
+            self
+        }
+
+        ///
+        /// Lee y verifica el cuerpo de una notificación
+        ///
+        pub fn handle(&self, body: &[u8]) -> CryptoMktResult<PaymentNotification> {
+            let body = String::from_utf8_lossy(body);
+            PaymentNotification::parse_verified(&body, &self.secret_key)
+        }
+
+        ///
+        /// Atiende las notificaciones recibidas en el listener hasta que se
+        /// cancela el futuro o falla el servidor
+        ///
+        /// Argumentos
+        ///     listener: Socket donde se reciben las notificaciones
+        ///     on_notification: Función llamada con cada notificación verificada
+        ///
+        pub async fn serve<F>(
+            self,
+            listener: TcpListener,
+            on_notification: F,
+        ) -> CryptoMktResult<()>
+        where
+            F: Fn(PaymentNotification) + Send + Sync + 'static,
+        {
+            let webhook = Arc::new(self);
+            let on_notification = Arc::new(on_notification);
+            let make_service = make_service_fn(move |_| {
+                let webhook = webhook.clone();
+                let on_notification = on_notification.clone();
+                async move {
+                    Ok::<_, Infallible>(service_fn(move |request| {
+                        let webhook = webhook.clone();
+                        let on_notification = on_notification.clone();
+                        async move {
+                            Ok::<_, Infallible>(
+                                webhook.respond(request, on_notification.as_ref()).await,
+                            )
+                        }
+                    }))
+                }
+            });
+            let server = Server::from_tcp(listener).map_err(server_error)?;
+            server.serve(make_service).await.map_err(server_error)
+        }
+
+        async fn respond<F>(&self, request: Request<Body>, on_notification: &F) -> Response<Body>
+        where
+            F: Fn(PaymentNotification),
+        {
+            if request.method() != Method::POST {
+                return status_response(StatusCode::METHOD_NOT_ALLOWED);
+            }
+            let content_length = request
+                .headers()
+                .get(CONTENT_LENGTH)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.parse::<u64>().ok())
+                .unwrap_or(0);
+            if content_length > self.max_body_size as u64 {
+                return status_response(StatusCode::PAYLOAD_TOO_LARGE);
+            }
+            let body = match read_body(request.into_body(), self.max_body_size).await {
+                Ok(body) => body,
+                Err(status) => return status_response(status),
+            };
+            match self.handle(&body) {
+                Ok(notification) => {
+                    on_notification(notification);
+                    status_response(StatusCode::OK)
+                }
+                Err(e) => {
+                    warn!(target: "cryptomkt", "Rejected payment notification: {}", e);
+                    if e.kind() == CryptoMktErrorType::InvalidSignature {
+                        status_response(StatusCode::UNAUTHORIZED)
+                    } else {
+                        status_response(StatusCode::BAD_REQUEST)
+                    }
+                }
+            }
+        }
+    }
+
+    ///
+    /// Lee el cuerpo por partes y corta la lectura en cuanto supera `limit`, también
+    /// cuando se envía sin `Content-Length` (`Transfer-Encoding: chunked`)
+    ///
+    async fn read_body(mut body: Body, limit: usize) -> Result<Vec<u8>, StatusCode> {
+        let mut buffer = Vec::new();
+        while let Some(chunk) = body.data().await {
+            let chunk = chunk.map_err(|_| StatusCode::BAD_REQUEST)?;
+            if buffer.len() + chunk.len() > limit {
+                return Err(StatusCode::PAYLOAD_TOO_LARGE);
+            }
+            buffer.extend_from_slice(&chunk);
+        }
+        Ok(buffer)
+    }
+
+    fn status_response(status: StatusCode) -> Response<Body> {
+        let mut response = Response::new(Body::empty());
+        *response.status_mut() = status;
+        response
+    }
+
+    fn server_error(error: hyper::Error) -> CryptoMktError {
+        CryptoMktError::from(CryptoMktErrorType::InvalidConfiguration)
+            .with_message("payment webhook server error")
+            .with_source(error)
+    }
+}
@@ -5,19 +5,27 @@
 //!
 //! ```
 //! extern crate cryptomkt;
+//! # #[cfg(feature = "blocking")]
+//! # mod example {
 //! use cryptomkt::{CryptoMktClient, OrderType};
 //!
 //! const API_KEY: &'static str = "<API_KEY>";
 //! const API_SECRET: &'static str = "<API SECRET>";
 //!
-//! fn main() {
+//! pub fn main() {
 //!
 //!     let client = CryptoMktClient::new(API_KEY, API_SECRET);
 //!
 //!     // Get the markets available in the exchange
-//!     let markets = client.get_markets();
+//!     let markets = match client.get_markets() {
+//!         Ok(markets) => markets,
+//!         Err(e) => {
+//!             println!("{}", e);
+//!             return;
+//!         }
+//!     };
 //!     for m in markets.iter() {
-//!         println!("{}", m.get_name());
+//!         println!("{} ({}/{})", m.get_name(), m.get_base_currency(), m.get_quote_currency());
 //!
 //!         // GET current Ticker
 //!         match m.get_current_ticker() {
@@ -30,18 +38,80 @@
 //!         }
 //!     }
 //! }
+//! # }
+//! # fn main() {
+//! #     #[cfg(feature = "blocking")]
+//! #     example::main();
+//! # }
 //! ```
+//!
+//! ## Async API
+//!
+//! `AsyncCryptoMktApi`, `AsyncCryptoMktClient` and `AsyncMarket` expose the same endpoints
+//! as `async fn`, they must be polled from a tokio runtime. The blocking API is enabled
+//! by the `blocking` feature (active by default), disable the default features to build
+//! only the async API.
+//!
+//! ## Timestamps
+//!
+//! With the `chrono` feature the model dates are `chrono::DateTime<Utc>` values (see
+//! `Timestamp`), and the date parameters of `get_trades` and `get_payment_orders` accept
+//! `DateTime` and `NaiveDate` values, formatted the way each endpoint expects.
 
 #[macro_use]
 extern crate log;
 
 mod api;
+mod async_api;
+mod async_client;
+mod async_market;
+#[cfg(feature = "blocking")]
 mod client;
 mod internal;
 mod market;
 
-pub use crate::api::{CryptoMktApi, RequestMethod};
+pub use crate::api::RequestMethod;
+#[cfg(feature = "blocking")]
+pub use crate::api::{CryptoMktApi, CryptoMktApiBuilder};
+pub use crate::async_api::{AsyncCryptoMktApi, AsyncCryptoMktApiBuilder};
+pub use crate::async_client::AsyncCryptoMktClient;
+pub use crate::async_market::AsyncMarket;
+#[cfg(feature = "blocking")]
 pub use crate::client::CryptoMktClient;
+pub use crate::internal::dates::{QueryDate, Timestamp};
+pub use crate::internal::errors::{CryptoMktError, CryptoMktErrorType, CryptoMktResult};
+pub use crate::internal::markets::{MarketInfo, MarketRegistry};
 pub use crate::internal::models;
+pub use crate::internal::models::{OrderState, OrderType, PaymentStatus};
+pub use crate::internal::order::{
+    CancelOutcome, OrderLimits, OrderRequest, OrderRequestBuilder, ReplaceOutcome,
+};
+pub use crate::internal::order_book::{BookFill, LevelChange, OrderBook, PriceLevel};
+pub use crate::internal::pagination::{Page, PageOptions};
+#[cfg(feature = "blocking")]
+pub use crate::internal::pagination::Paginator;
+pub use crate::internal::payment::{PaymentOrderRequest, PaymentOrderRequestBuilder};
+pub use crate::internal::rate_limit::{Quota, RateLimitMode, RateLimitScope, RateLimiter};
+pub use crate::internal::request;
+pub use crate::internal::request::{AsyncHttpRequest, HttpRequest};
 pub use crate::internal::response;
-pub use crate::market::{Market, OrderType};
+pub use crate::internal::retry::RetryPolicy;
+pub use crate::internal::slippage::{EstimateSource, SlippageEstimate};
+#[cfg(feature = "websocket")]
+pub use crate::internal::socket::{SocketOptions, SOCKET_URL};
+#[cfg(feature = "websocket")]
+pub use crate::internal::streams::{
+    AccountEvent, AccountSnapshot, AccountStream, MarketEvent, MarketStream,
+};
+#[cfg(feature = "blocking")]
+pub use crate::internal::tracker::OrderTracker;
+pub use crate::internal::tracker::{AsyncOrderTracker, Fill, OrderEvent};
+#[cfg(feature = "blocking")]
+pub use crate::internal::watcher::PaymentWatcher;
+pub use crate::internal::watcher::{AsyncPaymentWatcher, PaymentEvent, WatchOptions};
+pub use crate::internal::webhook::PaymentNotification;
+#[cfg(feature = "webhook-server")]
+pub use crate::internal::webhook::PaymentWebhook;
+pub use rust_decimal::Decimal;
+#[cfg(feature = "blocking")]
+pub use crate::market::Market;
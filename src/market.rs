@@ -1,34 +1,87 @@
+#[cfg(feature = "blocking")]
 use crate::api::{CryptoMktApi, RequestMethod};
-use crate::internal::errors::CryptoMktResult;
+#[cfg(feature = "blocking")]
+use crate::internal::dates::{QueryDate, TRADES_DATE_FORMAT};
+use crate::internal::errors::{CryptoMktError, CryptoMktErrorType, CryptoMktResult};
+#[cfg(feature = "blocking")]
+use crate::internal::markets::MarketInfo;
+#[cfg(feature = "blocking")]
 use crate::internal::models::{Book, Order, OrdersInstant, Ticker, Trade};
+#[cfg(feature = "blocking")]
+use crate::internal::order::{
+    check_replace, replace_amount, CancelOutcome, OrderLimits, OrderRequest, OrderRequestBuilder,
+    ReplaceOutcome,
+};
+#[cfg(feature = "blocking")]
+use crate::internal::order_book::{LevelChange, OrderBook};
+#[cfg(feature = "blocking")]
+use crate::internal::pagination::{Page, PageOptions, Paginator};
+#[cfg(feature = "blocking")]
+use crate::internal::slippage::{mid_price, SlippageEstimate};
+#[cfg(feature = "blocking")]
 use crate::internal::response::{
     BookResponse, EmptyResponse, OrderResponse, OrdersInstantResponse, SimpleOrderResponse,
     TickerResponse, TradeResponse,
 };
+use rust_decimal::Decimal;
+#[cfg(feature = "blocking")]
 use std::collections::HashMap;
-use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+pub use crate::internal::models::{OrderState, OrderType};
+
+///
+/// Quote currencies of the exchange, ordered from the longest code
+///
+const QUOTE_CURRENCIES: [&str; 8] = ["USDT", "CLP", "ARS", "BRL", "EUR", "MXN", "USD", "BTC"];
+
+///
+/// Split a market name (Ej ETHCLP) into its base and quote currencies (ETH, CLP)
+///
+/// Unknown quote currencies fall back to the last three letters of the name
+///
+pub(crate) fn split_market_name(market_name: &str) -> (String, String) {
+    let name = market_name.to_uppercase();
+    let quote_len = QUOTE_CURRENCIES
+        .iter()
+        .find(|quote| name.len() > quote.len() && name.ends_with(*quote))
+        .map(|quote| quote.len())
+        .unwrap_or_else(|| name.len().min(3));
+    let (base, quote) = name.split_at(name.len() - quote_len);
+    (base.to_string(), quote.to_string())
+}
 
 ///
-/// Order Type
+/// Convert an `f32` into a `Decimal` with the digits it is printed with, used by
+/// the deprecated `f32` entry points
 ///
-#[derive(Debug)]
-pub enum OrderType {
-    Buy,
-    Sell,
+pub(crate) fn decimal_from_f32(value: f32) -> CryptoMktResult<Decimal> {
+    Decimal::from_str(&value.to_string()).map_err(|e| {
+        CryptoMktError::from(CryptoMktErrorType::BadRequest)
+            .with_message(&format!("invalid amount {}", value))
+            .with_source(e)
+    })
 }
 
-impl Display for OrderType {
-    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-        write!(f, "{:?}", self)
+///
+/// Endpoint of the user orders in the given state
+///
+pub(crate) fn orders_endpoint(state: &OrderState) -> String {
+    match state {
+        OrderState::Active => "orders/active".to_string(),
+        OrderState::Executed => "orders/executed".to_string(),
+        OrderState::Other(state) => format!("orders/{}", state),
     }
 }
 
 ///
-/// Order State
+/// Whether the order is of the given side, every order when there is no side
 ///
-pub enum OrderState {
-    Active,
-    Executed,
+pub(crate) fn is_side(order: &crate::internal::models::Order, side: Option<&OrderType>) -> bool {
+    match side {
+        Some(side) => order.order_type == *side,
+        None => true,
+    }
 }
 
 ///
@@ -37,19 +90,26 @@ pub enum OrderState {
 /// Through this class you access the functionalities offered by the market,
 /// either create purchase order, choose the state of the market, etc ...
 ///
+#[cfg(feature = "blocking")]
+#[derive(Debug, Clone)]
 pub struct Market {
     api: CryptoMktApi,
     name: String,
+    info: MarketInfo,
+    limits: OrderLimits,
 }
 
+#[cfg(feature = "blocking")]
 impl Market {
     ///
     /// Create new instance
     ///
-    pub fn new<'m>(api: CryptoMktApi, market_name: &'m str) -> Self {
+    pub fn new(api: CryptoMktApi, market_name: &str) -> Self {
         Market {
-            api: api,
+            api,
             name: market_name.to_string(),
+            info: MarketInfo::new(market_name),
+            limits: OrderLimits::default(),
         }
     }
 
@@ -60,6 +120,54 @@ impl Market {
         self.name.clone()
     }
 
+    ///
+    /// Get the base currency of the market (Ej ETH in ETHCLP)
+    ///
+    pub fn get_base_currency(&self) -> String {
+        self.info.base_currency.clone()
+    }
+
+    ///
+    /// Get the quote currency of the market (Ej CLP in ETHCLP)
+    ///
+    pub fn get_quote_currency(&self) -> String {
+        self.info.quote_currency.clone()
+    }
+
+    ///
+    /// Get the metadata of the market: currencies, price tick, amount step and
+    /// minimum notional
+    ///
+    pub fn get_market_info(&self) -> MarketInfo {
+        self.info.clone()
+    }
+
+    ///
+    /// Set the metadata of the market, it replaces the order limits with the
+    /// limits of the market info
+    ///
+    pub fn with_market_info(mut self, info: MarketInfo) -> Self {
+        self.limits = info.order_limits();
+        self.info = info;
+        self
+    }
+
+    ///
+    /// Get the order limits of the market, checked before sending an order
+    ///
+    pub fn get_order_limits(&self) -> OrderLimits {
+        self.limits
+    }
+
+    ///
+    /// Set the order limits of the market (amount step, minimum amount, price
+    /// decimals and minimum notional)
+    ///
+    pub fn with_order_limits(mut self, limits: OrderLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
     ///
     /// Get the current ticker
     ///
@@ -70,7 +178,10 @@ impl Market {
             .api
             .call::<TickerResponse>(RequestMethod::Get(true), "ticker", params);
         match resp {
-            Ok(value) => Ok(value.data[0].clone()),
+            Ok(value) => value.data.into_iter().next().ok_or_else(|| {
+                CryptoMktError::from(CryptoMktErrorType::MalformedResource)
+                    .with_message(&format!("empty ticker for market {}", self.name))
+            }),
             Err(e) => Err(e),
         }
     }
@@ -84,9 +195,21 @@ impl Market {
         page: u32,
         limit: u32,
     ) -> CryptoMktResult<Vec<Book>> {
+        self.get_orders_book_page(orders_type, page, limit).map(|page| page.items)
+    }
+
+    ///
+    /// Get the order books, with the pagination metadata
+    ///
+    pub fn get_orders_book_page(
+        &self,
+        orders_type: OrderType,
+        page: u32,
+        limit: u32,
+    ) -> CryptoMktResult<Page<Book>> {
         let mut params = HashMap::new();
         params.insert("market".to_string(), self.name.clone());
-        params.insert("type".to_string(), orders_type.to_string().to_lowercase());
+        params.insert("type".to_string(), orders_type.to_string());
         params.insert("page".to_string(), format!("{}", page));
         params.insert("limit".to_string(), format!("{}", limit));
 
@@ -94,7 +217,7 @@ impl Market {
             .api
             .call::<BookResponse>(RequestMethod::Get(true), "book", params);
         match resp {
-            Ok(value) => Ok(value.data),
+            Ok(value) => Ok(Page::from(value)),
             Err(e) => Err(e),
         }
     }
@@ -102,17 +225,41 @@ impl Market {
     ///
     /// Get Trades
     ///
-    pub fn get_trades<'m>(
+    pub fn get_trades<S, E>(
+        &self,
+        start: &S,
+        end: &E,
+        page: u32,
+        limit: u32,
+    ) -> CryptoMktResult<Vec<Trade>>
+    where
+        S: QueryDate + ?Sized,
+        E: QueryDate + ?Sized,
+    {
+        self.get_trades_page(start, end, page, limit).map(|page| page.items)
+    }
+
+    ///
+    /// Get Trades, with the pagination metadata
+    ///
+    pub fn get_trades_page<S, E>(
         &self,
-        start: &'m str,
-        end: &'m str,
+        start: &S,
+        end: &E,
         page: u32,
         limit: u32,
-    ) -> CryptoMktResult<Vec<Trade>> {
+    ) -> CryptoMktResult<Page<Trade>>
+    where
+        S: QueryDate + ?Sized,
+        E: QueryDate + ?Sized,
+    {
         let mut params = HashMap::new();
         params.insert("market".to_string(), self.name.clone());
-        params.insert("start".to_string(), start.to_string());
-        params.insert("end".to_string(), end.to_string());
+        params.insert(
+            "start".to_string(),
+            start.to_query_date(TRADES_DATE_FORMAT)?,
+        );
+        params.insert("end".to_string(), end.to_query_date(TRADES_DATE_FORMAT)?);
         params.insert("page".to_string(), format!("{}", page));
         params.insert("limit".to_string(), format!("{}", limit));
 
@@ -120,7 +267,7 @@ impl Market {
             .api
             .call::<TradeResponse>(RequestMethod::Get(true), "trades", params);
         match resp {
-            Ok(value) => Ok(value.data),
+            Ok(value) => Ok(Page::from(value)),
             Err(e) => Err(e),
         }
     }
@@ -134,42 +281,182 @@ impl Market {
         page: u32,
         limit: u32,
     ) -> CryptoMktResult<Vec<Order>> {
+        self.get_user_orders_by_state_page(state, page, limit).map(|page| page.items)
+    }
+
+    ///
+    /// Get user orders by state, with the pagination metadata
+    ///
+    pub fn get_user_orders_by_state_page(
+        &self,
+        state: OrderState,
+        page: u32,
+        limit: u32,
+    ) -> CryptoMktResult<Page<Order>> {
         let mut params = HashMap::new();
         params.insert("market".to_string(), self.name.clone());
         params.insert("page".to_string(), format!("{}", page));
         params.insert("limit".to_string(), format!("{}", limit));
 
-        let endpoint = match state {
-            OrderState::Active => "orders/active",
-            OrderState::Executed => "orders/executed",
-        };
+        let endpoint = orders_endpoint(&state);
 
         let resp = self
             .api
-            .call::<OrderResponse>(RequestMethod::Get(false), endpoint, params);
+            .call::<OrderResponse>(RequestMethod::Get(false), &endpoint, params);
         match resp {
-            Ok(value) => Ok(value.data),
+            Ok(value) => Ok(Page::from(value)),
             Err(e) => Err(e),
         }
     }
+    ///
+    /// Get the order books of every page, as a lazy iterator
+    ///
+    pub fn iter_orders_book(
+        &self,
+        orders_type: OrderType,
+        options: PageOptions,
+    ) -> Paginator<Book> {
+        let mut params = HashMap::new();
+        params.insert("market".to_string(), self.name.clone());
+        params.insert("type".to_string(), orders_type.to_string());
+        let api = self.api.clone();
+        Paginator::new(options, move |page, limit| {
+            let mut params = params.clone();
+            params.insert("page".to_string(), format!("{}", page));
+            params.insert("limit".to_string(), format!("{}", limit));
+            let resp = api.call::<BookResponse>(RequestMethod::Get(true), "book", params);
+            match resp {
+                Ok(value) => Ok((value.data, value.pagination.next)),
+                Err(e) => Err(e),
+            }
+        })
+    }
+
+    ///
+    /// Get the trades between two dates of every page, as a lazy iterator
+    ///
+    pub fn iter_trades<S, E>(&self, start: &S, end: &E, options: PageOptions) -> Paginator<Trade>
+    where
+        S: QueryDate + ?Sized,
+        E: QueryDate + ?Sized,
+    {
+        let mut params = HashMap::new();
+        params.insert("market".to_string(), self.name.clone());
+        let mut error = None;
+        match (
+            start.to_query_date(TRADES_DATE_FORMAT),
+            end.to_query_date(TRADES_DATE_FORMAT),
+        ) {
+            (Ok(start), Ok(end)) => {
+                params.insert("start".to_string(), start);
+                params.insert("end".to_string(), end);
+            }
+            (Err(e), _) | (_, Err(e)) => error = Some(e),
+        }
+        let api = self.api.clone();
+        Paginator::new(options, move |page, limit| {
+            if let Some(e) = error.take() {
+                return Err(e);
+            }
+            let mut params = params.clone();
+            params.insert("page".to_string(), format!("{}", page));
+            params.insert("limit".to_string(), format!("{}", limit));
+            let resp = api.call::<TradeResponse>(RequestMethod::Get(true), "trades", params);
+            match resp {
+                Ok(value) => Ok((value.data, value.pagination.next)),
+                Err(e) => Err(e),
+            }
+        })
+    }
+
+    ///
+    /// Get the user orders by state of every page, as a lazy iterator
+    ///
+    pub fn iter_user_orders_by_state(
+        &self,
+        state: OrderState,
+        options: PageOptions,
+    ) -> Paginator<Order> {
+        let mut params = HashMap::new();
+        params.insert("market".to_string(), self.name.clone());
+        let endpoint = orders_endpoint(&state);
+        let api = self.api.clone();
+        Paginator::new(options, move |page, limit| {
+            let mut params = params.clone();
+            params.insert("page".to_string(), format!("{}", page));
+            params.insert("limit".to_string(), format!("{}", limit));
+            let resp = api.call::<OrderResponse>(RequestMethod::Get(false), &endpoint, params);
+            match resp {
+                Ok(value) => Ok((value.data, value.pagination.next)),
+                Err(e) => Err(e),
+            }
+        })
+    }
+
+    ///
+    /// Get both sides of the order book across every page, see
+    /// [`OrderBook`](struct.OrderBook.html)
+    ///
+    pub fn get_full_order_book(&self) -> CryptoMktResult<OrderBook> {
+        let bids = self
+            .iter_orders_book(OrderType::Buy, PageOptions::default())
+            .collect::<CryptoMktResult<Vec<Book>>>()?;
+        let asks = self
+            .iter_orders_book(OrderType::Sell, PageOptions::default())
+            .collect::<CryptoMktResult<Vec<Book>>>()?;
+        Ok(OrderBook::from_entries(&self.name, &bids, &asks))
+    }
+
+    ///
+    /// Fetch the full order book again and update `book`, returns the levels that changed
+    ///
+    pub fn refresh_order_book(&self, book: &mut OrderBook) -> CryptoMktResult<Vec<LevelChange>> {
+        Ok(book.refresh_from(self.get_full_order_book()?))
+    }
+
+    ///
+    /// Start a limit order with the limits of the market, see
+    /// [`OrderRequest`](struct.OrderRequest.html)
+    ///
+    pub fn order_request(
+        &self,
+        order_type: OrderType,
+        amount: Decimal,
+        price: Decimal,
+    ) -> OrderRequestBuilder {
+        OrderRequest::builder(order_type, amount, price)
+            .market(&self.name)
+            .limits(self.limits)
+    }
+
     ///
     /// Create order
     ///
+    /// The price is rounded to the price tick of the market (down for purchases, up
+    /// for sales) and the amount down to the amount step, then the order is checked
+    /// against the market limits before it is signed and sent
+    ///
     pub fn create_order(
         &self,
         order_type: OrderType,
-        amount: f32,
-        price: f32,
+        amount: Decimal,
+        price: Decimal,
     ) -> CryptoMktResult<Vec<Order>> {
-        let mut params = HashMap::new();
-        params.insert("market".to_string(), self.name.clone());
-        params.insert("amount".to_string(), format!("{}", amount));
-        params.insert("price".to_string(), format!("{}", price));
-        params.insert("type".to_string(), order_type.to_string().to_lowercase());
+        let amount = self.info.round_amount(amount);
+        let price = self.info.round_price(price, &order_type);
+        let request = self.order_request(order_type, amount, price).build()?;
+        self.submit_order(&request)
+    }
 
-        let resp = self
-            .api
-            .call::<OrderResponse>(RequestMethod::Post, "orders/create", params);
+    ///
+    /// Send a validated order
+    ///
+    pub fn submit_order(&self, request: &OrderRequest) -> CryptoMktResult<Vec<Order>> {
+        let resp = self.api.call::<OrderResponse>(
+            RequestMethod::Post,
+            "orders/create",
+            request.to_params(&self.name),
+        );
         match resp {
             Ok(value) => Ok(value.data),
             Err(e) => Err(e),
@@ -179,7 +466,7 @@ impl Market {
     ///
     /// Get Order status
     ///
-    pub fn get_order_status<'m>(&self, order_id: &'m str) -> CryptoMktResult<Order> {
+    pub fn get_order_status(&self, order_id: &str) -> CryptoMktResult<Order> {
         let mut params = HashMap::new();
         params.insert("id".to_string(), order_id.to_string());
 
@@ -197,7 +484,7 @@ impl Market {
     ///
     /// Cancel Order
     ///
-    pub fn cancel_order<'m>(&self, order_id: &'m str) -> CryptoMktResult<Order> {
+    pub fn cancel_order(&self, order_id: &str) -> CryptoMktResult<Order> {
         let mut params = HashMap::new();
         params.insert("id".to_string(), order_id.to_string());
 
@@ -210,6 +497,100 @@ impl Market {
         }
     }
 
+    ///
+    /// Cancel a limit order and place a new one at another price
+    ///
+    /// The order is cancelled and its state confirmed with `orders/status`; only when
+    /// the state is cancelled or executed the new order is placed, with the same side
+    /// and the amount not yet executed. An empty or unknown state confirms nothing.
+    /// Every step is reported in the outcome; when the cancelled order reports neither
+    /// its executed nor its remaining amount no order is placed and the placement holds
+    /// an `InvalidArgument` error. Fails without sending any request when the new price
+    /// or amount is not positive
+    ///
+    /// Arguments:
+    ///     order_id: Order to replace
+    ///     new_price: Price of the new order
+    ///     new_amount: Total amount of the new order, including the amount already
+    ///         executed by the cancelled order. `None` keeps the original amount
+    ///
+    pub fn replace_order(
+        &self,
+        order_id: &str,
+        new_price: Decimal,
+        new_amount: Option<Decimal>,
+    ) -> CryptoMktResult<ReplaceOutcome> {
+        check_replace(new_price, new_amount)?;
+        let cancel = self.cancel_order(order_id);
+        let confirmation = self.get_order_status(order_id);
+        let mut outcome = ReplaceOutcome {
+            order_id: order_id.to_string(),
+            cancel,
+            confirmation,
+            amount: None,
+            placement: None,
+        };
+        if !outcome.is_cancelled() {
+            return Ok(outcome);
+        }
+        if let Ok(cancelled) = &outcome.confirmation {
+            let order_type = cancelled.order_type.clone();
+            match replace_amount(cancelled, new_amount) {
+                Ok(amount) => {
+                    outcome.amount = Some(amount);
+                    if amount > Decimal::ZERO {
+                        outcome.placement =
+                            Some(self.create_order(order_type, amount, new_price));
+                    }
+                }
+                Err(e) => outcome.placement = Some(Err(e)),
+            }
+        }
+        Ok(outcome)
+    }
+
+    ///
+    /// Cancel every active order of the market, or only the purchases or the sales
+    ///
+    /// The active orders of every page are listed before cancelling them, and each
+    /// order gets its own outcome so a failed cancellation does not stop the rest.
+    /// Fails only when the active orders cannot be listed
+    ///
+    pub fn cancel_all_orders(
+        &self,
+        side: Option<OrderType>,
+    ) -> CryptoMktResult<Vec<CancelOutcome>> {
+        let orders = self.active_orders(side.as_ref())?;
+        Ok(self.cancel_orders(orders))
+    }
+
+    ///
+    /// Active orders of every page, of the given side
+    ///
+    pub(crate) fn active_orders(&self, side: Option<&OrderType>) -> CryptoMktResult<Vec<Order>> {
+        let orders = self
+            .iter_user_orders_by_state(OrderState::Active, PageOptions::default())
+            .collect::<CryptoMktResult<Vec<Order>>>()?;
+        Ok(orders
+            .into_iter()
+            .filter(|order| is_side(order, side))
+            .collect())
+    }
+
+    ///
+    /// Cancel the orders one by one
+    ///
+    pub(crate) fn cancel_orders(&self, orders: Vec<Order>) -> Vec<CancelOutcome> {
+        orders
+            .into_iter()
+            .map(|order| CancelOutcome {
+                result: self.cancel_order(&order.id),
+                order_id: order.id,
+                market: self.name.clone(),
+            })
+            .collect()
+    }
+
     ///
     /// Get order instant
     ///
@@ -219,12 +600,12 @@ impl Market {
     pub fn get_order_instant(
         &self,
         order_type: OrderType,
-        amount: f32,
+        amount: Decimal,
     ) -> CryptoMktResult<OrdersInstant> {
         let mut params = HashMap::new();
         params.insert("market".to_string(), self.name.clone());
-        params.insert("amount".to_string(), format!("{}", amount));
-        params.insert("type".to_string(), order_type.to_string().to_lowercase());
+        params.insert("amount".to_string(), amount.to_string());
+        params.insert("type".to_string(), order_type.to_string());
 
         let resp = self.api.call::<OrdersInstantResponse>(
             RequestMethod::Get(false),
@@ -240,15 +621,20 @@ impl Market {
     ///
     /// Create an instant order in the Instant Exchange of CryptoMarket
     ///
+    /// The amount is rounded down: purchases are given in the quote currency and
+    /// rounded to its decimals, sales are given in the base currency and rounded to
+    /// the amount step of the market
+    ///
     pub fn create_order_instant(
         &self,
         order_type: OrderType,
-        amount: f32,
+        amount: Decimal,
     ) -> CryptoMktResult<String> {
+        let amount = self.info.instant_amount(amount, &order_type)?;
         let mut params = HashMap::new();
         params.insert("market".to_string(), self.name.clone());
-        params.insert("amount".to_string(), format!("{}", amount));
-        params.insert("type".to_string(), order_type.to_string().to_lowercase());
+        params.insert("amount".to_string(), amount.to_string());
+        params.insert("type".to_string(), order_type.to_string());
 
         let resp =
             self.api
@@ -258,4 +644,92 @@ impl Market {
             Err(e) => Err(e),
         }
     }
+
+    ///
+    /// Estimate the execution of an instant order from the Instant Exchange quote,
+    /// against the mid price of the current ticker
+    ///
+    /// See [`SlippageEstimate`](struct.SlippageEstimate.html), or
+    /// [`SlippageEstimate::from_order_book`](struct.SlippageEstimate.html#method.from_order_book)
+    /// to estimate it from a full order book
+    ///
+    pub fn estimate_order_instant(
+        &self,
+        order_type: OrderType,
+        amount: Decimal,
+    ) -> CryptoMktResult<SlippageEstimate> {
+        let quote = self.get_order_instant(order_type.clone(), amount)?;
+        let ticker = self.get_current_ticker()?;
+        Ok(SlippageEstimate::from_quote(
+            order_type,
+            amount,
+            &quote,
+            mid_price(&ticker),
+        ))
+    }
+
+    ///
+    /// Create an instant order only if the estimated slippage versus the mid price is
+    /// at most `max_slippage` (Ej. 0.01 = 1%) and the market can fill the whole amount,
+    /// otherwise the order is not sent and a `SlippageExceeded` error is returned.
+    /// The amount is rounded as in `create_order_instant` before it is quoted
+    ///
+    pub fn create_order_instant_with_max_slippage(
+        &self,
+        order_type: OrderType,
+        amount: Decimal,
+        max_slippage: Decimal,
+    ) -> CryptoMktResult<String> {
+        let amount = self.info.instant_amount(amount, &order_type)?;
+        let estimate = self.estimate_order_instant(order_type.clone(), amount)?;
+        estimate.check(max_slippage)?;
+        self.create_order_instant(order_type, amount)
+    }
+
+    ///
+    /// Create order from `f32` values
+    ///
+    #[deprecated(since = "0.3.0", note = "use `create_order` with `Decimal` values")]
+    pub fn create_order_f32(
+        &self,
+        order_type: OrderType,
+        amount: f32,
+        price: f32,
+    ) -> CryptoMktResult<Vec<Order>> {
+        self.create_order(
+            order_type,
+            decimal_from_f32(amount)?,
+            decimal_from_f32(price)?,
+        )
+    }
+
+    ///
+    /// Get order instant from an `f32` amount
+    ///
+    #[deprecated(
+        since = "0.3.0",
+        note = "use `get_order_instant` with a `Decimal` amount"
+    )]
+    pub fn get_order_instant_f32(
+        &self,
+        order_type: OrderType,
+        amount: f32,
+    ) -> CryptoMktResult<OrdersInstant> {
+        self.get_order_instant(order_type, decimal_from_f32(amount)?)
+    }
+
+    ///
+    /// Create an instant order from an `f32` amount
+    ///
+    #[deprecated(
+        since = "0.3.0",
+        note = "use `create_order_instant` with a `Decimal` amount"
+    )]
+    pub fn create_order_instant_f32(
+        &self,
+        order_type: OrderType,
+        amount: f32,
+    ) -> CryptoMktResult<String> {
+        self.create_order_instant(order_type, decimal_from_f32(amount)?)
+    }
 }
@@ -3,6 +3,6 @@ use cryptomkt::CryptoMktClient;
 #[test]
 fn test_api_get_markets() {
     let api = CryptoMktClient::new("APK", "SK");
-    let markets = api.get_markets();
+    let markets = api.get_markets().unwrap();
     assert!(markets.len() > 1);
 }